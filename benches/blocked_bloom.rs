@@ -0,0 +1,87 @@
+use basalgo::set::filter::{BlockedBloomFilter, BloomFilter};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rand::Rng;
+
+const NUM_ITEMS: u64 = 10_000_000;
+const NUM_QUERIES: usize = 100_000;
+const FP_RATE: f64 = 0.01;
+
+/// Half of the queries hit inserted items, half miss
+fn queries() -> Vec<u64> {
+    let mut rng = rand::rng();
+
+    (0..NUM_QUERIES)
+        .map(|i| {
+            if i % 2 == 0 {
+                rng.random_range(0..NUM_ITEMS)
+            } else {
+                rng.random_range(NUM_ITEMS..2 * NUM_ITEMS)
+            }
+        })
+        .collect()
+}
+
+fn bench_contains(c: &mut Criterion) {
+    let queries = queries();
+
+    let mut standard = BloomFilter::with_capacity(NUM_ITEMS as usize, FP_RATE);
+    let mut blocked = BlockedBloomFilter::with_capacity(NUM_ITEMS as usize, FP_RATE);
+    for i in 0..NUM_ITEMS {
+        standard.insert(&i);
+        blocked.insert(&i);
+    }
+
+    let mut group = c.benchmark_group("bloom_layout_contains");
+    group.throughput(Throughput::Elements(NUM_QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("standard", NUM_ITEMS), |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter(|item| standard.contains(*item))
+                .count()
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("blocked", NUM_ITEMS), |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter(|item| blocked.contains(*item))
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let queries = queries();
+
+    let mut standard = BloomFilter::with_capacity(NUM_ITEMS as usize, FP_RATE);
+    let mut blocked = BlockedBloomFilter::with_capacity(NUM_ITEMS as usize, FP_RATE);
+
+    let mut group = c.benchmark_group("bloom_layout_insert");
+    group.throughput(Throughput::Elements(NUM_QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("standard", NUM_ITEMS), |b| {
+        b.iter(|| {
+            for item in &queries {
+                standard.insert(item);
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("blocked", NUM_ITEMS), |b| {
+        b.iter(|| {
+            for item in &queries {
+                blocked.insert(item);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_contains, bench_insert);
+criterion_main!(benches);
@@ -0,0 +1,119 @@
+use basalgo::hash::DefaultBuildHasher;
+use basalgo::set::filter::BloomFilter;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::hint::black_box;
+
+const NUM_BITS: usize = 1 << 20;
+const NUM_ITEMS: usize = 10_000;
+
+/// The probing scheme `BloomFilter` used before double hashing: every probe
+/// re-hashes the whole item with its index as a seed.
+struct SeededBloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+    hash_builder: DefaultBuildHasher,
+}
+
+impl SeededBloomFilter {
+    fn new(num_bits: usize, num_hashes: u32) -> Self {
+        Self {
+            bits: vec![0; num_bits.div_ceil(64)],
+            num_hashes,
+            hash_builder: DefaultBuildHasher::default(),
+        }
+    }
+
+    fn index<T: Hash>(&self, item: &T, seed: u64) -> usize {
+        let mut hasher = self.hash_builder.build_hasher();
+        item.hash(&mut hasher);
+        seed.hash(&mut hasher);
+        (hasher.finish() % (self.bits.len() as u64 * 64)) as usize
+    }
+
+    fn insert<T: Hash>(&mut self, item: &T) {
+        for seed in 0..self.num_hashes as u64 {
+            let index = self.index(item, seed);
+            self.bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    fn contains<T: Hash>(&self, item: &T) -> bool {
+        (0..self.num_hashes as u64).all(|seed| {
+            let index = self.index(item, seed);
+            self.bits[index / 64] & (1 << (index % 64)) != 0
+        })
+    }
+}
+
+fn items() -> Vec<String> {
+    (0..NUM_ITEMS)
+        .map(|i| format!("https://example.com/some/reasonably/long/path/{i}"))
+        .collect()
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let items = items();
+    let mut group = c.benchmark_group("bloom_insert");
+    group.throughput(Throughput::Elements(NUM_ITEMS as u64));
+
+    for num_hashes in [7, 10, 13, 16, 20] {
+        group.bench_with_input(
+            BenchmarkId::new("double_hashing", num_hashes),
+            &num_hashes,
+            |b, &num_hashes| {
+                b.iter(|| {
+                    let mut filter = BloomFilter::with_params(NUM_BITS, num_hashes);
+                    for item in &items {
+                        filter.insert(item);
+                    }
+                    black_box(filter)
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("seeded", num_hashes),
+            &num_hashes,
+            |b, &num_hashes| {
+                b.iter(|| {
+                    let mut filter = SeededBloomFilter::new(NUM_BITS, num_hashes);
+                    for item in &items {
+                        filter.insert(item);
+                    }
+                    black_box(filter.bits.len())
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_contains(c: &mut Criterion) {
+    let items = items();
+    let mut group = c.benchmark_group("bloom_contains");
+    group.throughput(Throughput::Elements(NUM_ITEMS as u64));
+
+    for num_hashes in [7, 10, 13, 16, 20] {
+        let mut filter = BloomFilter::with_params(NUM_BITS, num_hashes);
+        let mut seeded = SeededBloomFilter::new(NUM_BITS, num_hashes);
+        for item in &items {
+            filter.insert(item);
+            seeded.insert(item);
+        }
+
+        group.bench_function(BenchmarkId::new("double_hashing", num_hashes), |b| {
+            b.iter(|| items.iter().filter(|item| filter.contains(*item)).count())
+        });
+
+        group.bench_function(BenchmarkId::new("seeded", num_hashes), |b| {
+            b.iter(|| items.iter().filter(|item| seeded.contains(*item)).count())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert, bench_contains);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+use basalgo::set::filter::BloomFilter;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rand::Rng;
+
+const NUM_ITEMS: u64 = 10_000_000;
+const NUM_QUERIES: usize = 100_000;
+const FP_RATE: f64 = 0.01;
+
+/// Half of the queries hit inserted items, half miss
+fn queries() -> Vec<u64> {
+    let mut rng = rand::rng();
+
+    (0..NUM_QUERIES)
+        .map(|i| {
+            if i % 2 == 0 {
+                rng.random_range(0..NUM_ITEMS)
+            } else {
+                rng.random_range(NUM_ITEMS..2 * NUM_ITEMS)
+            }
+        })
+        .collect()
+}
+
+fn bench_contains(c: &mut Criterion) {
+    let queries = queries();
+
+    let mut filter = BloomFilter::with_capacity(NUM_ITEMS as usize, FP_RATE);
+    filter.insert_many(0..NUM_ITEMS);
+
+    let mut group = c.benchmark_group("bloom_batch_contains");
+    group.throughput(Throughput::Elements(NUM_QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("single", NUM_ITEMS), |b| {
+        b.iter(|| queries.iter().filter(|item| filter.contains(*item)).count())
+    });
+
+    group.bench_function(BenchmarkId::new("batch", NUM_ITEMS), |b| {
+        b.iter(|| {
+            filter
+                .contains_many(&queries)
+                .into_iter()
+                .filter(|&found| found)
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let queries = queries();
+
+    let mut single = BloomFilter::with_capacity(NUM_ITEMS as usize, FP_RATE);
+    let mut batch = BloomFilter::with_capacity(NUM_ITEMS as usize, FP_RATE);
+
+    let mut group = c.benchmark_group("bloom_batch_insert");
+    group.throughput(Throughput::Elements(NUM_QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("single", NUM_ITEMS), |b| {
+        b.iter(|| {
+            for item in &queries {
+                single.insert(item);
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("batch", NUM_ITEMS), |b| {
+        b.iter(|| batch.insert_many(&queries))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_contains, bench_insert);
+criterion_main!(benches);
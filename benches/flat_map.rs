@@ -0,0 +1,111 @@
+use basalgo::tree::{AvlTree, FlatMap};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+const SIZES: [u64; 3] = [100, 10_000, 1_000_000];
+const NUM_QUERIES: usize = 10_000;
+
+fn shuffled_keys(len: u64) -> Vec<u64> {
+    let mut keys: Vec<u64> = (0..len).collect();
+    keys.shuffle(&mut rand::rng());
+    keys
+}
+
+/// Half of the queries hit existing keys, half miss
+fn queries(len: u64) -> Vec<u64> {
+    let mut rng = rand::rng();
+
+    (0..NUM_QUERIES)
+        .map(|i| {
+            if i % 2 == 0 {
+                rng.random_range(0..len)
+            } else {
+                rng.random_range(len..2 * len)
+            }
+        })
+        .collect()
+}
+
+fn bench_get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flat_map_get");
+    group.throughput(Throughput::Elements(NUM_QUERIES as u64));
+
+    for len in SIZES {
+        let keys = shuffled_keys(len);
+        let queries = queries(len);
+        let flat: FlatMap<u64, u64> = keys.iter().map(|&key| (key, key)).collect();
+        let tree: AvlTree<u64, u64> = keys.iter().map(|&key| (key, key)).collect();
+
+        group.bench_function(BenchmarkId::new("flat_map", len), |b| {
+            b.iter(|| queries.iter().filter_map(|key| flat.get(key)).count())
+        });
+        group.bench_function(BenchmarkId::new("avl_tree", len), |b| {
+            b.iter(|| queries.iter().filter_map(|key| tree.get(key)).count())
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_iter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flat_map_iter");
+
+    for len in SIZES {
+        let keys = shuffled_keys(len);
+        let flat: FlatMap<u64, u64> = keys.iter().map(|&key| (key, key)).collect();
+        let tree: AvlTree<u64, u64> = keys.iter().map(|&key| (key, key)).collect();
+        group.throughput(Throughput::Elements(len));
+
+        group.bench_function(BenchmarkId::new("flat_map", len), |b| {
+            b.iter(|| flat.values().sum::<u64>())
+        });
+        group.bench_function(BenchmarkId::new("avl_tree", len), |b| {
+            b.iter(|| tree.values().sum::<u64>())
+        });
+    }
+
+    group.finish();
+}
+
+/// Building from shuffled keys: one insert at a time, or a single bulk
+/// `extend` for the flat map
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flat_map_build");
+
+    for len in SIZES {
+        let keys = shuffled_keys(len);
+        group.throughput(Throughput::Elements(len));
+
+        if len <= 10_000 {
+            group.bench_function(BenchmarkId::new("flat_map_insert", len), |b| {
+                b.iter(|| {
+                    let mut map = FlatMap::new();
+                    for &key in &keys {
+                        map.insert(key, key);
+                    }
+                    map
+                })
+            });
+        }
+        group.bench_function(BenchmarkId::new("flat_map_extend", len), |b| {
+            b.iter(|| {
+                keys.iter()
+                    .map(|&key| (key, key))
+                    .collect::<FlatMap<_, _>>()
+            })
+        });
+        group.bench_function(BenchmarkId::new("avl_tree_insert", len), |b| {
+            b.iter(|| {
+                keys.iter()
+                    .map(|&key| (key, key))
+                    .collect::<AvlTree<_, _>>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_get, bench_iter, bench_build);
+criterion_main!(benches);
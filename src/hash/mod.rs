@@ -0,0 +1,54 @@
+//! Hashers whose output is fixed by their specification.
+//!
+//! The standard library's `DefaultHasher` may change between Rust releases,
+//! which makes anything derived from it (filter bit arrays, sketches)
+//! impossible to persist or exchange between processes. The hashers in this
+//! module also feed integers in little-endian order, so results agree across
+//! platforms as long as the hashed type's `Hash` implementation does.
+
+use std::hash::{BuildHasher, Hash, Hasher};
+
+mod murmur;
+mod sip;
+mod xxhash;
+
+pub use murmur::{BuildMurmur3Hasher, Murmur3Hasher};
+pub use sip::{BuildSipHasher24, SipHasher24};
+pub use xxhash::{BuildXxHash64, XxHash64};
+
+/// Hasher used by the structures in this crate when none is specified.
+pub type DefaultBuildHasher = BuildXxHash64;
+
+/// Hashes `item` once and derives a 128-bit digest from the hasher state.
+///
+/// The second half is produced by extending the already consumed input with
+/// a single marker byte, so the item itself is only fed to the hasher once.
+pub(crate) fn hash128<T: Hash + ?Sized, S: BuildHasher>(hash_builder: &S, item: &T) -> (u64, u64) {
+    let mut hasher = hash_builder.build_hasher();
+    item.hash(&mut hasher);
+    let h1 = hasher.finish();
+    hasher.write_u8(0xa5);
+    let h2 = hasher.finish();
+
+    (h1, h2)
+}
+
+/// MurmurHash3's 64-bit finalizer, which mixes every input bit into every
+/// output bit.
+pub(crate) fn murmur64(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^= hash >> 33;
+    hash
+}
+
+/// Advances `state` and returns the next output of the SplitMix64 generator.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,247 @@
+use std::hash::{BuildHasher, Hasher};
+
+const C1: u64 = 0x87c3_7b91_1142_53d5;
+const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+const BLOCK_LEN: usize = 16;
+
+/// Streaming MurmurHash3_x64_128.
+///
+/// [`Hasher::finish`] returns the first half of the digest, the full 128-bit
+/// value is available through [`Murmur3Hasher::finish128`].
+#[derive(Clone, Debug)]
+pub struct Murmur3Hasher {
+    seed: u32,
+    h1: u64,
+    h2: u64,
+    buffer: [u8; BLOCK_LEN],
+    buffer_len: usize,
+    total_len: u64,
+}
+
+impl Murmur3Hasher {
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u32) -> Self {
+        Self {
+            seed,
+            h1: seed as u64,
+            h2: seed as u64,
+            buffer: [0; BLOCK_LEN],
+            buffer_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn finish128(&self) -> (u64, u64) {
+        let (mut h1, mut h2) = (self.h1, self.h2);
+
+        let tail = &self.buffer[..self.buffer_len];
+        let mut k1 = 0u64;
+        let mut k2 = 0u64;
+
+        for (i, &byte) in tail.iter().enumerate() {
+            if i < 8 {
+                k1 |= (byte as u64) << (8 * i);
+            } else {
+                k2 |= (byte as u64) << (8 * (i - 8));
+            }
+        }
+
+        if tail.len() > 8 {
+            h2 ^= Self::mix_k2(k2);
+        }
+        if !tail.is_empty() {
+            h1 ^= Self::mix_k1(k1);
+        }
+
+        h1 ^= self.total_len;
+        h2 ^= self.total_len;
+
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+
+        h1 = Self::fmix(h1);
+        h2 = Self::fmix(h2);
+
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+
+        (h1, h2)
+    }
+
+    fn mix_k1(k1: u64) -> u64 {
+        k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2)
+    }
+
+    fn mix_k2(k2: u64) -> u64 {
+        k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1)
+    }
+
+    fn fmix(mut k: u64) -> u64 {
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        k ^= k >> 33;
+        k
+    }
+
+    fn process_block(&mut self, block: &[u8]) {
+        let k1 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        self.h1 ^= Self::mix_k1(k1);
+        self.h1 = self
+            .h1
+            .rotate_left(27)
+            .wrapping_add(self.h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+
+        self.h2 ^= Self::mix_k2(k2);
+        self.h2 = self
+            .h2
+            .rotate_left(31)
+            .wrapping_add(self.h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+}
+
+impl Default for Murmur3Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for Murmur3Hasher {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.total_len += bytes.len() as u64;
+
+        if self.buffer_len != 0 {
+            let take = (BLOCK_LEN - self.buffer_len).min(bytes.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&bytes[..take]);
+            self.buffer_len += take;
+            bytes = &bytes[take..];
+
+            if self.buffer_len < BLOCK_LEN {
+                return;
+            }
+
+            let block = self.buffer;
+            self.process_block(&block);
+            self.buffer_len = 0;
+        }
+
+        let mut blocks = bytes.chunks_exact(BLOCK_LEN);
+        for block in &mut blocks {
+            self.process_block(block);
+        }
+
+        let remainder = blocks.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffer_len = remainder.len();
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.finish128().0
+    }
+}
+
+/// Builds [`Murmur3Hasher`] instances sharing the same seed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuildMurmur3Hasher {
+    seed: u32,
+}
+
+impl BuildMurmur3Hasher {
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u32) -> Self {
+        Self { seed }
+    }
+}
+
+impl BuildHasher for BuildMurmur3Hasher {
+    type Hasher = Murmur3Hasher;
+
+    fn build_hasher(&self) -> Murmur3Hasher {
+        Murmur3Hasher::with_seed(self.seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn murmur3(bytes: &[u8], seed: u32) -> (u64, u64) {
+        let mut hasher = Murmur3Hasher::with_seed(seed);
+        hasher.write(bytes);
+        hasher.finish128()
+    }
+
+    #[test]
+    fn test_reference_vectors() {
+        assert_eq!(murmur3(b"", 0), (0, 0));
+        assert_eq!(
+            murmur3(b"hello", 0),
+            (0xcbd8_a7b3_41bd_9b02, 0x5b1e_906a_48ae_1d19)
+        );
+        assert_eq!(
+            murmur3(b"The quick brown fox jumps over the lazy dog", 0),
+            (0xe34b_bc7b_bc07_1b6c, 0x7a43_3ca9_c49a_9347)
+        );
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let message: Vec<u8> = (0..100).collect();
+
+        for split in 0..message.len() {
+            let mut hasher = Murmur3Hasher::with_seed(7);
+            hasher.write(&message[..split]);
+            hasher.write(&message[split..]);
+            assert_eq!(hasher.finish128(), murmur3(&message, 7));
+        }
+    }
+
+    #[test]
+    fn test_finish_is_first_half() {
+        let mut hasher = BuildMurmur3Hasher::with_seed(3).build_hasher();
+        hasher.write(b"basalgo");
+        assert_eq!(hasher.finish(), hasher.finish128().0);
+    }
+}
@@ -0,0 +1,242 @@
+use std::hash::{BuildHasher, Hasher};
+
+const DEFAULT_K0: u64 = 0x0706_0504_0302_0100;
+const DEFAULT_K1: u64 = 0x0f0e_0d0c_0b0a_0908;
+
+/// SipHash-2-4 with caller-provided (or fixed default) keys.
+///
+/// Unlike `std::collections::hash_map::DefaultHasher`, the output of this
+/// hasher is fixed by the SipHash specification and does not change between
+/// Rust releases.
+#[derive(Clone, Debug)]
+pub struct SipHasher24 {
+    k0: u64,
+    k1: u64,
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    tail: u64,
+    tail_len: usize,
+    length: usize,
+}
+
+impl SipHasher24 {
+    pub fn new() -> Self {
+        Self::new_with_keys(DEFAULT_K0, DEFAULT_K1)
+    }
+
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        Self {
+            k0,
+            k1,
+            v0: k0 ^ 0x736f_6d65_7073_6575,
+            v1: k1 ^ 0x646f_7261_6e64_6f6d,
+            v2: k0 ^ 0x6c79_6765_6e65_7261,
+            v3: k1 ^ 0x7465_6462_7974_6573,
+            tail: 0,
+            tail_len: 0,
+            length: 0,
+        }
+    }
+
+    pub fn keys(&self) -> (u64, u64) {
+        (self.k0, self.k1)
+    }
+
+    fn compress(v0: &mut u64, v1: &mut u64, v2: &mut u64, v3: &mut u64) {
+        *v0 = v0.wrapping_add(*v1);
+        *v1 = v1.rotate_left(13);
+        *v1 ^= *v0;
+        *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3);
+        *v3 = v3.rotate_left(16);
+        *v3 ^= *v2;
+        *v0 = v0.wrapping_add(*v3);
+        *v3 = v3.rotate_left(21);
+        *v3 ^= *v0;
+        *v2 = v2.wrapping_add(*v1);
+        *v1 = v1.rotate_left(17);
+        *v1 ^= *v2;
+        *v2 = v2.rotate_left(32);
+    }
+
+    fn process_block(&mut self, block: u64) {
+        self.v3 ^= block;
+        for _ in 0..2 {
+            Self::compress(&mut self.v0, &mut self.v1, &mut self.v2, &mut self.v3);
+        }
+        self.v0 ^= block;
+    }
+}
+
+impl Default for SipHasher24 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for SipHasher24 {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.length += bytes.len();
+
+        if self.tail_len != 0 {
+            let needed = 8 - self.tail_len;
+            let take = needed.min(bytes.len());
+            for (i, &byte) in bytes[..take].iter().enumerate() {
+                self.tail |= (byte as u64) << (8 * (self.tail_len + i));
+            }
+            self.tail_len += take;
+            bytes = &bytes[take..];
+
+            if self.tail_len < 8 {
+                return;
+            }
+
+            let block = self.tail;
+            self.process_block(block);
+            self.tail = 0;
+            self.tail_len = 0;
+        }
+
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.process_block(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        for (i, &byte) in chunks.remainder().iter().enumerate() {
+            self.tail |= (byte as u64) << (8 * i);
+        }
+        self.tail_len = chunks.remainder().len();
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        let (mut v0, mut v1, mut v2, mut v3) = (self.v0, self.v1, self.v2, self.v3);
+
+        let block = ((self.length as u64 & 0xff) << 56) | self.tail;
+
+        v3 ^= block;
+        for _ in 0..2 {
+            Self::compress(&mut v0, &mut v1, &mut v2, &mut v3);
+        }
+        v0 ^= block;
+
+        v2 ^= 0xff;
+        for _ in 0..4 {
+            Self::compress(&mut v0, &mut v1, &mut v2, &mut v3);
+        }
+
+        v0 ^ v1 ^ v2 ^ v3
+    }
+}
+
+/// Builds [`SipHasher24`] instances sharing the same pair of keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildSipHasher24 {
+    k0: u64,
+    k1: u64,
+}
+
+impl BuildSipHasher24 {
+    pub fn new() -> Self {
+        Self::with_keys(DEFAULT_K0, DEFAULT_K1)
+    }
+
+    pub fn with_keys(k0: u64, k1: u64) -> Self {
+        Self { k0, k1 }
+    }
+}
+
+impl Default for BuildSipHasher24 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildHasher for BuildSipHasher24 {
+    type Hasher = SipHasher24;
+
+    fn build_hasher(&self) -> SipHasher24 {
+        SipHasher24::new_with_keys(self.k0, self.k1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sip(bytes: &[u8]) -> u64 {
+        let mut hasher = SipHasher24::new();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_reference_vectors() {
+        // Vectors from the SipHash paper, key = 00 01 .. 0f, message = 00 01 .. (len - 1)
+        let message: Vec<u8> = (0..64).collect();
+
+        assert_eq!(sip(&message[..0]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(sip(&message[..1]), 0x74f8_39c5_93dc_67fd);
+        assert_eq!(sip(&message[..8]), 0x93f5_f579_9a93_2462);
+        assert_eq!(sip(&message[..15]), 0xa129_ca61_49be_45e5);
+        assert_eq!(sip(&message[..63]), 0x958a_324c_eb06_4572);
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let message: Vec<u8> = (0..100).collect();
+
+        for split in 0..message.len() {
+            let mut hasher = SipHasher24::new();
+            hasher.write(&message[..split]);
+            hasher.write(&message[split..]);
+            assert_eq!(hasher.finish(), sip(&message));
+        }
+    }
+
+    #[test]
+    fn test_integers_are_little_endian() {
+        let mut hasher = SipHasher24::new();
+        hasher.write_u32(0x0403_0201);
+        assert_eq!(hasher.finish(), sip(&[1, 2, 3, 4]));
+
+        let mut hasher = SipHasher24::new();
+        hasher.write_usize(1);
+        assert_eq!(hasher.finish(), sip(&1u64.to_le_bytes()));
+    }
+
+    #[test]
+    fn test_build_hasher_keys() {
+        let build = BuildSipHasher24::with_keys(1, 2);
+        assert_eq!(build.build_hasher().keys(), (1, 2));
+        assert_eq!(
+            BuildSipHasher24::default().build_hasher().finish(),
+            sip(&[])
+        );
+    }
+}
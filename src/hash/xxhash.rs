@@ -0,0 +1,241 @@
+use std::hash::{BuildHasher, Hasher};
+
+const PRIME_1: u64 = 0x9e37_79b1_85eb_ca87;
+const PRIME_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const PRIME_3: u64 = 0x1656_67b1_9e37_79f9;
+const PRIME_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const PRIME_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+const STRIPE_LEN: usize = 32;
+
+/// Streaming xxHash64.
+#[derive(Clone, Debug)]
+pub struct XxHash64 {
+    seed: u64,
+    acc: [u64; 4],
+    buffer: [u8; STRIPE_LEN],
+    buffer_len: usize,
+    total_len: u64,
+}
+
+impl XxHash64 {
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            acc: [
+                seed.wrapping_add(PRIME_1).wrapping_add(PRIME_2),
+                seed.wrapping_add(PRIME_2),
+                seed,
+                seed.wrapping_sub(PRIME_1),
+            ],
+            buffer: [0; STRIPE_LEN],
+            buffer_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn round(acc: u64, input: u64) -> u64 {
+        acc.wrapping_add(input.wrapping_mul(PRIME_2))
+            .rotate_left(31)
+            .wrapping_mul(PRIME_1)
+    }
+
+    fn merge_round(acc: u64, val: u64) -> u64 {
+        (acc ^ Self::round(0, val))
+            .wrapping_mul(PRIME_1)
+            .wrapping_add(PRIME_4)
+    }
+
+    fn process_stripe(acc: &mut [u64; 4], stripe: &[u8]) {
+        for (lane, word) in acc.iter_mut().zip(stripe.chunks_exact(8)) {
+            *lane = Self::round(*lane, u64::from_le_bytes(word.try_into().unwrap()));
+        }
+    }
+}
+
+impl Default for XxHash64 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for XxHash64 {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.total_len += bytes.len() as u64;
+
+        if self.buffer_len != 0 {
+            let take = (STRIPE_LEN - self.buffer_len).min(bytes.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&bytes[..take]);
+            self.buffer_len += take;
+            bytes = &bytes[take..];
+
+            if self.buffer_len < STRIPE_LEN {
+                return;
+            }
+
+            Self::process_stripe(&mut self.acc, &self.buffer);
+            self.buffer_len = 0;
+        }
+
+        let mut stripes = bytes.chunks_exact(STRIPE_LEN);
+        for stripe in &mut stripes {
+            Self::process_stripe(&mut self.acc, stripe);
+        }
+
+        let remainder = stripes.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffer_len = remainder.len();
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = if self.total_len >= STRIPE_LEN as u64 {
+            let [v1, v2, v3, v4] = self.acc;
+            let mut hash = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+
+            for lane in self.acc {
+                hash = Self::merge_round(hash, lane);
+            }
+
+            hash
+        } else {
+            self.seed.wrapping_add(PRIME_5)
+        };
+
+        hash = hash.wrapping_add(self.total_len);
+
+        let mut tail = &self.buffer[..self.buffer_len];
+
+        while tail.len() >= 8 {
+            let word = u64::from_le_bytes(tail[..8].try_into().unwrap());
+            hash ^= Self::round(0, word);
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(PRIME_1)
+                .wrapping_add(PRIME_4);
+            tail = &tail[8..];
+        }
+
+        if tail.len() >= 4 {
+            let word = u32::from_le_bytes(tail[..4].try_into().unwrap()) as u64;
+            hash ^= word.wrapping_mul(PRIME_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(PRIME_2)
+                .wrapping_add(PRIME_3);
+            tail = &tail[4..];
+        }
+
+        for &byte in tail {
+            hash ^= (byte as u64).wrapping_mul(PRIME_5);
+            hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(PRIME_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(PRIME_3);
+        hash ^= hash >> 32;
+
+        hash
+    }
+}
+
+/// Builds [`XxHash64`] instances sharing the same seed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuildXxHash64 {
+    seed: u64,
+}
+
+impl BuildXxHash64 {
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl BuildHasher for BuildXxHash64 {
+    type Hasher = XxHash64;
+
+    fn build_hasher(&self) -> XxHash64 {
+        XxHash64::with_seed(self.seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xxh64(bytes: &[u8], seed: u64) -> u64 {
+        let mut hasher = XxHash64::with_seed(seed);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_reference_vectors() {
+        assert_eq!(xxh64(b"", 0), 0xef46_db37_51d8_e999);
+        assert_eq!(xxh64(b"a", 0), 0xd24e_c4f1_a98c_6e5b);
+        assert_eq!(xxh64(b"abc", 0), 0x44bc_2cf5_ad77_0999);
+        assert_eq!(
+            xxh64(b"Nobody inspects the spammish repetition", 0),
+            0xfbce_a83c_8a37_8bf1
+        );
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let message: Vec<u8> = (0..200).collect();
+
+        for split in 0..message.len() {
+            let mut hasher = XxHash64::with_seed(7);
+            hasher.write(&message[..split]);
+            hasher.write(&message[split..]);
+            assert_eq!(hasher.finish(), xxh64(&message, 7));
+        }
+    }
+
+    #[test]
+    fn test_seed_changes_output() {
+        assert_ne!(xxh64(b"abc", 0), xxh64(b"abc", 1));
+        assert_eq!(BuildXxHash64::with_seed(1).build_hasher().seed(), 1);
+    }
+}
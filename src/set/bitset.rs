@@ -0,0 +1,428 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
+
+/// Dense set of indices below `num_bits`, one bit each, stored in `u64`
+/// words.
+///
+/// The size is fixed at creation and only changes through
+/// [`grow`](Self::grow) or the set operations that need the larger of two
+/// sizes. Bits past `num_bits` are always clear, so whole words can be
+/// counted and compared directly.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    words: Vec<u64>,
+    num_bits: usize,
+}
+
+impl BitSet {
+    /// Empty set of size zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All bits clear
+    pub fn with_len(num_bits: usize) -> Self {
+        Self {
+            words: vec![0; num_bits.div_ceil(64)],
+            num_bits,
+        }
+    }
+
+    /// Bit `i` is bit `i % 64` of word `i / 64`.
+    ///
+    /// Panics unless there are exactly `num_bits.div_ceil(64)` words with
+    /// no bit set past `num_bits`
+    pub fn from_words(words: Vec<u64>, num_bits: usize) -> Self {
+        assert_eq!(
+            words.len(),
+            num_bits.div_ceil(64),
+            "{num_bits} bits need {} words",
+            num_bits.div_ceil(64)
+        );
+        assert!(
+            num_bits.is_multiple_of(64) || words[words.len() - 1] >> (num_bits % 64) == 0,
+            "bits set past the end of the set"
+        );
+
+        Self { words, num_bits }
+    }
+
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn into_words(self) -> Vec<u64> {
+        self.words
+    }
+
+    /// Size of the set, one past the largest index it can hold
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Bytes taken by the words
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.words.as_slice())
+    }
+
+    /// Extends the set to `num_bits` with the new bits clear. Does nothing
+    /// if it is already that large.
+    pub fn grow(&mut self, num_bits: usize) {
+        if num_bits > self.num_bits {
+            self.words.resize(num_bits.div_ceil(64), 0);
+            self.num_bits = num_bits;
+        }
+    }
+
+    /// Returns whether the bit was newly set.
+    ///
+    /// Panics if `index >= num_bits`
+    pub fn insert(&mut self, index: usize) -> bool {
+        assert!(
+            index < self.num_bits,
+            "index {index} out of range for {} bits",
+            self.num_bits
+        );
+
+        let word = &mut self.words[index / 64];
+        let mask = 1 << (index % 64);
+        let added = *word & mask == 0;
+        *word |= mask;
+        added
+    }
+
+    /// Returns whether the bit was set
+    pub fn remove(&mut self, index: usize) -> bool {
+        let Some(word) = self.words.get_mut(index / 64) else {
+            return false;
+        };
+
+        let mask = 1 << (index % 64);
+        let removed = *word & mask != 0;
+        *word &= !mask;
+        removed
+    }
+
+    /// False for indices past the end
+    pub fn contains(&self, index: usize) -> bool {
+        self.words
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    /// Clears every bit, keeping the size
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.num_bits - self.count_ones()
+    }
+
+    /// Whether no bit is set
+    pub fn is_clear(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Number of set bits at indices up to and including `index`
+    pub fn rank(&self, index: usize) -> usize {
+        if index / 64 >= self.words.len() {
+            return self.count_ones();
+        }
+
+        rank(&self.words, index)
+    }
+
+    /// Index of the `n`-th set bit, counting from zero
+    pub fn select(&self, n: usize) -> Option<usize> {
+        select(&self.words, n)
+    }
+
+    /// Indices of the set bits in increasing order, skipping clear words
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(&self.words)
+    }
+
+    /// Sets every bit set in `other`, growing to its size if it is larger
+    pub fn union_with(&mut self, other: &Self) {
+        self.grow(other.num_bits);
+        for (word, &other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    /// Clears every bit not set in `other`
+    pub fn intersect_with(&mut self, other: &Self) {
+        let shared = self.words.len().min(other.words.len());
+        for (word, &other) in self.words.iter_mut().zip(&other.words) {
+            *word &= other;
+        }
+        self.words[shared..].fill(0);
+    }
+
+    /// Clears every bit set in `other`
+    pub fn difference_with(&mut self, other: &Self) {
+        for (word, &other) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other;
+        }
+    }
+
+    /// Flips every bit set in `other`, growing to its size if it is larger
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        self.grow(other.num_bits);
+        for (word, &other) in self.words.iter_mut().zip(&other.words) {
+            *word ^= other;
+        }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .enumerate()
+            .all(|(index, &word)| word & !other.words.get(index).copied().unwrap_or(0) == 0)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .all(|(&word, &other)| word & other == 0)
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    /// Sized to one past the largest index
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    /// Grows as needed to fit every index
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for index in iter {
+            self.grow(index + 1);
+            self.insert(index);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+macro_rules! impl_operator {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:ident) => {
+        impl $trait<&BitSet> for &BitSet {
+            type Output = BitSet;
+
+            fn $method(self, other: &BitSet) -> BitSet {
+                let mut result = self.clone();
+                result.$op(other);
+                result
+            }
+        }
+
+        impl $assign_trait<&BitSet> for BitSet {
+            fn $assign_method(&mut self, other: &BitSet) {
+                self.$op(other);
+            }
+        }
+    };
+}
+
+impl_operator!(BitOr, bitor, BitOrAssign, bitor_assign, union_with);
+impl_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, intersect_with);
+impl_operator!(
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    symmetric_difference_with
+);
+impl_operator!(Sub, sub, SubAssign, sub_assign, difference_with);
+
+/// Number of set bits in `words`
+pub(crate) fn count_ones(words: &[u64]) -> usize {
+    words.iter().map(|word| word.count_ones() as usize).sum()
+}
+
+/// Number of set bits at positions up to and including `index`, which must
+/// fall inside `words`
+pub(crate) fn rank(words: &[u64], index: usize) -> usize {
+    let (word, bit) = (index / 64, index % 64);
+    count_ones(&words[..word]) + (words[word] & (u64::MAX >> (63 - bit))).count_ones() as usize
+}
+
+/// Position of the `n`-th set bit in `words`, counting from zero
+pub(crate) fn select(words: &[u64], mut n: usize) -> Option<usize> {
+    for (index, &word) in words.iter().enumerate() {
+        let ones = word.count_ones() as usize;
+        if n < ones {
+            let mut word = word;
+            for _ in 0..n {
+                word &= word - 1;
+            }
+            return Some(index * 64 + word.trailing_zeros() as usize);
+        }
+        n -= ones;
+    }
+
+    None
+}
+
+/// Iterator over the set bits of a [`BitSet`], or of any word slice
+pub struct Iter<'a> {
+    words: &'a [u64],
+    index: usize,
+    /// Bits of `words[index]` not yet returned
+    word: u64,
+}
+
+impl<'a> Iter<'a> {
+    pub(crate) fn new(words: &'a [u64]) -> Self {
+        Self {
+            words,
+            index: 0,
+            word: words.first().copied().unwrap_or(0),
+        }
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(self.index * 64 + bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn sample(step: usize, num_bits: usize) -> (BitSet, BTreeSet<usize>) {
+        let expected: BTreeSet<usize> = (0..num_bits).step_by(step).collect();
+        let mut set = BitSet::with_len(num_bits);
+        for &index in &expected {
+            assert!(set.insert(index));
+        }
+        (set, expected)
+    }
+
+    #[test]
+    fn test_insert_remove_contains() {
+        let mut set = BitSet::with_len(130);
+        assert!(set.insert(0));
+        assert!(set.insert(129));
+        assert!(!set.insert(129));
+
+        assert!(set.contains(129));
+        assert!(!set.contains(128));
+        assert!(!set.contains(10_000));
+        assert_eq!(set.count_ones(), 2);
+        assert_eq!(set.count_zeros(), 128);
+
+        assert!(set.remove(0));
+        assert!(!set.remove(0));
+        assert!(!set.remove(10_000));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![129]);
+
+        set.clear();
+        assert!(set.is_clear());
+        assert_eq!(set.num_bits(), 130);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_out_of_range_panics() {
+        BitSet::with_len(64).insert(64);
+    }
+
+    #[test]
+    fn test_rank_select_iter() {
+        let (set, expected) = sample(7, 1_000);
+
+        assert!(set.iter().eq(expected.iter().copied()));
+        for (n, &index) in expected.iter().enumerate() {
+            assert_eq!(set.select(n), Some(index));
+            assert_eq!(set.rank(index), n + 1);
+            assert_eq!(set.rank(index + 1), n + 1);
+        }
+        assert_eq!(set.select(expected.len()), None);
+        assert_eq!(set.rank(5_000), expected.len());
+        assert_eq!(BitSet::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_grow() {
+        let mut set = BitSet::with_len(10);
+        set.insert(9);
+        set.grow(200);
+        set.grow(100);
+
+        assert_eq!(set.num_bits(), 200);
+        assert_eq!(set.as_words().len(), 4);
+        assert!(set.insert(199));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![9, 199]);
+
+        let collected: BitSet = [3, 300].into_iter().collect();
+        assert_eq!(collected.num_bits(), 301);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let (a, left) = sample(2, 500);
+        let (b, right) = sample(3, 700);
+
+        let check = |set: BitSet, expected: BTreeSet<usize>| {
+            assert!(set.iter().eq(expected.into_iter()));
+        };
+        check(&a | &b, &left | &right);
+        check(&a & &b, &left & &right);
+        check(&a ^ &b, &left ^ &right);
+        check(&a - &b, &left - &right);
+        check(&b & &a, &left & &right);
+        check(&b - &a, &right - &left);
+        assert_eq!((&a | &b).num_bits(), 700);
+        assert_eq!((&a & &b).num_bits(), 500);
+
+        let mut c = a.clone();
+        c &= &b;
+        assert!(c.is_subset(&a) && c.is_subset(&b));
+        c -= &b;
+        assert!(c.is_clear());
+        assert!((&a - &b).is_disjoint(&b));
+    }
+
+    #[test]
+    fn test_words() {
+        let set = BitSet::from_words(vec![u64::MAX, 0b101], 67);
+        assert_eq!(set.count_ones(), 66);
+        assert_eq!(set.clone().into_words(), vec![u64::MAX, 0b101]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_words_with_trailing_bits_panics() {
+        BitSet::from_words(vec![0b1000], 3);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::hash::XxHash64;
+use std::fmt;
+use std::hash::Hasher;
+
+/// Error returned when deserializing a structure from bytes fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u8),
+    ChecksumMismatch,
+    Invalid(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "input is truncated"),
+            DecodeError::BadMagic => write!(f, "input does not start with the expected magic"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {version}")
+            }
+            DecodeError::ChecksumMismatch => write!(f, "checksum mismatch"),
+            DecodeError::Invalid(reason) => write!(f, "invalid input: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Little-endian cursor over serialized bytes.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn read_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub(crate) fn read_u16(&mut self) -> Result<u16, DecodeError> {
+        self.read_array().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        self.read_array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        self.read_array().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn read_f64(&mut self) -> Result<f64, DecodeError> {
+        self.read_array().map(f64::from_le_bytes)
+    }
+
+    /// Reads a `u64` that must fit the platform's `usize`
+    pub(crate) fn read_usize(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.read_u64()?)
+            .map_err(|_| DecodeError::Invalid("length overflows usize"))
+    }
+
+    /// Checks the magic and returns the format version that follows it
+    pub(crate) fn read_header(&mut self, magic: &[u8; 4]) -> Result<u8, DecodeError> {
+        if self.read_bytes(4)? != magic {
+            return Err(DecodeError::BadMagic);
+        }
+
+        self.read_u8()
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Fails if any input is left unread
+    pub(crate) fn finish(self) -> Result<(), DecodeError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::Invalid("trailing bytes"))
+        }
+    }
+}
+
+/// xxHash64 over the concatenation of `parts`
+pub(crate) fn checksum(parts: &[&[u8]]) -> u64 {
+    let mut hasher = XxHash64::with_seed(0);
+    for part in parts {
+        hasher.write(part);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader() {
+        let mut bytes = b"TEST".to_vec();
+        bytes.push(3);
+        bytes.extend_from_slice(&9u16.to_le_bytes());
+        bytes.extend_from_slice(&7u32.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&0.5f64.to_le_bytes());
+
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_header(b"TEST"), Ok(3));
+        assert_eq!(reader.read_u16(), Ok(9));
+        assert_eq!(reader.read_u32(), Ok(7));
+        assert_eq!(reader.read_u64(), Ok(u64::MAX));
+        assert_eq!(reader.remaining(), 8);
+        assert_eq!(reader.read_f64(), Ok(0.5));
+        assert_eq!(reader.read_u8(), Err(DecodeError::Truncated));
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn test_bad_magic_and_trailing_bytes() {
+        assert_eq!(
+            Reader::new(b"NOPE\x01").read_header(b"TEST"),
+            Err(DecodeError::BadMagic)
+        );
+        assert!(Reader::new(b"x").finish().is_err());
+    }
+}
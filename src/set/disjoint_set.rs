@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+
+/// Partition of the indices `0..len` into disjoint sets (union-find).
+///
+/// Sets are trees of parent links rooted at a representative. `union` hangs
+/// the smaller tree under the larger one and `find` points every node it
+/// passes straight at the root, which together make any sequence of
+/// operations take nearly constant amortized time per operation (inverse
+/// Ackermann).
+#[derive(Clone, Debug, Default)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    /// Size of the set, only meaningful at roots
+    size: Vec<usize>,
+    num_sets: usize,
+}
+
+impl DisjointSet {
+    /// `len` singleton sets
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            num_sets: len,
+        }
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Number of disjoint sets
+    pub fn num_sets(&self) -> usize {
+        self.num_sets
+    }
+
+    /// Adds a singleton set and returns its element
+    pub fn add(&mut self) -> usize {
+        let element = self.parent.len();
+        self.parent.push(element);
+        self.size.push(1);
+        self.num_sets += 1;
+        element
+    }
+
+    /// Representative of the set containing `element`.
+    ///
+    /// Panics if `element >= len`
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut node = element;
+        while self.parent[node] != root {
+            node = std::mem::replace(&mut self.parent[node], root);
+        }
+
+        root
+    }
+
+    /// Merges the sets containing `a` and `b`. Returns false if they were
+    /// already the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.num_sets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Number of elements in the set containing `element`
+    pub fn set_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    /// Every set as its elements in increasing order, sets ordered by their
+    /// smallest element
+    pub fn groups(&mut self) -> Vec<Vec<usize>> {
+        let mut group_of_root = HashMap::with_capacity(self.num_sets);
+        let mut groups: Vec<Vec<usize>> = Vec::with_capacity(self.num_sets);
+
+        for element in 0..self.len() {
+            let root = self.find(element);
+            let group = *group_of_root.entry(root).or_insert_with(|| {
+                groups.push(Vec::with_capacity(self.size[root]));
+                groups.len() - 1
+            });
+            groups[group].push(element);
+        }
+
+        groups
+    }
+}
+
+/// Marks the end of a chain of keys with the same hash
+const NONE: usize = usize::MAX;
+
+/// [`DisjointSet`] over arbitrary keys, each mapped to an index on first
+/// use.
+///
+/// This is a separate type rather than a key parameter on [`DisjointSet`]
+/// so the index version keeps its lookup-free `usize` API. Keys are stored
+/// once, in insertion order, and found through a table from key hash to
+/// index, so they only need `Hash + Eq`.
+#[derive(Clone, Debug)]
+pub struct KeyedDisjointSet<T> {
+    keys: Vec<T>,
+    /// First index of the keys with each hash, the rest chained through
+    /// `next`
+    heads: HashMap<u64, usize>,
+    next: Vec<usize>,
+    hash_builder: RandomState,
+    sets: DisjointSet,
+}
+
+impl<T: Hash + Eq> KeyedDisjointSet<T> {
+    pub fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            heads: HashMap::new(),
+            next: Vec::new(),
+            hash_builder: RandomState::new(),
+            sets: DisjointSet::new(0),
+        }
+    }
+
+    /// Number of keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.sets.num_sets()
+    }
+
+    pub fn contains(&self, key: &T) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Adds `key` as a singleton set. Returns false if it was already present.
+    pub fn insert(&mut self, key: T) -> bool {
+        let len = self.len();
+        self.index_of(key);
+        self.len() > len
+    }
+
+    /// Representative key of the set containing `key`
+    pub fn find(&mut self, key: &T) -> Option<&T> {
+        let index = self.position(key)?;
+        let root = self.sets.find(index);
+        Some(&self.keys[root])
+    }
+
+    /// Merges the sets containing `a` and `b`, inserting either key if it
+    /// is new. Returns false if they were already the same set.
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let (a, b) = (self.index_of(a), self.index_of(b));
+        self.sets.union(a, b)
+    }
+
+    /// False if either key is missing
+    pub fn same_set(&mut self, a: &T, b: &T) -> bool {
+        match (self.position(a), self.position(b)) {
+            (Some(a), Some(b)) => self.sets.same_set(a, b),
+            _ => false,
+        }
+    }
+
+    /// Number of keys in the set containing `key`
+    pub fn set_size(&mut self, key: &T) -> Option<usize> {
+        let index = self.position(key)?;
+        Some(self.sets.set_size(index))
+    }
+
+    /// Every set as its keys in insertion order, sets ordered by their
+    /// earliest inserted key
+    pub fn groups(&mut self) -> Vec<Vec<&T>> {
+        self.sets
+            .groups()
+            .into_iter()
+            .map(|group| group.into_iter().map(|index| &self.keys[index]).collect())
+            .collect()
+    }
+
+    fn position(&self, key: &T) -> Option<usize> {
+        self.find_in_chain(key, self.hash_builder.hash_one(key))
+    }
+
+    fn find_in_chain(&self, key: &T, hash: u64) -> Option<usize> {
+        let mut index = *self.heads.get(&hash)?;
+        while index != NONE {
+            if self.keys[index] == *key {
+                return Some(index);
+            }
+            index = self.next[index];
+        }
+
+        None
+    }
+
+    fn index_of(&mut self, key: T) -> usize {
+        let hash = self.hash_builder.hash_one(&key);
+        if let Some(index) = self.find_in_chain(&key, hash) {
+            return index;
+        }
+
+        let index = self.sets.add();
+        self.next
+            .push(self.heads.insert(hash, index).unwrap_or(NONE));
+        self.keys.push(key);
+        index
+    }
+}
+
+impl<T: Hash + Eq> Default for KeyedDisjointSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_union_find() {
+        let mut sets = DisjointSet::new(6);
+        assert_eq!(sets.num_sets(), 6);
+
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+
+        assert!(sets.same_set(0, 3));
+        assert!(!sets.same_set(0, 4));
+        assert_eq!(sets.set_size(2), 4);
+        assert_eq!(sets.set_size(5), 1);
+        assert_eq!(sets.num_sets(), 3);
+        assert_eq!(sets.groups(), vec![vec![0, 1, 2, 3], vec![4], vec![5]]);
+
+        let added = sets.add();
+        assert_eq!(added, 6);
+        assert!(sets.union(added, 5));
+        assert_eq!(sets.groups(), vec![vec![0, 1, 2, 3], vec![4], vec![5, 6]]);
+    }
+
+    #[test]
+    fn test_path_compression() {
+        let mut sets = DisjointSet::new(1_000);
+        for i in 1..1_000 {
+            sets.union(i - 1, i);
+        }
+
+        let root = sets.find(999);
+        for element in 0..1_000 {
+            assert_eq!(sets.find(element), root);
+            assert_eq!(sets.parent[element], root);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_range_panics() {
+        DisjointSet::new(3).find(3);
+    }
+
+    #[quickcheck]
+    fn prop_matches_naive_labels(len: u8, edges: Vec<(u8, u8)>) -> bool {
+        let len = len as usize + 1;
+        let mut sets = DisjointSet::new(len);
+        let mut labels: Vec<usize> = (0..len).collect();
+
+        for (a, b) in edges {
+            let (a, b) = (a as usize % len, b as usize % len);
+            let merged = labels[a] != labels[b];
+            assert_eq!(sets.union(a, b), merged);
+
+            let (from, to) = (labels[b], labels[a]);
+            for label in &mut labels {
+                if *label == from {
+                    *label = to;
+                }
+            }
+        }
+
+        (0..len).all(|a| {
+            let size = labels.iter().filter(|&&label| label == labels[a]).count();
+            sets.set_size(a) == size
+                && (0..len).all(|b| sets.same_set(a, b) == (labels[a] == labels[b]))
+        })
+    }
+
+    #[test]
+    fn test_keyed() {
+        let mut sets = KeyedDisjointSet::new();
+        assert!(sets.insert("core"));
+        assert!(!sets.insert("core"));
+
+        assert!(sets.union("app", "core"));
+        assert!(sets.union("cli", "app"));
+        assert!(sets.union("docs", "site"));
+        assert!(!sets.union("core", "cli"));
+
+        assert_eq!(sets.len(), 5);
+        assert_eq!(sets.num_sets(), 2);
+        assert!(sets.same_set(&"cli", &"core"));
+        assert!(!sets.same_set(&"cli", &"docs"));
+        assert!(!sets.same_set(&"cli", &"missing"));
+        assert_eq!(sets.set_size(&"app"), Some(3));
+        assert_eq!(sets.set_size(&"missing"), None);
+        assert_eq!(sets.find(&"missing"), None);
+        let root = sets.find(&"core").copied();
+        assert!(root.is_some());
+        assert_eq!(sets.find(&"cli").copied(), root);
+
+        assert_eq!(
+            sets.groups(),
+            vec![vec![&"core", &"app", &"cli"], vec![&"docs", &"site"]]
+        );
+    }
+
+    #[test]
+    fn test_keyed_hash_collisions() {
+        /// Every key hashes the same, and can't be cloned
+        #[derive(Debug, PartialEq, Eq)]
+        struct Colliding(u32);
+
+        impl Hash for Colliding {
+            fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+        }
+
+        let mut sets = KeyedDisjointSet::new();
+        for i in 0..10 {
+            assert!(sets.insert(Colliding(i)));
+        }
+        assert!(!sets.insert(Colliding(3)));
+        assert!(sets.union(Colliding(1), Colliding(7)));
+        assert!(sets.union(Colliding(7), Colliding(10)));
+
+        assert_eq!(sets.len(), 11);
+        assert!(sets.contains(&Colliding(0)));
+        assert!(!sets.contains(&Colliding(11)));
+        assert!(sets.same_set(&Colliding(1), &Colliding(10)));
+        assert!(!sets.same_set(&Colliding(1), &Colliding(2)));
+        assert_eq!(sets.set_size(&Colliding(10)), Some(3));
+    }
+}
@@ -0,0 +1,269 @@
+use crate::hash::DefaultBuildHasher;
+use crate::set::filter::BloomFilter;
+use crate::set::filter::params::{optimal_num_cells, optimal_num_hashes};
+use crate::set::filter::probe::cell_indices;
+use std::hash::{BuildHasher, Hash};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Bloom filter that can be shared between threads without a lock.
+///
+/// Bits are only ever set, and setting one with a relaxed `fetch_or` can't
+/// undo another thread's write, so an insert that has returned is never lost.
+/// A `contains` racing with the insert of the same item may still miss it;
+/// once the inserting thread synchronizes with the reader (a join, a channel
+/// send, ...) the item is guaranteed to be found.
+///
+/// The bits live in `AtomicU64` words instead of a
+/// [`BitSet`](crate::set::BitSet), which needs `&mut` access to set a bit.
+pub struct AtomicBloomFilter<S = DefaultBuildHasher> {
+    bits: Vec<AtomicU64>,
+    num_bits: usize,
+    num_hashes: u32,
+    len: AtomicUsize,
+    hash_builder: S,
+}
+
+impl AtomicBloomFilter {
+    pub fn with_params(num_bits: usize, num_hashes: u32) -> Self {
+        Self::with_params_and_hasher(num_bits, num_hashes, DefaultBuildHasher::default())
+    }
+
+    /// Sizes the filter to hold `expected_items` at the given false positive rate
+    pub fn with_capacity(expected_items: usize, fp_rate: f64) -> Self {
+        Self::with_capacity_and_hasher(expected_items, fp_rate, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> AtomicBloomFilter<S> {
+    /// Panics unless `0 < fp_rate < 1`
+    pub fn with_capacity_and_hasher(expected_items: usize, fp_rate: f64, hash_builder: S) -> Self {
+        let num_bits = optimal_num_cells(expected_items, fp_rate);
+        let num_hashes = optimal_num_hashes(num_bits, expected_items);
+
+        Self::with_params_and_hasher(num_bits, num_hashes, hash_builder)
+    }
+
+    /// Panics if `num_bits` or `num_hashes` is zero
+    pub fn with_params_and_hasher(num_bits: usize, num_hashes: u32, hash_builder: S) -> Self {
+        assert!(num_bits > 0, "bloom filter must have at least one bit");
+        assert!(num_hashes > 0, "bloom filter must use at least one hash");
+
+        Self {
+            bits: (0..num_bits.div_ceil(64))
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            num_bits,
+            num_hashes,
+            len: AtomicUsize::new(0),
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Number of insertions, duplicates included
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes taken by the bit array
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.bits.as_slice())
+    }
+
+    /// Probability that an item never inserted is reported present,
+    /// `(X / m)^k` for `X` set bits out of `m`
+    pub fn fp_rate(&self) -> f64 {
+        let ones: usize = self
+            .bits
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed).count_ones() as usize)
+            .sum();
+
+        (ones as f64 / self.num_bits as f64).powi(self.num_hashes as i32)
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&self, item: &T) {
+        for index in self.indices(item) {
+            self.bits[index / 64].fetch_or(1 << (index % 64), Ordering::Relaxed);
+        }
+        self.len.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.indices(item)
+            .all(|index| self.bits[index / 64].load(Ordering::Relaxed) & (1 << (index % 64)) != 0)
+    }
+
+    /// Takes `&mut self` so no insert can be half done while bits are reset
+    pub fn clear(&mut self) {
+        for word in &mut self.bits {
+            *word.get_mut() = 0;
+        }
+        *self.len.get_mut() = 0;
+    }
+
+    /// Copies the current bits into a plain filter with the same parameters.
+    ///
+    /// Inserts running concurrently may be partially included. An insert is
+    /// only guaranteed to be fully included if it happens-before this call,
+    /// e.g. its thread was joined or it was followed by a channel send this
+    /// thread received.
+    pub fn snapshot(&self) -> BloomFilter<S>
+    where
+        S: Clone,
+    {
+        let bits = self
+            .bits
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed))
+            .collect();
+
+        BloomFilter::from_parts(
+            bits,
+            self.num_bits,
+            self.num_hashes,
+            self.len(),
+            self.hash_builder.clone(),
+        )
+    }
+
+    fn indices<T: Hash + ?Sized>(&self, item: &T) -> impl Iterator<Item = usize> + use<T, S> {
+        cell_indices(&self.hash_builder, item, self.num_hashes, self.num_bits)
+    }
+}
+
+impl<S: BuildHasher> From<BloomFilter<S>> for AtomicBloomFilter<S> {
+    fn from(filter: BloomFilter<S>) -> Self {
+        let (bits, num_bits, num_hashes, len, hash_builder) = filter.into_parts();
+
+        Self {
+            bits: bits.into_iter().map(AtomicU64::new).collect(),
+            num_bits,
+            num_hashes,
+            len: AtomicUsize::new(len),
+            hash_builder,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const NUM_THREADS: u64 = 8;
+
+    #[test]
+    fn test_is_sync() {
+        fn assert_sync<T: Sync + Send>() {}
+        assert_sync::<AtomicBloomFilter>();
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let filter = AtomicBloomFilter::with_capacity(1_000, 0.01);
+        assert!(filter.is_empty());
+
+        filter.insert(&"hello");
+        assert!(filter.contains(&"hello"));
+        assert_eq!(filter.len(), 1);
+    }
+
+    #[test]
+    fn test_matches_plain_filter() {
+        let atomic = AtomicBloomFilter::with_capacity(1_000, 0.01);
+        let mut plain = BloomFilter::with_capacity(1_000, 0.01);
+
+        for i in 0..1_000 {
+            atomic.insert(&i);
+            plain.insert(&i);
+        }
+
+        let snapshot = atomic.snapshot();
+        assert_eq!(snapshot.to_bytes(), plain.to_bytes());
+    }
+
+    #[test]
+    fn test_concurrent_inserts_have_no_false_negatives() {
+        let filter = AtomicBloomFilter::with_capacity(80_000, 0.01);
+
+        // Threads write disjoint items into shared words
+        thread::scope(|scope| {
+            for t in 0..NUM_THREADS {
+                let filter = &filter;
+                scope.spawn(move || {
+                    for i in (t..80_000).step_by(NUM_THREADS as usize) {
+                        filter.insert(&i);
+                        // A thread always observes its own writes
+                        assert!(filter.contains(&i));
+                    }
+                });
+            }
+        });
+
+        assert_eq!(filter.len(), 80_000);
+        for i in 0..80_000u64 {
+            assert!(filter.contains(&i), "lost insert of {i}");
+        }
+    }
+
+    #[test]
+    fn test_concurrent_readers_and_writers() {
+        let filter = AtomicBloomFilter::with_params(1 << 12, 4);
+
+        // Everything set before the scope stays visible to concurrent readers
+        for i in 0..200u64 {
+            filter.insert(&i);
+        }
+
+        thread::scope(|scope| {
+            for t in 0..NUM_THREADS / 2 {
+                let filter = &filter;
+                scope.spawn(move || {
+                    for i in 0..5_000u64 {
+                        filter.insert(&(1_000 + t * 5_000 + i));
+                    }
+                });
+                scope.spawn(move || {
+                    for _ in 0..50 {
+                        assert!((0..200u64).all(|i| filter.contains(&i)));
+                    }
+                });
+            }
+        });
+
+        let snapshot = filter.snapshot();
+        for i in (0..200).chain(1_000..1_000 + NUM_THREADS / 2 * 5_000) {
+            assert!(snapshot.contains(&i));
+        }
+    }
+
+    #[test]
+    fn test_from_plain_filter_and_clear() {
+        let mut plain = BloomFilter::with_capacity(100, 0.01);
+        plain.insert(&"kept");
+
+        let mut atomic = AtomicBloomFilter::from(plain);
+        assert!(atomic.contains(&"kept"));
+        assert_eq!(atomic.len(), 1);
+
+        atomic.clear();
+        assert!(atomic.is_empty());
+        assert!(!atomic.contains(&"kept"));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::filter::params::{check_fp_rate, optimal_num_cells, optimal_num_hashes};
+use crate::set::filter::probe::Probes;
+use std::hash::{BuildHasher, Hash};
+
+const BLOCK_BITS: usize = 512;
+const BLOCK_WORDS: usize = BLOCK_BITS / 64;
+
+#[derive(Clone, Copy)]
+#[repr(align(64))]
+struct Block([u64; BLOCK_WORDS]);
+
+/// Bloom filter that keeps all bits of an item inside one 64-byte block
+/// (Putze, Sanders, Singler, "Cache-, Hash- and Space-Efficient Bloom
+/// Filters"), so a lookup touches a single cache line.
+///
+/// Confining bits to a block makes some blocks more loaded than others, which
+/// raises the false positive rate over a standard filter of the same size.
+/// [`BlockedBloomFilter::with_capacity`] compensates by growing the filter
+/// until the rate predicted by [`BlockedBloomFilter::fp_rate_at`] meets the
+/// target, which costs roughly 5-20% more memory at 5%-0.01% targets.
+///
+/// Blocks are stored as 64-byte aligned arrays rather than a
+/// [`BitSet`](crate::set::BitSet), whose words are only 8-byte aligned and
+/// would let a block straddle two cache lines.
+pub struct BlockedBloomFilter<S = DefaultBuildHasher> {
+    blocks: Vec<Block>,
+    num_hashes: u32,
+    len: usize,
+    hash_builder: S,
+}
+
+impl BlockedBloomFilter {
+    pub fn with_params(num_bits: usize, num_hashes: u32) -> Self {
+        Self::with_params_and_hasher(num_bits, num_hashes, DefaultBuildHasher::default())
+    }
+
+    /// Sizes the filter to hold `expected_items` at the given false positive rate
+    pub fn with_capacity(expected_items: usize, fp_rate: f64) -> Self {
+        Self::with_capacity_and_hasher(expected_items, fp_rate, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> BlockedBloomFilter<S> {
+    /// Panics unless `0 < fp_rate < 1`
+    pub fn with_capacity_and_hasher(expected_items: usize, fp_rate: f64, hash_builder: S) -> Self {
+        check_fp_rate(fp_rate);
+
+        let mut num_blocks = optimal_num_cells(expected_items, fp_rate).div_ceil(BLOCK_BITS);
+        let mut num_hashes;
+
+        loop {
+            num_hashes = optimal_num_hashes(num_blocks * BLOCK_BITS, expected_items);
+            if blocked_fp_rate(num_blocks, num_hashes, expected_items) <= fp_rate {
+                break;
+            }
+
+            num_blocks += num_blocks.div_ceil(32);
+        }
+
+        Self::with_params_and_hasher(num_blocks * BLOCK_BITS, num_hashes, hash_builder)
+    }
+
+    /// Rounds `num_bits` up to a whole number of 512-bit blocks.
+    ///
+    /// Panics if `num_bits` or `num_hashes` is zero
+    pub fn with_params_and_hasher(num_bits: usize, num_hashes: u32, hash_builder: S) -> Self {
+        assert!(num_bits > 0, "bloom filter must have at least one bit");
+        assert!(num_hashes > 0, "bloom filter must use at least one hash");
+
+        Self {
+            blocks: vec![Block([0; BLOCK_WORDS]); num_bits.div_ceil(BLOCK_BITS)],
+            num_hashes,
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.blocks.len() * BLOCK_BITS
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Number of insertions, duplicates included
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes taken by the blocks
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.blocks.as_slice())
+    }
+
+    /// False positive rate predicted after inserting `num_items` distinct items
+    pub fn fp_rate_at(&self, num_items: usize) -> f64 {
+        blocked_fp_rate(self.blocks.len(), self.num_hashes, num_items)
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let (block, probes) = self.locate(item);
+        let block = &mut self.blocks[block].0;
+
+        for bit in probes {
+            block[bit / 64] |= 1 << (bit % 64);
+        }
+        self.len += 1;
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let (block, probes) = self.locate(item);
+        let block = &self.blocks[block].0;
+
+        probes
+            .into_iter()
+            .all(|bit| block[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.fill(Block([0; BLOCK_WORDS]));
+        self.len = 0;
+    }
+
+    /// The block is picked from the high bits of `h1`, bits inside it from
+    /// double hashing over `(h2, h1)`
+    fn locate<T: Hash + ?Sized>(
+        &self,
+        item: &T,
+    ) -> (usize, impl Iterator<Item = usize> + use<T, S>) {
+        let (h1, h2) = hash128(&self.hash_builder, item);
+        let block = ((h1 as u128 * self.blocks.len() as u128) >> 64) as usize;
+        let probes = Probes::new((h2, h1), self.num_hashes).map(|hash| hash as usize % BLOCK_BITS);
+
+        (block, probes)
+    }
+}
+
+/// Expected false positive rate of a blocked filter: block loads follow a
+/// Poisson distribution with mean `n / num_blocks`, and a block holding `i`
+/// items behaves like a standard 512-bit filter with `i` items.
+fn blocked_fp_rate(num_blocks: usize, num_hashes: u32, num_items: usize) -> f64 {
+    let mean = num_items as f64 / num_blocks as f64;
+    if mean == 0.0 {
+        return 0.0;
+    }
+
+    let max_load = (mean + 10.0 * mean.sqrt() + 20.0) as usize;
+    let miss = 1.0 - 1.0 / BLOCK_BITS as f64;
+
+    let mut log_pmf = -mean;
+    let mut fp_rate = 0.0;
+
+    for load in 0..=max_load {
+        if load > 0 {
+            log_pmf += mean.ln() - (load as f64).ln();
+        }
+
+        let bit_set = 1.0 - miss.powf(load as f64 * num_hashes as f64);
+        fp_rate += log_pmf.exp() * bit_set.powi(num_hashes as i32);
+    }
+
+    fp_rate.min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_are_cache_lines() {
+        assert_eq!(std::mem::size_of::<Block>(), 64);
+        assert_eq!(std::mem::align_of::<Block>(), 64);
+
+        let filter = BlockedBloomFilter::with_params(1_000, 7);
+        assert_eq!(filter.num_bits(), 1_024);
+        assert_eq!(filter.memory_usage(), 128);
+        assert_eq!(filter.blocks.as_ptr() as usize % 64, 0);
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut filter = BlockedBloomFilter::with_capacity(1_000, 0.01);
+
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+        for i in 0..1_000 {
+            assert!(filter.contains(&i));
+        }
+
+        filter.clear();
+        assert!(!filter.contains(&1));
+    }
+
+    #[test]
+    fn test_item_bits_stay_in_one_block() {
+        let mut filter = BlockedBloomFilter::with_params(64 * BLOCK_BITS, 8);
+        filter.insert(&"hello");
+
+        let touched = filter
+            .blocks
+            .iter()
+            .filter(|block| block.0.iter().any(|&word| word != 0))
+            .count();
+        assert_eq!(touched, 1);
+    }
+
+    #[test]
+    fn test_sizing_meets_target() {
+        for fp_rate in [0.05, 0.01, 0.001] {
+            let mut filter = BlockedBloomFilter::with_capacity(20_000, fp_rate);
+            assert!(filter.fp_rate_at(20_000) <= fp_rate);
+
+            for i in 0..20_000 {
+                filter.insert(&i);
+            }
+
+            let false_positives = (20_000..220_000).filter(|i| filter.contains(i)).count();
+            let observed = false_positives as f64 / 200_000.0;
+            assert!(observed < fp_rate * 1.3, "{fp_rate}: {observed}");
+        }
+    }
+
+    #[test]
+    fn test_fp_rate_model() {
+        // With one item per block on average the model stays close to the
+        // standard filter, with heavier loads it is noticeably worse
+        let light = blocked_fp_rate(1_000, 7, 1_000);
+        assert!(light < 1e-10);
+
+        let standard = (1.0 - (-7.0f64 * 50.0 / 512.0).exp()).powi(7);
+        let blocked = blocked_fp_rate(1_000, 7, 50_000);
+        assert!(blocked > standard);
+        assert!(blocked < standard * 2.0);
+
+        assert_eq!(blocked_fp_rate(10, 3, 0), 0.0);
+    }
+}
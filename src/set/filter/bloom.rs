@@ -1,42 +1,395 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::codec::{Reader, checksum};
+use crate::set::filter::IncompatibleFilters;
+use crate::set::filter::params::{optimal_num_cells, optimal_num_hashes};
+use crate::set::filter::probe::{cell_indices, hash_indices, prefetch};
+use crate::set::{BitSet, DecodeError};
+use std::hash::{BuildHasher, Hash};
+use std::ops::{BitAndAssign, BitOrAssign};
 
-pub struct BloomFilter {
-    bits: u128,
+const DEFAULT_NUM_BITS: usize = 128;
+const DEFAULT_NUM_HASHES: u32 = 2;
+
+/// Items hashed ahead of probing by the batch methods
+const BATCH_SIZE: usize = 32;
+
+const MAGIC: &[u8; 4] = b"BLMF";
+const VERSION: u8 = 2;
+const HEADER_LEN: usize = 40;
+
+pub struct BloomFilter<S = DefaultBuildHasher> {
+    bits: BitSet,
+    num_hashes: u32,
+    len: usize,
+    hash_builder: S,
 }
 
 impl BloomFilter {
     pub fn new() -> Self {
-        Self { bits: 0 }
+        Self::with_hasher(DefaultBuildHasher::default())
+    }
+
+    pub fn with_params(num_bits: usize, num_hashes: u32) -> Self {
+        Self::with_params_and_hasher(num_bits, num_hashes, DefaultBuildHasher::default())
+    }
+
+    /// Sizes the filter to hold `expected_items` at the given false positive rate
+    pub fn with_capacity(expected_items: usize, fp_rate: f64) -> Self {
+        Self::with_capacity_and_hasher(expected_items, fp_rate, DefaultBuildHasher::default())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> BloomFilter<S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_params_and_hasher(DEFAULT_NUM_BITS, DEFAULT_NUM_HASHES, hash_builder)
+    }
+
+    /// Panics unless `0 < fp_rate < 1`
+    pub fn with_capacity_and_hasher(expected_items: usize, fp_rate: f64, hash_builder: S) -> Self {
+        let num_bits = optimal_num_cells(expected_items, fp_rate);
+        let num_hashes = optimal_num_hashes(num_bits, expected_items);
+
+        Self::with_params_and_hasher(num_bits, num_hashes, hash_builder)
+    }
+
+    /// Panics if `num_bits` or `num_hashes` is zero
+    pub fn with_params_and_hasher(num_bits: usize, num_hashes: u32, hash_builder: S) -> Self {
+        assert!(num_bits > 0, "bloom filter must have at least one bit");
+        assert!(num_hashes > 0, "bloom filter must use at least one hash");
+
+        Self {
+            bits: BitSet::with_len(num_bits),
+            num_hashes,
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.bits.num_bits()
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Number of insertions, duplicates included
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
-    pub fn insert<T: Hash>(&mut self, item: &T) {
-        for i in 0..2 {
-            let hash = self.hash(item, i);
-            self.bits |= 1 << (hash % 128);
+    /// Bytes taken by the bit array
+    pub fn memory_usage(&self) -> usize {
+        self.bits.memory_usage()
+    }
+
+    /// Swamidass–Baldi estimate of the number of distinct items inserted,
+    /// `-(m / k) * ln(1 - X / m)` for `X` set bits out of `m`. Infinite once
+    /// every bit is set.
+    pub fn estimate_cardinality(&self) -> f64 {
+        let num_bits = self.num_bits() as f64;
+        let ones = self.count_ones() as f64;
+
+        -(num_bits / self.num_hashes as f64) * (1.0 - ones / num_bits).ln()
+    }
+
+    /// Probability that an item never inserted is reported present,
+    /// `(X / m)^k` for `X` set bits out of `m`
+    pub fn fp_rate(&self) -> f64 {
+        (self.count_ones() as f64 / self.num_bits() as f64).powi(self.num_hashes as i32)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for index in self.indices(item) {
+            self.bits.insert(index);
         }
+        self.len += 1;
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.indices(item).all(|index| self.bits.contains(index))
     }
 
-    pub fn contains<T: Hash>(&self, item: &T) -> bool {
-        for i in 0..2 {
-            let hash = self.hash(item, i);
-            if (self.bits & (1 << (hash % 128))) == 0 {
-                return false;
+    /// Inserts every item, in batches: each batch is hashed and its words
+    /// prefetched before any bit is set, so cache misses overlap instead of
+    /// being paid one item at a time.
+    pub fn insert_many<I>(&mut self, items: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let mut items = items.into_iter();
+        let mut hashes = [(0, 0); BATCH_SIZE];
+
+        loop {
+            let mut count = 0;
+            for (hash, item) in hashes.iter_mut().zip(items.by_ref()) {
+                *hash = hash128(&self.hash_builder, &item);
+                count += 1;
+            }
+
+            let batch = &hashes[..count];
+            self.prefetch_batch(batch);
+
+            for &hash in batch {
+                for index in hash_indices(hash, self.num_hashes, self.num_bits()) {
+                    self.bits.insert(index);
+                }
             }
+            self.len += count;
+
+            if count < BATCH_SIZE {
+                break;
+            }
+        }
+    }
+
+    /// Batched [`contains`](Self::contains), hashing and prefetching like
+    /// [`insert_many`](Self::insert_many). The result is in the order of
+    /// `items`.
+    pub fn contains_many<T: Hash>(&self, items: &[T]) -> Vec<bool> {
+        let mut result = Vec::with_capacity(items.len());
+        let mut hashes = [(0, 0); BATCH_SIZE];
+
+        for chunk in items.chunks(BATCH_SIZE) {
+            for (hash, item) in hashes.iter_mut().zip(chunk) {
+                *hash = hash128(&self.hash_builder, item);
+            }
+
+            let batch = &hashes[..chunk.len()];
+            self.prefetch_batch(batch);
+
+            result.extend(batch.iter().map(|&hash| {
+                hash_indices(hash, self.num_hashes, self.num_bits())
+                    .all(|index| self.bits.contains(index))
+            }));
         }
 
-        true
+        result
     }
 
     pub fn clear(&mut self) {
-        self.bits = 0;
+        self.bits.clear();
+        self.len = 0;
     }
 
-    fn hash<T: Hash>(&self, item: &T, seed: u64) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        item.hash(&mut hasher);
-        seed.hash(&mut hasher);
-        hasher.finish()
+    /// Serializes the filter parameters and bit array. The hasher is not
+    /// stored: the filter must be read back with an identically configured one.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.memory_usage());
+        self.encode(&mut bytes);
+        bytes
+    }
+
+    pub fn from_bytes_with_hasher(bytes: &[u8], hash_builder: S) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let filter = Self::decode(&mut reader, hash_builder)?;
+        reader.finish()?;
+
+        Ok(filter)
+    }
+
+    /// Writes a 40-byte header followed by the bit array as little-endian
+    /// words. The header ends with a checksum over the rest of the header and
+    /// the words.
+    ///
+    /// Version 1 had a 32-byte header without `len` and checksummed only the
+    /// words; it is rejected as unsupported.
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        let words_start = start + HEADER_LEN;
+
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&[0; 3]);
+        out.extend_from_slice(&self.num_hashes.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(self.num_bits() as u64).to_le_bytes());
+        out.extend_from_slice(&(self.len as u64).to_le_bytes());
+        out.extend_from_slice(&[0; 8]);
+
+        for word in self.bits.as_words() {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+
+        let checksum = checksum(&[&out[start..words_start - 8], &out[words_start..]]);
+        out[words_start - 8..words_start].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    pub(crate) fn decode(reader: &mut Reader<'_>, hash_builder: S) -> Result<Self, DecodeError> {
+        let parts = EncodedParts::read(reader)?;
+
+        let bits = parts
+            .words
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+
+        Ok(Self::from_parts(
+            bits,
+            parts.num_bits,
+            parts.num_hashes,
+            parts.len,
+            hash_builder,
+        ))
+    }
+
+    pub(crate) fn from_parts(
+        bits: Vec<u64>,
+        num_bits: usize,
+        num_hashes: u32,
+        len: usize,
+        hash_builder: S,
+    ) -> Self {
+        Self {
+            bits: BitSet::from_words(bits, num_bits),
+            num_hashes,
+            len,
+            hash_builder,
+        }
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<u64>, usize, u32, usize, S) {
+        let num_bits = self.num_bits();
+        (
+            self.bits.into_words(),
+            num_bits,
+            self.num_hashes,
+            self.len,
+            self.hash_builder,
+        )
+    }
+
+    fn indices<T: Hash + ?Sized>(&self, item: &T) -> impl Iterator<Item = usize> + use<T, S> {
+        cell_indices(&self.hash_builder, item, self.num_hashes, self.num_bits())
+    }
+
+    fn prefetch_batch(&self, hashes: &[(u64, u64)]) {
+        for &hash in hashes {
+            for index in hash_indices(hash, self.num_hashes, self.num_bits()) {
+                prefetch(&self.bits.as_words()[index / 64]);
+            }
+        }
+    }
+}
+
+impl<S: BuildHasher + Clone + PartialEq> BloomFilter<S> {
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.num_bits() == other.num_bits()
+            && self.num_hashes == other.num_hashes
+            && self.hash_builder == other.hash_builder
+    }
+
+    /// Filter containing every item of either filter, identical to one built
+    /// by inserting both sets of items
+    pub fn union(&self, other: &Self) -> Result<Self, IncompatibleFilters> {
+        let mut result = self.clone();
+        result.union_with(other)?;
+        Ok(result)
+    }
+
+    /// Filter containing the items of both filters. It may report more false
+    /// positives than a filter built from the intersection of the two sets.
+    pub fn intersect(&self, other: &Self) -> Result<Self, IncompatibleFilters> {
+        let mut result = self.clone();
+        result.intersect_with(other)?;
+        Ok(result)
+    }
+
+    /// The resulting length is the sum of both lengths
+    pub fn union_with(&mut self, other: &Self) -> Result<(), IncompatibleFilters> {
+        self.combine_with(other, BitSet::union_with)?;
+        self.len = self.len.saturating_add(other.len);
+        Ok(())
+    }
+
+    /// The resulting length is the smaller of both lengths
+    pub fn intersect_with(&mut self, other: &Self) -> Result<(), IncompatibleFilters> {
+        self.combine_with(other, BitSet::intersect_with)?;
+        self.len = self.len.min(other.len);
+        Ok(())
+    }
+
+    pub fn estimate_union_size(&self, other: &Self) -> Result<f64, IncompatibleFilters> {
+        Ok(self.union(other)?.estimate_cardinality())
+    }
+
+    /// Inclusion-exclusion over the cardinality estimates of both filters and
+    /// their union
+    pub fn estimate_intersection_size(&self, other: &Self) -> Result<f64, IncompatibleFilters> {
+        let union = self.estimate_union_size(other)?;
+        let intersection = self.estimate_cardinality() + other.estimate_cardinality() - union;
+
+        Ok(intersection.max(0.0))
+    }
+
+    /// Estimated Jaccard similarity of the two underlying sets
+    pub fn jaccard_estimate(&self, other: &Self) -> Result<f64, IncompatibleFilters> {
+        let union = self.estimate_union_size(other)?;
+        if union == 0.0 {
+            return Ok(0.0);
+        }
+
+        let intersection = self.estimate_intersection_size(other)?;
+
+        Ok((intersection / union).min(1.0))
+    }
+
+    fn combine_with(
+        &mut self,
+        other: &Self,
+        combine: impl Fn(&mut BitSet, &BitSet),
+    ) -> Result<(), IncompatibleFilters> {
+        if !self.is_compatible(other) {
+            return Err(IncompatibleFilters);
+        }
+
+        combine(&mut self.bits, &other.bits);
+
+        Ok(())
+    }
+}
+
+impl<S: Clone> Clone for BloomFilter<S> {
+    fn clone(&self) -> Self {
+        Self {
+            bits: self.bits.clone(),
+            num_hashes: self.num_hashes,
+            len: self.len,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+/// Panics if the filters are not compatible, see [`BloomFilter::union_with`]
+impl<S: BuildHasher + Clone + PartialEq> BitOrAssign<&BloomFilter<S>> for BloomFilter<S> {
+    fn bitor_assign(&mut self, other: &BloomFilter<S>) {
+        self.union_with(other)
+            .expect("cannot union bloom filters with different parameters");
+    }
+}
+
+/// Panics if the filters are not compatible, see [`BloomFilter::intersect_with`]
+impl<S: BuildHasher + Clone + PartialEq> BitAndAssign<&BloomFilter<S>> for BloomFilter<S> {
+    fn bitand_assign(&mut self, other: &BloomFilter<S>) {
+        self.intersect_with(other)
+            .expect("cannot intersect bloom filters with different parameters");
     }
 }
 
@@ -46,20 +399,78 @@ impl Default for BloomFilter {
     }
 }
 
+/// Validated contents of a serialized [`BloomFilter`], borrowing its words
+pub(crate) struct EncodedParts<'a> {
+    pub(crate) num_bits: usize,
+    pub(crate) num_hashes: u32,
+    pub(crate) len: usize,
+    /// The bit array as little-endian words
+    pub(crate) words: &'a [u8],
+}
+
+impl<'a> EncodedParts<'a> {
+    /// Reads and checks the header, the checksum and the padding bits
+    pub(crate) fn read(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let header = reader.read_bytes(HEADER_LEN - 8)?;
+        let mut header_reader = Reader::new(header);
+
+        let version = header_reader.read_header(MAGIC)?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        header_reader.read_bytes(3)?;
+        let num_hashes = header_reader.read_u32()?;
+        header_reader.read_bytes(4)?;
+        let num_bits = header_reader.read_usize()?;
+        let len = header_reader.read_usize()?;
+        let expected_checksum = reader.read_u64()?;
+
+        if num_hashes == 0 || num_bits == 0 {
+            return Err(DecodeError::Invalid("empty bloom filter parameters"));
+        }
+
+        let num_words = num_bits.div_ceil(64);
+        if reader.remaining() / 8 < num_words {
+            return Err(DecodeError::Truncated);
+        }
+
+        let words = reader.read_bytes(num_words * 8)?;
+        if checksum(&[header, words]) != expected_checksum {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        let last_word = u64::from_le_bytes(words[words.len() - 8..].try_into().unwrap());
+        if num_bits % 64 != 0 && last_word >> (num_bits % 64) != 0 {
+            return Err(DecodeError::Invalid("bits set past the end of the filter"));
+        }
+
+        Ok(Self {
+            num_bits,
+            num_hashes,
+            len,
+            words,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hash::{BuildMurmur3Hasher, BuildSipHasher24, BuildXxHash64};
+
+    const STABLE_BITS: [u64; 2] = [0x0000_0041_0000_0000, 0x0800_0000_0008_0000];
 
     #[test]
     fn test_new_bloom_filter() {
         let filter = BloomFilter::new();
-        assert_eq!(filter.bits, 0);
+        assert!(filter.bits.is_clear());
     }
 
     #[test]
     fn test_default() {
         let filter = BloomFilter::default();
-        assert_eq!(filter.bits, 0);
+        assert!(filter.bits.is_clear());
     }
 
     #[test]
@@ -95,7 +506,7 @@ mod tests {
         assert!(filter.contains(&42));
 
         filter.clear();
-        assert_eq!(filter.bits, 0);
+        assert!(filter.bits.is_clear());
     }
 
     #[test]
@@ -128,4 +539,275 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_custom_hashers() {
+        let mut sip = BloomFilter::with_hasher(BuildSipHasher24::with_keys(1, 2));
+        let mut murmur = BloomFilter::with_hasher(BuildMurmur3Hasher::with_seed(3));
+
+        for i in 0..10 {
+            sip.insert(&i);
+            murmur.insert(&i);
+        }
+
+        for i in 0..10 {
+            assert!(sip.contains(&i));
+            assert!(murmur.contains(&i));
+        }
+    }
+
+    #[test]
+    fn test_params() {
+        let filter = BloomFilter::with_params(1000, 7);
+        assert_eq!(filter.num_bits(), 1000);
+        assert_eq!(filter.num_hashes(), 7);
+        assert_eq!(filter.bits.as_words().len(), 16);
+
+        let filter = BloomFilter::new();
+        assert_eq!(filter.num_bits(), 128);
+        assert_eq!(filter.num_hashes(), 2);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let mut filter = BloomFilter::with_capacity(1_000, 0.01);
+        assert_eq!(filter.num_bits(), 9_586);
+        assert_eq!(filter.num_hashes(), 7);
+
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+
+        let false_positives = (1_000..11_000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 200, "{false_positives}");
+    }
+
+    #[test]
+    fn test_batch_matches_single() {
+        let mut single = BloomFilter::with_capacity(1_000, 0.01);
+        let mut batch = BloomFilter::with_capacity(1_000, 0.01);
+
+        // Not a multiple of the batch size
+        for i in 0..1_000u32 {
+            single.insert(&i);
+        }
+        batch.insert_many(0..1_000u32);
+
+        assert_eq!(batch.bits, single.bits);
+        assert_eq!(batch.len(), 1_000);
+
+        let queries: Vec<u32> = (500..5_000).collect();
+        let expected: Vec<bool> = queries.iter().map(|i| single.contains(i)).collect();
+        assert_eq!(batch.contains_many(&queries), expected);
+        assert!(
+            batch
+                .contains_many(&queries[..500])
+                .iter()
+                .all(|&found| found)
+        );
+    }
+
+    #[test]
+    fn test_batch_empty_input() {
+        let mut filter = BloomFilter::new();
+        filter.insert_many(Vec::<u32>::new());
+
+        assert!(filter.is_empty());
+        assert!(filter.contains_many::<u32>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut filter = BloomFilter::with_capacity(1_000, 0.01);
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + filter.memory_usage());
+
+        let decoded = BloomFilter::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.num_bits(), filter.num_bits());
+        assert_eq!(decoded.num_hashes(), filter.num_hashes());
+        assert_eq!(decoded.bits, filter.bits);
+        assert_eq!(decoded.len(), 1_000);
+    }
+
+    #[test]
+    fn test_deserialization_errors() {
+        let mut filter = BloomFilter::with_params(100, 3);
+        filter.insert(&"hello");
+        let bytes = filter.to_bytes();
+
+        assert_eq!(
+            BloomFilter::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::Truncated)
+        );
+        assert_eq!(
+            BloomFilter::from_bytes(&bytes[..10]).err(),
+            Some(DecodeError::Truncated)
+        );
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            BloomFilter::from_bytes(&corrupted).err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[0] = b'X';
+        assert_eq!(
+            BloomFilter::from_bytes(&corrupted).err(),
+            Some(DecodeError::BadMagic)
+        );
+
+        for version in [1, 9] {
+            let mut corrupted = bytes.clone();
+            corrupted[4] = version;
+            assert_eq!(
+                BloomFilter::from_bytes(&corrupted).err(),
+                Some(DecodeError::UnsupportedVersion(version))
+            );
+        }
+
+        let mut extended = bytes;
+        extended.push(0);
+        assert!(BloomFilter::from_bytes(&extended).is_err());
+    }
+
+    #[test]
+    fn test_union_and_intersect() {
+        let mut a = BloomFilter::with_capacity(1_000, 0.01);
+        let mut b = BloomFilter::with_capacity(1_000, 0.01);
+        let mut both = BloomFilter::with_capacity(1_000, 0.01);
+
+        for i in 0..300 {
+            a.insert(&i);
+            both.insert(&i);
+        }
+        for i in 200..500 {
+            b.insert(&i);
+            both.insert(&i);
+        }
+
+        let union = a.union(&b).unwrap();
+        assert_eq!(union.bits, both.bits);
+        assert_eq!(union.len(), 600);
+
+        let intersection = a.intersect(&b).unwrap();
+        assert_eq!(intersection.len(), 300);
+        for i in 200..300 {
+            assert!(intersection.contains(&i));
+        }
+
+        let mut in_place = a.clone();
+        in_place |= &b;
+        assert_eq!(in_place.bits, union.bits);
+
+        let mut in_place = a.clone();
+        in_place &= &b;
+        assert_eq!(in_place.bits, intersection.bits);
+    }
+
+    #[test]
+    fn test_incompatible_filters() {
+        let a = BloomFilter::with_params(1_000, 7);
+
+        assert_eq!(
+            a.union(&BloomFilter::with_params(1_001, 7)).err(),
+            Some(IncompatibleFilters)
+        );
+        assert_eq!(
+            a.intersect(&BloomFilter::with_params(1_000, 6)).err(),
+            Some(IncompatibleFilters)
+        );
+
+        let seeded = BloomFilter::with_params_and_hasher(1_000, 7, BuildXxHash64::with_seed(1));
+        let mut other = BloomFilter::with_params_and_hasher(1_000, 7, BuildXxHash64::with_seed(2));
+        assert!(other.union_with(&seeded).is_err());
+        assert!(other.jaccard_estimate(&seeded).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_incompatible_operator_panics() {
+        let mut a = BloomFilter::with_params(1_000, 7);
+        a |= &BloomFilter::with_params(2_000, 7);
+    }
+
+    #[test]
+    fn test_cardinality_estimates() {
+        let mut a = BloomFilter::with_capacity(10_000, 0.01);
+        let mut b = BloomFilter::with_capacity(10_000, 0.01);
+
+        assert_eq!(a.estimate_cardinality(), 0.0);
+        assert_eq!(a.jaccard_estimate(&b), Ok(0.0));
+
+        for i in 0..6_000 {
+            a.insert(&i);
+        }
+        for i in 3_000..9_000 {
+            b.insert(&i);
+        }
+
+        let close = |estimate: f64, expected: f64| (estimate - expected).abs() < expected * 0.05;
+
+        assert!(close(a.estimate_cardinality(), 6_000.0));
+        assert!(close(a.estimate_union_size(&b).unwrap(), 9_000.0));
+        assert!(close(a.estimate_intersection_size(&b).unwrap(), 3_000.0));
+        assert!(close(a.jaccard_estimate(&b).unwrap(), 1.0 / 3.0));
+    }
+
+    #[test]
+    fn test_saturated_cardinality_is_infinite() {
+        let mut filter = BloomFilter::with_params(8, 1);
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+
+        assert_eq!(filter.count_ones(), 8);
+        assert!(filter.estimate_cardinality().is_infinite());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_bits_panics() {
+        BloomFilter::with_params(0, 3);
+    }
+
+    #[test]
+    fn test_many_hashes_no_false_negatives() {
+        for num_hashes in [1, 7, 13, 20] {
+            let mut filter = BloomFilter::with_params(10_000, num_hashes);
+
+            for i in 0..500 {
+                filter.insert(&i);
+            }
+
+            for i in 0..500 {
+                assert!(filter.contains(&i));
+            }
+
+            let false_positives = (500..10_500).filter(|i| filter.contains(i)).count();
+            assert!(
+                false_positives < 1000,
+                "k = {num_hashes}: {false_positives}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bits_are_stable() {
+        let mut filter = BloomFilter::new();
+        filter.insert(&"hello");
+        filter.insert(&42u64);
+
+        let mut other = BloomFilter::with_hasher(BuildXxHash64::with_seed(0));
+        other.insert(&"hello");
+        other.insert(&42u64);
+
+        assert_eq!(filter.bits, other.bits);
+        assert_eq!(filter.bits.as_words(), STABLE_BITS);
+    }
 }
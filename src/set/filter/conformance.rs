@@ -0,0 +1,150 @@
+//! Checks every `Filter` implementation has to pass
+
+use crate::set::filter::{
+    AtomicBloomFilter, BlockedBloomFilter, BloomFilter, CounterWidth, CountingBloomFilter,
+    CuckooFilter, DeletableFilter, Filter, QuotientFilter, ScalableBloomFilter,
+};
+
+const NUM_ITEMS: u64 = 10_000;
+const NUM_PROBES: u64 = 100_000;
+
+/// Keys of the probe set never collide with inserted ones
+fn absent_key(i: u64) -> u64 {
+    i | 1 << 63
+}
+
+/// Fills `filter` to its configured load and checks membership, length,
+/// false positive rate and clearing. `target_fp_rate` is the rate the filter
+/// was sized for.
+fn check_filter<F: Filter>(mut filter: F, target_fp_rate: f64) {
+    assert!(filter.is_empty());
+    assert!(filter.memory_bytes() > 0);
+    assert_eq!(filter.expected_fp_rate(), 0.0);
+
+    for i in 0..NUM_ITEMS {
+        filter.insert(&i).unwrap();
+    }
+
+    for i in 0..NUM_ITEMS {
+        assert!(filter.contains(&i), "false negative for {i}");
+    }
+
+    // Filters that skip items they already report as present count fewer
+    let len = filter.len() as u64;
+    assert!((NUM_ITEMS * 99 / 100..=NUM_ITEMS).contains(&len), "{len}");
+
+    let false_positives = (0..NUM_PROBES)
+        .filter(|&i| filter.contains(&absent_key(i)))
+        .count();
+    let measured = false_positives as f64 / NUM_PROBES as f64;
+    let expected = filter.expected_fp_rate();
+
+    assert!(
+        measured <= 1.5 * target_fp_rate,
+        "measured {measured}, target {target_fp_rate}"
+    );
+    assert!(
+        expected <= 1.5 * target_fp_rate,
+        "expected {expected}, target {target_fp_rate}"
+    );
+
+    // Five standard deviations of the binomial count, plus some slack for
+    // the approximations in the rate models
+    let tolerance = 5.0 * (expected * (1.0 - expected) / NUM_PROBES as f64).sqrt();
+    assert!(
+        (measured - expected).abs() <= tolerance + 0.2 * expected,
+        "measured {measured}, expected {expected}"
+    );
+
+    filter.clear();
+    assert!(filter.is_empty());
+    assert_eq!(filter.expected_fp_rate(), 0.0);
+    assert!((0..NUM_ITEMS).all(|i| !filter.contains(&i)));
+}
+
+/// Removes half of the inserted items and checks the other half survives
+fn check_deletable<F: DeletableFilter>(mut filter: F) {
+    for i in 0..NUM_ITEMS {
+        filter.insert(&i).unwrap();
+    }
+    let len = filter.len();
+
+    for i in (0..NUM_ITEMS).step_by(2) {
+        assert!(filter.remove(&i), "failed to remove {i}");
+    }
+    assert_eq!(filter.len(), len - NUM_ITEMS as usize / 2);
+
+    for i in (1..NUM_ITEMS).step_by(2) {
+        assert!(filter.contains(&i), "false negative for {i}");
+    }
+
+    let still_present = (0..NUM_ITEMS)
+        .step_by(2)
+        .filter(|i| filter.contains(i))
+        .count();
+    assert!(still_present < NUM_ITEMS as usize / 20, "{still_present}");
+}
+
+#[test]
+fn test_bloom_filter() {
+    check_filter(BloomFilter::with_capacity(NUM_ITEMS as usize, 0.01), 0.01);
+}
+
+#[test]
+fn test_atomic_bloom_filter() {
+    check_filter(
+        AtomicBloomFilter::with_capacity(NUM_ITEMS as usize, 0.01),
+        0.01,
+    );
+}
+
+#[test]
+fn test_blocked_bloom_filter() {
+    check_filter(
+        BlockedBloomFilter::with_capacity(NUM_ITEMS as usize, 0.01),
+        0.01,
+    );
+}
+
+#[test]
+fn test_counting_bloom_filter() {
+    for width in [CounterWidth::Four, CounterWidth::Eight] {
+        check_filter(
+            CountingBloomFilter::with_capacity(NUM_ITEMS as usize, 0.01, width),
+            0.01,
+        );
+        check_deletable(CountingBloomFilter::with_capacity(
+            NUM_ITEMS as usize,
+            0.001,
+            width,
+        ));
+    }
+}
+
+#[test]
+fn test_scalable_bloom_filter() {
+    // Starts small enough to grow several slices
+    check_filter(
+        ScalableBloomFilter::new(NUM_ITEMS as usize / 16, 0.01),
+        0.01,
+    );
+}
+
+#[test]
+fn test_cuckoo_filter() {
+    let filter = CuckooFilter::with_params(NUM_ITEMS as usize, 12, 4);
+    let target = filter.fp_rate_bound();
+    check_filter(filter, target);
+
+    check_deletable(CuckooFilter::new(NUM_ITEMS as usize));
+}
+
+#[test]
+fn test_quotient_filter() {
+    check_filter(
+        QuotientFilter::with_capacity(NUM_ITEMS as usize, 0.01),
+        0.01,
+    );
+
+    check_deletable(QuotientFilter::with_capacity(NUM_ITEMS as usize, 0.01));
+}
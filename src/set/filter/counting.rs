@@ -0,0 +1,372 @@
+use crate::hash::DefaultBuildHasher;
+use crate::set::filter::BloomFilter;
+use crate::set::filter::params::{optimal_num_cells, optimal_num_hashes};
+use crate::set::filter::probe::cell_indices;
+use std::hash::{BuildHasher, Hash};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterWidth {
+    Four,
+    Eight,
+}
+
+impl CounterWidth {
+    pub fn max_count(self) -> u8 {
+        match self {
+            CounterWidth::Four => 0x0f,
+            CounterWidth::Eight => 0xff,
+        }
+    }
+
+    fn cells_per_byte(self) -> usize {
+        match self {
+            CounterWidth::Four => 2,
+            CounterWidth::Eight => 1,
+        }
+    }
+}
+
+/// Bloom filter with a small saturating counter per cell, which makes
+/// removal possible.
+///
+/// A counter that reaches its maximum sticks there: it is never decremented
+/// again, since its true value is no longer known. Such cells can only cause
+/// false positives, never false negatives.
+pub struct CountingBloomFilter<S = DefaultBuildHasher> {
+    counters: Vec<u8>,
+    num_cells: usize,
+    num_hashes: u32,
+    width: CounterWidth,
+    overflowed: bool,
+    len: usize,
+    hash_builder: S,
+}
+
+impl CountingBloomFilter {
+    pub fn with_params(num_cells: usize, num_hashes: u32, width: CounterWidth) -> Self {
+        Self::with_params_and_hasher(num_cells, num_hashes, width, DefaultBuildHasher::default())
+    }
+
+    /// Sizes the filter to hold `expected_items` at the given false positive rate
+    pub fn with_capacity(expected_items: usize, fp_rate: f64, width: CounterWidth) -> Self {
+        Self::with_capacity_and_hasher(
+            expected_items,
+            fp_rate,
+            width,
+            DefaultBuildHasher::default(),
+        )
+    }
+}
+
+impl<S: BuildHasher> CountingBloomFilter<S> {
+    /// Panics unless `0 < fp_rate < 1`
+    pub fn with_capacity_and_hasher(
+        expected_items: usize,
+        fp_rate: f64,
+        width: CounterWidth,
+        hash_builder: S,
+    ) -> Self {
+        let num_cells = optimal_num_cells(expected_items, fp_rate);
+        let num_hashes = optimal_num_hashes(num_cells, expected_items);
+
+        Self::with_params_and_hasher(num_cells, num_hashes, width, hash_builder)
+    }
+
+    /// Panics if `num_cells` or `num_hashes` is zero
+    pub fn with_params_and_hasher(
+        num_cells: usize,
+        num_hashes: u32,
+        width: CounterWidth,
+        hash_builder: S,
+    ) -> Self {
+        assert!(
+            num_cells > 0,
+            "counting bloom filter must have at least one cell"
+        );
+        assert!(
+            num_hashes > 0,
+            "counting bloom filter must use at least one hash"
+        );
+
+        Self {
+            counters: vec![0; num_cells.div_ceil(width.cells_per_byte())],
+            num_cells,
+            num_hashes,
+            width,
+            overflowed: false,
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.num_cells
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    pub fn counter_width(&self) -> CounterWidth {
+        self.width
+    }
+
+    /// Insertions minus successful removals
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes taken by the counters
+    pub fn memory_usage(&self) -> usize {
+        self.counters.len()
+    }
+
+    /// Probability that an item never inserted is reported present,
+    /// `(X / m)^k` for `X` non-zero counters out of `m`
+    pub fn fp_rate(&self) -> f64 {
+        let nonzero = (0..self.num_cells)
+            .filter(|&index| self.counter(index) != 0)
+            .count();
+
+        (nonzero as f64 / self.num_cells as f64).powi(self.num_hashes as i32)
+    }
+
+    /// Returns `true` once any counter has saturated. From then on counts for
+    /// the affected cells are lower bounds and removals leave them untouched.
+    pub fn has_overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let max_count = self.width.max_count();
+
+        for index in self.indices(item) {
+            let count = self.counter(index);
+            if count < max_count {
+                self.set_counter(index, count + 1);
+                self.overflowed |= count + 1 == max_count;
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Removes one occurrence of `item`.
+    ///
+    /// Returns `false` and leaves the filter untouched if `item` is definitely
+    /// absent. Removing an item that was never inserted but happens to be a
+    /// false positive corrupts the filter and can cause false negatives.
+    pub fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+
+        let max_count = self.width.max_count();
+
+        for index in self.indices(item) {
+            let count = self.counter(index);
+            // A repeated index can reach zero before its last probe when
+            // `item` is a false positive
+            if count != 0 && count < max_count {
+                self.set_counter(index, count - 1);
+            }
+        }
+        self.len = self.len.saturating_sub(1);
+
+        true
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.indices(item).all(|index| self.counter(index) != 0)
+    }
+
+    /// Upper bound on how many times `item` was inserted (minus removals),
+    /// unless the smallest of its counters has saturated.
+    pub fn count_estimate<T: Hash + ?Sized>(&self, item: &T) -> u8 {
+        self.indices(item)
+            .map(|index| self.counter(index))
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+        self.overflowed = false;
+        self.len = 0;
+    }
+
+    /// Plain Bloom filter with a bit set for every non-zero counter. It answers
+    /// `contains` exactly like this filter in a fraction of the memory.
+    pub fn to_bloom_filter(&self) -> BloomFilter<S>
+    where
+        S: Clone,
+    {
+        let mut bits = vec![0u64; self.num_cells.div_ceil(64)];
+
+        for index in 0..self.num_cells {
+            if self.counter(index) != 0 {
+                bits[index / 64] |= 1 << (index % 64);
+            }
+        }
+
+        BloomFilter::from_parts(
+            bits,
+            self.num_cells,
+            self.num_hashes,
+            self.len,
+            self.hash_builder.clone(),
+        )
+    }
+
+    fn indices<T: Hash + ?Sized>(&self, item: &T) -> impl Iterator<Item = usize> + use<T, S> {
+        cell_indices(&self.hash_builder, item, self.num_hashes, self.num_cells)
+    }
+
+    fn counter(&self, index: usize) -> u8 {
+        match self.width {
+            CounterWidth::Four => (self.counters[index / 2] >> (4 * (index % 2))) & 0x0f,
+            CounterWidth::Eight => self.counters[index],
+        }
+    }
+
+    fn set_counter(&mut self, index: usize, count: u8) {
+        match self.width {
+            CounterWidth::Four => {
+                let shift = 4 * (index % 2);
+                let byte = &mut self.counters[index / 2];
+                *byte = (*byte & !(0x0f << shift)) | (count << shift);
+            }
+            CounterWidth::Eight => self.counters[index] = count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_remove() {
+        for width in [CounterWidth::Four, CounterWidth::Eight] {
+            let mut filter = CountingBloomFilter::with_capacity(100, 0.01, width);
+
+            filter.insert(&"hello");
+            filter.insert(&"world");
+            assert!(filter.contains(&"hello"));
+            assert!(filter.contains(&"world"));
+
+            assert!(filter.remove(&"hello"));
+            assert!(!filter.contains(&"hello"));
+            assert!(filter.contains(&"world"));
+
+            assert!(!filter.remove(&"hello"));
+            assert!(filter.contains(&"world"));
+        }
+    }
+
+    #[test]
+    fn test_count_estimate() {
+        let mut filter = CountingBloomFilter::with_capacity(100, 0.01, CounterWidth::Eight);
+
+        assert_eq!(filter.count_estimate(&7), 0);
+
+        for _ in 0..5 {
+            filter.insert(&7);
+        }
+        assert_eq!(filter.count_estimate(&7), 5);
+
+        filter.remove(&7);
+        assert_eq!(filter.count_estimate(&7), 4);
+    }
+
+    #[test]
+    fn test_four_bit_counters_are_packed() {
+        let mut filter = CountingBloomFilter::with_params(100, 3, CounterWidth::Four);
+        assert_eq!(filter.counters.len(), 50);
+
+        for index in 0..100 {
+            filter.set_counter(index, (index % 16) as u8);
+        }
+        for index in 0..100 {
+            assert_eq!(filter.counter(index), (index % 16) as u8);
+        }
+    }
+
+    #[test]
+    fn test_overflow_saturates() {
+        let mut filter = CountingBloomFilter::with_params(64, 3, CounterWidth::Four);
+
+        for _ in 0..14 {
+            filter.insert(&"hot");
+        }
+        assert!(!filter.has_overflowed());
+
+        for _ in 0..10 {
+            filter.insert(&"hot");
+        }
+        assert!(filter.has_overflowed());
+        assert_eq!(filter.count_estimate(&"hot"), 15);
+
+        // Saturated counters are never decremented, so the item stays present
+        for _ in 0..30 {
+            filter.remove(&"hot");
+        }
+        assert!(filter.contains(&"hot"));
+
+        filter.clear();
+        assert!(!filter.has_overflowed());
+        assert!(!filter.contains(&"hot"));
+    }
+
+    #[test]
+    fn test_remove_false_positive_with_repeated_index() {
+        // Every probe of a single-cell filter lands on the same counter
+        let mut filter = CountingBloomFilter::with_params(1, 2, CounterWidth::Eight);
+        filter.set_counter(0, 1);
+
+        assert!(filter.remove(&"never inserted"));
+        assert_eq!(filter.counter(0), 0);
+    }
+
+    #[test]
+    fn test_no_false_negatives_after_removals() {
+        let mut filter = CountingBloomFilter::with_capacity(1_000, 0.01, CounterWidth::Four);
+
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+        for i in (0..1_000).step_by(2) {
+            assert!(filter.remove(&i));
+        }
+        for i in (1..1_000).step_by(2) {
+            assert!(filter.contains(&i));
+        }
+    }
+
+    #[test]
+    fn test_to_bloom_filter() {
+        let mut filter = CountingBloomFilter::with_capacity(1_000, 0.01, CounterWidth::Four);
+
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+        for i in 0..100 {
+            filter.remove(&i);
+        }
+
+        let bloom = filter.to_bloom_filter();
+        assert_eq!(bloom.num_bits(), filter.num_cells());
+        assert_eq!(bloom.num_hashes(), filter.num_hashes());
+
+        for i in 0..5_000 {
+            assert_eq!(bloom.contains(&i), filter.contains(&i));
+        }
+    }
+}
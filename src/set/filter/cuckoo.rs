@@ -0,0 +1,361 @@
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::filter::FilterFull;
+use crate::set::filter::packed::PackedArray;
+use std::hash::{BuildHasher, Hash};
+
+const DEFAULT_FINGERPRINT_BITS: u32 = 16;
+const DEFAULT_BUCKET_SIZE: usize = 4;
+const MAX_KICKS: usize = 500;
+const MAX_LOAD_FACTOR: f64 = 0.96;
+
+/// Cuckoo filter (Fan et al., "Cuckoo Filter: Practically Better Than
+/// Bloom") storing an `f`-bit fingerprint per item in one of two buckets.
+///
+/// The alternate bucket of a fingerprint is derived from the fingerprint
+/// alone (partial-key cuckoo hashing), so items can be relocated and removed
+/// without access to the original keys. The false positive rate is bounded
+/// by `2 * bucket_size / 2^f`.
+pub struct CuckooFilter<S = DefaultBuildHasher> {
+    slots: PackedArray,
+    bucket_size: usize,
+    bucket_mask: usize,
+    len: usize,
+    rng_state: u64,
+    hash_builder: S,
+}
+
+impl CuckooFilter {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_params(capacity, DEFAULT_FINGERPRINT_BITS, DEFAULT_BUCKET_SIZE)
+    }
+
+    pub fn with_params(capacity: usize, fingerprint_bits: u32, bucket_size: usize) -> Self {
+        Self::with_params_and_hasher(
+            capacity,
+            fingerprint_bits,
+            bucket_size,
+            DefaultBuildHasher::default(),
+        )
+    }
+}
+
+impl<S: BuildHasher> CuckooFilter<S> {
+    pub fn with_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self::with_params_and_hasher(
+            capacity,
+            DEFAULT_FINGERPRINT_BITS,
+            DEFAULT_BUCKET_SIZE,
+            hash_builder,
+        )
+    }
+
+    /// Allocates a power of two number of buckets, enough to stay under a
+    /// 96% load factor at `capacity` items.
+    ///
+    /// Panics unless `1 <= fingerprint_bits <= 32` and `bucket_size > 0`
+    pub fn with_params_and_hasher(
+        capacity: usize,
+        fingerprint_bits: u32,
+        bucket_size: usize,
+        hash_builder: S,
+    ) -> Self {
+        assert!(
+            (1..=32).contains(&fingerprint_bits),
+            "fingerprint must have 1 to 32 bits"
+        );
+        assert!(
+            bucket_size > 0,
+            "buckets must hold at least one fingerprint"
+        );
+
+        let mut num_buckets = capacity.div_ceil(bucket_size).max(1).next_power_of_two();
+        if capacity as f64 / (num_buckets * bucket_size) as f64 > MAX_LOAD_FACTOR {
+            num_buckets *= 2;
+        }
+
+        Self {
+            slots: PackedArray::new(num_buckets * bucket_size, fingerprint_bits),
+            bucket_size,
+            bucket_mask: num_buckets - 1,
+            len: 0,
+            rng_state: 0x2545_f491_4f6c_dd1d,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn fingerprint_bits(&self) -> u32 {
+        self.slots.width()
+    }
+
+    pub fn bucket_size(&self) -> usize {
+        self.bucket_size
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.bucket_mask + 1
+    }
+
+    /// Total number of fingerprint slots
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes taken by the fingerprint table
+    pub fn memory_usage(&self) -> usize {
+        self.slots.memory_usage()
+    }
+
+    /// Upper bound on the false positive rate, `2 * bucket_size / 2^f`
+    pub fn fp_rate_bound(&self) -> f64 {
+        (2 * self.bucket_size) as f64 / (1u64 << self.fingerprint_bits()) as f64
+    }
+
+    /// Approximate false positive rate at the current load: a lookup compares
+    /// against the occupied slots of two buckets, each matching with
+    /// probability `1 / (2^f - 1)`
+    pub fn fp_rate(&self) -> f64 {
+        let compared = (2 * self.bucket_size * self.len) as f64 / self.capacity() as f64;
+        let miss = 1.0 - 1.0 / ((1u64 << self.fingerprint_bits()) - 1) as f64;
+
+        1.0 - miss.powf(compared)
+    }
+
+    /// Stores a fingerprint of `item`, relocating up to 500 existing ones to
+    /// make room. On failure the filter is left exactly as it was.
+    ///
+    /// Inserting the same item twice stores two fingerprints, both of which
+    /// have to be removed before the item is reported absent.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        let (index, fingerprint) = self.index_and_fingerprint(item);
+        let alt_index = self.alt_index(index, fingerprint);
+
+        if self.try_place(index, fingerprint) || self.try_place(alt_index, fingerprint) {
+            self.len += 1;
+            return Ok(());
+        }
+
+        let mut evictions = Vec::with_capacity(MAX_KICKS);
+        let mut fingerprint = fingerprint;
+        let mut index = if self.next_random() & 1 == 0 {
+            index
+        } else {
+            alt_index
+        };
+
+        for _ in 0..MAX_KICKS {
+            let slot = index * self.bucket_size + self.next_random() as usize % self.bucket_size;
+            let victim = self.slots.get(slot);
+            self.slots.set(slot, fingerprint);
+            evictions.push(slot);
+
+            fingerprint = victim;
+            index = self.alt_index(index, fingerprint);
+
+            if self.try_place(index, fingerprint) {
+                self.len += 1;
+                return Ok(());
+            }
+        }
+
+        // Put every evicted fingerprint back where it came from
+        for &slot in evictions.iter().rev() {
+            let displaced = self.slots.get(slot);
+            self.slots.set(slot, fingerprint);
+            fingerprint = displaced;
+        }
+
+        Err(FilterFull)
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let (index, fingerprint) = self.index_and_fingerprint(item);
+
+        self.find(index, fingerprint).is_some()
+            || self
+                .find(self.alt_index(index, fingerprint), fingerprint)
+                .is_some()
+    }
+
+    /// Removes one fingerprint of `item`. Removing an item that was never
+    /// inserted may delete the fingerprint of another one.
+    pub fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let (index, fingerprint) = self.index_and_fingerprint(item);
+
+        let slot = self
+            .find(index, fingerprint)
+            .or_else(|| self.find(self.alt_index(index, fingerprint), fingerprint));
+
+        match slot {
+            Some(slot) => {
+                self.slots.set(slot, 0);
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+
+    /// Fingerprint 0 marks an empty slot, so fingerprints are in `1..2^f`
+    fn index_and_fingerprint<T: Hash + ?Sized>(&self, item: &T) -> (usize, u64) {
+        let (h1, h2) = hash128(&self.hash_builder, item);
+        let max_fingerprint = (1u64 << self.fingerprint_bits()) - 1;
+
+        ((h1 as usize) & self.bucket_mask, h2 % max_fingerprint + 1)
+    }
+
+    fn alt_index(&self, index: usize, fingerprint: u64) -> usize {
+        let hash = fingerprint.wrapping_mul(0xc6a4_a793_5bd1_e995);
+        (index ^ (hash >> 32) as usize) & self.bucket_mask
+    }
+
+    fn bucket_slots(&self, index: usize) -> std::ops::Range<usize> {
+        index * self.bucket_size..(index + 1) * self.bucket_size
+    }
+
+    fn find(&self, index: usize, fingerprint: u64) -> Option<usize> {
+        self.bucket_slots(index)
+            .find(|&slot| self.slots.get(slot) == fingerprint)
+    }
+
+    fn try_place(&mut self, index: usize, fingerprint: u64) -> bool {
+        match self.find(index, 0) {
+            Some(slot) => {
+                self.slots.set(slot, fingerprint);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// xorshift64*, only used to pick eviction victims
+    fn next_random(&mut self) -> u64 {
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        self.rng_state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizing() {
+        let filter = CuckooFilter::new(900);
+        assert_eq!(filter.num_buckets(), 256);
+        assert_eq!(filter.capacity(), 1_024);
+        assert_eq!(filter.memory_usage(), 2_048);
+
+        // 1_000 / 1_024 is above the maximum load factor
+        let filter = CuckooFilter::with_params(1_000, 12, 2);
+        assert_eq!(filter.num_buckets(), 1_024);
+
+        let filter = CuckooFilter::with_params(990, 8, 4);
+        assert_eq!(filter.num_buckets(), 512);
+    }
+
+    #[test]
+    fn test_insert_contains_remove() {
+        let mut filter = CuckooFilter::new(1_000);
+        assert!(filter.is_empty());
+
+        for i in 0..900 {
+            filter.insert(&i).unwrap();
+        }
+        assert_eq!(filter.len(), 900);
+
+        for i in 0..900 {
+            assert!(filter.contains(&i));
+        }
+
+        for i in 0..450 {
+            assert!(filter.remove(&i));
+        }
+        assert_eq!(filter.len(), 450);
+
+        for i in 450..900 {
+            assert!(filter.contains(&i));
+        }
+
+        let false_positives = (0..450).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 5);
+
+        filter.clear();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&500));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let mut filter = CuckooFilter::new(100);
+
+        filter.insert(&"hello").unwrap();
+        filter.insert(&"hello").unwrap();
+        assert_eq!(filter.len(), 2);
+
+        assert!(filter.remove(&"hello"));
+        assert!(filter.contains(&"hello"));
+        assert!(filter.remove(&"hello"));
+        assert!(!filter.contains(&"hello"));
+        assert!(!filter.remove(&"hello"));
+    }
+
+    #[test]
+    fn test_full_filter_is_left_untouched() {
+        let mut filter = CuckooFilter::with_params(64, 16, 4);
+
+        let mut inserted = Vec::new();
+        let mut item = 0;
+        while filter.insert(&item).is_ok() {
+            inserted.push(item);
+            item += 1;
+        }
+
+        assert!(inserted.len() > filter.capacity() * 9 / 10);
+        assert_eq!(filter.len(), inserted.len());
+
+        let snapshot: Vec<u64> = (0..filter.capacity())
+            .map(|i| filter.slots.get(i))
+            .collect();
+        assert_eq!(filter.insert(&item), Err(FilterFull));
+        let after: Vec<u64> = (0..filter.capacity())
+            .map(|i| filter.slots.get(i))
+            .collect();
+        assert_eq!(snapshot, after);
+
+        for item in inserted {
+            assert!(filter.contains(&item));
+        }
+    }
+
+    #[test]
+    fn test_fp_rate_bound() {
+        let mut filter = CuckooFilter::with_params(10_000, 8, 4);
+        assert_eq!(filter.fp_rate_bound(), 8.0 / 256.0);
+
+        for i in 0..9_000 {
+            filter.insert(&i).unwrap();
+        }
+
+        let false_positives = (10_000..110_000).filter(|i| filter.contains(i)).count();
+        assert!((false_positives as f64) < 100_000.0 * filter.fp_rate_bound());
+    }
+}
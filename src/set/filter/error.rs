@@ -0,0 +1,85 @@
+use std::fmt;
+
+/// Returned when combining filters built with different parameters or hashers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncompatibleFilters;
+
+impl fmt::Display for IncompatibleFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "filters have different parameters or hashers")
+    }
+}
+
+impl std::error::Error for IncompatibleFilters {}
+
+/// Returned when a filter has no room left for another item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FilterFull;
+
+impl fmt::Display for FilterFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "filter is full")
+    }
+}
+
+impl std::error::Error for FilterFull {}
+
+/// Returned when a static filter could not be constructed within the
+/// allowed number of seeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildFailed;
+
+impl fmt::Display for BuildFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to build filter, every attempted seed was rejected"
+        )
+    }
+}
+
+impl std::error::Error for BuildFailed {}
+
+/// Returned when an invertible table holds too many entries to list them all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeelFailed;
+
+impl fmt::Display for PeelFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "table holds too many entries to list them")
+    }
+}
+
+impl std::error::Error for PeelFailed {}
+
+/// Returned when merging filters whose entries are kept apart, such as
+/// quotient filter fingerprints, either because the filters are
+/// incompatible or because the result cannot hold both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    Incompatible,
+    Full,
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Incompatible => IncompatibleFilters.fmt(f),
+            Self::Full => write!(f, "merged filter cannot hold every entry"),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+impl From<IncompatibleFilters> for MergeError {
+    fn from(_: IncompatibleFilters) -> Self {
+        Self::Incompatible
+    }
+}
+
+impl From<FilterFull> for MergeError {
+    fn from(_: FilterFull) -> Self {
+        Self::Full
+    }
+}
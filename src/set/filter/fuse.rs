@@ -0,0 +1,332 @@
+use crate::hash::{DefaultBuildHasher, murmur64, splitmix64};
+use crate::set::DecodeError;
+use crate::set::codec::Reader;
+use crate::set::filter::BuildFailed;
+use crate::set::filter::xor::{
+    DEFAULT_SEED, Fingerprint, MAX_ATTEMPTS, assign, decode_fingerprints, encode_fingerprints,
+    peel, unique_key_hashes,
+};
+use std::hash::{BuildHasher, Hash};
+
+const ARITY: usize = 3;
+const MAX_SEGMENT_LENGTH: usize = 1 << 18;
+
+const MAGIC: &[u8; 4] = b"BFUF";
+const VERSION: u8 = 2;
+
+/// Binary fuse filter (Graf, Lemire, "Binary Fuse Filters: Fast and Smaller
+/// Than Xor Filters") over an immutable set of keys.
+///
+/// Like [`XorFilter`](crate::set::filter::XorFilter) but the three slots of a
+/// key fall into consecutive segments of a window, which lets the table
+/// shrink to ~1.125 slots per key for large sets: about 9 bits per key at a
+/// ~0.39% false positive rate with 8-bit fingerprints.
+pub struct BinaryFuseFilter<F, S = DefaultBuildHasher> {
+    fingerprints: Vec<F>,
+    layout: Layout,
+    seed: u64,
+    len: usize,
+    hash_builder: S,
+}
+
+pub type BinaryFuseFilter8<S = DefaultBuildHasher> = BinaryFuseFilter<u8, S>;
+pub type BinaryFuseFilter16<S = DefaultBuildHasher> = BinaryFuseFilter<u16, S>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Layout {
+    segment_length: usize,
+    segment_count: usize,
+}
+
+impl Layout {
+    /// Sizing rules from the reference implementation
+    fn for_size(size: usize) -> Self {
+        let segment_length = if size == 0 {
+            4
+        } else {
+            let exponent = ((size as f64).ln() / 3.33f64.ln() + 2.25).floor() as u32;
+            (1usize << exponent).min(MAX_SEGMENT_LENGTH)
+        };
+
+        let capacity = if size <= 1 {
+            0
+        } else {
+            let size_factor = (0.875 + 0.25 * 1_000_000f64.ln() / (size as f64).ln()).max(1.125);
+            (size as f64 * size_factor).round() as usize
+        };
+
+        let init_segment_count = capacity.div_ceil(segment_length).saturating_sub(ARITY - 1);
+        let array_length = (init_segment_count + ARITY - 1) * segment_length;
+        let segment_count = match array_length.div_ceil(segment_length) {
+            count if count < ARITY => 1,
+            count => count - (ARITY - 1),
+        };
+
+        Self {
+            segment_length,
+            segment_count,
+        }
+    }
+
+    fn array_length(&self) -> usize {
+        (self.segment_count + ARITY - 1) * self.segment_length
+    }
+
+    fn positions(&self, hash: u64) -> [usize; 3] {
+        let segment_count_length = (self.segment_count * self.segment_length) as u64;
+        let mask = self.segment_length as u64 - 1;
+
+        let h0 = ((hash as u128 * segment_count_length as u128) >> 64) as u64;
+        let h1 = (h0 + self.segment_length as u64) ^ ((hash >> 18) & mask);
+        let h2 = (h0 + 2 * self.segment_length as u64) ^ (hash & mask);
+
+        [h0 as usize, h1 as usize, h2 as usize]
+    }
+}
+
+impl<F: Fingerprint> BinaryFuseFilter<F> {
+    pub fn build<T: Hash>(keys: &[T]) -> Result<Self, BuildFailed> {
+        Self::build_with_hasher(keys, DefaultBuildHasher::default())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<F: Fingerprint, S: BuildHasher> BinaryFuseFilter<F, S> {
+    pub fn build_with_hasher<T: Hash>(keys: &[T], hash_builder: S) -> Result<Self, BuildFailed> {
+        Self::build_with_seed_and_hasher(keys, DEFAULT_SEED, hash_builder)
+    }
+
+    /// Tries up to 100 seeds derived from `seed`. The same keys, seed and
+    /// hasher always produce the same filter.
+    pub fn build_with_seed_and_hasher<T: Hash>(
+        keys: &[T],
+        seed: u64,
+        hash_builder: S,
+    ) -> Result<Self, BuildFailed> {
+        let hashes = unique_key_hashes(keys, &hash_builder);
+        let layout = Layout::for_size(hashes.len());
+        let positions = |hash| layout.positions(hash);
+
+        let mut rng_state = seed;
+        for _ in 0..MAX_ATTEMPTS {
+            let seed = splitmix64(&mut rng_state);
+
+            let seeded: Vec<u64> = hashes
+                .iter()
+                .map(|&hash| murmur64(hash.wrapping_add(seed)))
+                .collect();
+
+            if let Some(stack) = peel(&seeded, layout.array_length(), positions) {
+                return Ok(Self {
+                    fingerprints: assign(&stack, layout.array_length(), positions),
+                    layout,
+                    seed,
+                    len: hashes.len(),
+                    hash_builder,
+                });
+            }
+        }
+
+        Err(BuildFailed)
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let hash = murmur64(self.hash_builder.hash_one(item).wrapping_add(self.seed));
+        let [a, b, c] = self.layout.positions(hash);
+
+        F::from_hash(hash) == self.fingerprints[a] ^ self.fingerprints[b] ^ self.fingerprints[c]
+    }
+
+    /// Number of distinct keys the filter was built from
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Seed that produced the filter
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Bytes taken by the fingerprint table
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.fingerprints.as_slice())
+    }
+
+    pub fn bits_per_key(&self) -> f64 {
+        (self.memory_usage() * 8) as f64 / self.len.max(1) as f64
+    }
+
+    /// Serializes the seed, layout and fingerprint table. The hasher is not
+    /// stored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(48 + self.memory_usage());
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(F::BITS as u8);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.len as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.layout.segment_length as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.layout.segment_count as u64).to_le_bytes());
+        encode_fingerprints(&self.fingerprints, &mut bytes);
+
+        bytes
+    }
+
+    pub fn from_bytes_with_hasher(bytes: &[u8], hash_builder: S) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+
+        let version = reader.read_header(MAGIC)?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        if reader.read_u8()? as u32 != F::BITS {
+            return Err(DecodeError::Invalid("fingerprint width mismatch"));
+        }
+
+        let seed = reader.read_u64()?;
+        let len = reader.read_usize()?;
+        let segment_length = reader.read_usize()?;
+        let segment_count = reader.read_usize()?;
+
+        if !segment_length.is_power_of_two()
+            || segment_length > MAX_SEGMENT_LENGTH
+            || segment_count == 0
+            || segment_count > usize::MAX / segment_length - ARITY
+        {
+            return Err(DecodeError::Invalid("bad binary fuse filter layout"));
+        }
+
+        let layout = Layout {
+            segment_length,
+            segment_count,
+        };
+        let header = &bytes[..bytes.len() - reader.remaining()];
+        let fingerprints = decode_fingerprints(&mut reader, header, layout.array_length())?;
+        reader.finish()?;
+
+        Ok(Self {
+            fingerprints,
+            layout,
+            seed,
+            len,
+            hash_builder,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let layout = Layout::for_size(1_000_000);
+        assert_eq!(layout.segment_length, 1 << 13);
+        assert!(layout.array_length() < 1_131_000);
+
+        for size in [0, 1, 2, 3, 10, 100] {
+            let layout = Layout::for_size(size);
+            assert!(layout.array_length() >= size);
+            assert!(layout.segment_count >= 1);
+        }
+    }
+
+    #[test]
+    fn test_positions_stay_in_window() {
+        let layout = Layout::for_size(10_000);
+
+        for hash in (0..1_000u64).map(murmur64) {
+            let [a, b, c] = layout.positions(hash);
+            let segment = a / layout.segment_length;
+
+            assert_eq!(b / layout.segment_length, segment + 1);
+            assert_eq!(c / layout.segment_length, segment + 2);
+            assert!(c < layout.array_length());
+        }
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        for size in [0, 1, 2, 3, 10, 1_000, 50_000] {
+            let keys: Vec<u64> = (0..size).collect();
+
+            let filter = BinaryFuseFilter8::build(&keys).unwrap();
+            assert_eq!(filter.len(), size as usize);
+            assert!(keys.iter().all(|key| filter.contains(key)));
+
+            let filter = BinaryFuseFilter16::build(&keys).unwrap();
+            assert!(keys.iter().all(|key| filter.contains(key)));
+        }
+    }
+
+    #[test]
+    fn test_fp_rate_and_size() {
+        let keys: Vec<u64> = (0..1_000_000).collect();
+
+        let filter = BinaryFuseFilter8::build(&keys).unwrap();
+        assert!(filter.bits_per_key() < 9.1, "{}", filter.bits_per_key());
+
+        let false_positives = (1_000_000..2_000_000u64)
+            .filter(|key| filter.contains(key))
+            .count();
+        assert!(false_positives < 5_000, "{false_positives}");
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let keys: Vec<String> = (0..1_000).map(|i| format!("key-{i}")).collect();
+
+        let a = BinaryFuseFilter16::build(&keys).unwrap();
+        let b = BinaryFuseFilter16::build(&keys).unwrap();
+        assert_eq!(a.seed(), b.seed());
+        assert_eq!(a.fingerprints, b.fingerprints);
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = BinaryFuseFilter8::build(&keys).unwrap();
+
+        let bytes = filter.to_bytes();
+        let decoded = BinaryFuseFilter8::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.fingerprints, filter.fingerprints);
+        assert_eq!(decoded.layout, filter.layout);
+        assert!(keys.iter().all(|key| decoded.contains(key)));
+
+        assert!(BinaryFuseFilter16::from_bytes(&bytes).is_err());
+        assert_eq!(
+            BinaryFuseFilter8::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::Truncated)
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[3] = b'X';
+        assert_eq!(
+            BinaryFuseFilter8::from_bytes(&corrupted).err(),
+            Some(DecodeError::BadMagic)
+        );
+
+        // Seed, length and layout are covered by the checksum
+        for offset in [6, 14, 30] {
+            let mut corrupted = bytes.clone();
+            corrupted[offset] ^= 1;
+            assert_eq!(
+                BinaryFuseFilter8::from_bytes(&corrupted).err(),
+                Some(DecodeError::ChecksumMismatch),
+                "{offset}"
+            );
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::DecodeError;
+use crate::set::codec::{Reader, checksum};
+use crate::set::filter::probe::Probes;
+use crate::set::filter::{IncompatibleFilters, PeelFailed};
+use std::hash::BuildHasher;
+
+const DEFAULT_NUM_HASHES: u32 = 3;
+
+/// Hashed along with a key to get its checksum, so the checksum is
+/// independent of the cell indices
+const CHECK_TAG: u8 = 0x3c;
+
+const MAGIC: &[u8; 4] = b"IBLT";
+const VERSION: u8 = 1;
+
+/// Invertible Bloom lookup table (Goodrich & Mitzenmacher) over byte keys.
+///
+/// Every key is added to one cell in each of `k` equal parts of the table.
+/// A cell keeps the number of keys it holds together with the xor of their
+/// lengths, bytes and checksums, so a cell holding a single key gives it
+/// back. Subtracting the table of one set from the table of another cancels
+/// the common keys, and as long as the difference is small enough the
+/// remaining keys can be peeled out one pure cell at a time.
+///
+/// Keys are at most `max_key_len` bytes and are hashed with the same stable
+/// hashers as [`BloomFilter`](crate::set::filter::BloomFilter).
+pub struct InvertibleBloomLookupTable<S = DefaultBuildHasher> {
+    counts: Vec<i64>,
+    len_sums: Vec<u64>,
+    hash_sums: Vec<u64>,
+    /// `max_key_len` bytes per cell
+    key_sums: Vec<u8>,
+    num_hashes: u32,
+    max_key_len: usize,
+    hash_builder: S,
+}
+
+/// Keys peeled from an [`InvertibleBloomLookupTable`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListedEntries {
+    /// Keys inserted more often than removed. After `a.subtract(&b)`, the
+    /// keys only in `a`.
+    pub inserted: Vec<Vec<u8>>,
+    /// Keys removed more often than inserted. After `a.subtract(&b)`, the
+    /// keys only in `b`.
+    pub removed: Vec<Vec<u8>>,
+}
+
+impl InvertibleBloomLookupTable {
+    pub fn with_params(num_cells: usize, num_hashes: u32, max_key_len: usize) -> Self {
+        Self::with_params_and_hasher(
+            num_cells,
+            num_hashes,
+            max_key_len,
+            DefaultBuildHasher::default(),
+        )
+    }
+
+    /// Sizes the table so that up to `expected_entries` keys, such as the
+    /// symmetric difference of two sets, can be listed with high probability
+    pub fn with_capacity(expected_entries: usize, max_key_len: usize) -> Self {
+        Self::with_capacity_and_hasher(expected_entries, max_key_len, DefaultBuildHasher::default())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> InvertibleBloomLookupTable<S> {
+    /// Three hashes and 1.5 cells per entry, plus some slack since small
+    /// tables peel less reliably
+    pub fn with_capacity_and_hasher(
+        expected_entries: usize,
+        max_key_len: usize,
+        hash_builder: S,
+    ) -> Self {
+        let num_cells = expected_entries + expected_entries / 2 + 30;
+        Self::with_params_and_hasher(num_cells, DEFAULT_NUM_HASHES, max_key_len, hash_builder)
+    }
+
+    /// Rounds `num_cells` up to a multiple of `num_hashes`.
+    ///
+    /// Panics if `num_cells`, `num_hashes` or `max_key_len` is zero
+    pub fn with_params_and_hasher(
+        num_cells: usize,
+        num_hashes: u32,
+        max_key_len: usize,
+        hash_builder: S,
+    ) -> Self {
+        assert!(num_cells > 0, "table must have at least one cell");
+        assert!(num_hashes > 0, "table must use at least one hash");
+        assert!(max_key_len > 0, "keys must be allowed at least one byte");
+
+        let num_cells = num_cells.next_multiple_of(num_hashes as usize);
+
+        Self {
+            counts: vec![0; num_cells],
+            len_sums: vec![0; num_cells],
+            hash_sums: vec![0; num_cells],
+            key_sums: vec![0; num_cells * max_key_len],
+            num_hashes,
+            max_key_len,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    pub fn max_key_len(&self) -> usize {
+        self.max_key_len
+    }
+
+    /// Bytes taken by the cells
+    pub fn memory_usage(&self) -> usize {
+        self.num_cells() * 24 + self.key_sums.len()
+    }
+
+    /// Returns `true` if every insertion has been cancelled by a removal or
+    /// a subtraction
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|&count| count == 0)
+            && self.hash_sums.iter().all(|&hash| hash == 0)
+            && self.len_sums.iter().all(|&len| len == 0)
+            && self.key_sums.iter().all(|&byte| byte == 0)
+    }
+
+    /// Panics if `key` is longer than `max_key_len`
+    pub fn insert(&mut self, key: &[u8]) {
+        self.update(key, 1);
+    }
+
+    /// Removing a key that was never inserted is allowed; it is then listed
+    /// in [`ListedEntries::removed`].
+    ///
+    /// Panics if `key` is longer than `max_key_len`
+    pub fn remove(&mut self, key: &[u8]) {
+        self.update(key, -1);
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+        self.len_sums.fill(0);
+        self.hash_sums.fill(0);
+        self.key_sums.fill(0);
+    }
+
+    /// Lists every key left in the table by repeatedly taking a key out of a
+    /// cell that holds only that key. Fails if the table is too full for
+    /// peeling to finish.
+    pub fn list_entries(&self) -> Result<ListedEntries, PeelFailed>
+    where
+        S: Clone,
+    {
+        let mut table = self.clone();
+        let mut entries = ListedEntries::default();
+
+        let mut pure: Vec<usize> = (0..table.num_cells())
+            .filter(|&cell| table.pure_key(cell).is_some())
+            .collect();
+
+        while let Some(cell) = pure.pop() {
+            // The cell may have changed since it was queued
+            let Some(key) = table.pure_key(cell) else {
+                continue;
+            };
+
+            let sign = table.counts[cell];
+            for index in table.cells(&key) {
+                table.apply(index, &key, -sign);
+                if table.pure_key(index).is_some() {
+                    pure.push(index);
+                }
+            }
+
+            if sign > 0 {
+                entries.inserted.push(key);
+            } else {
+                entries.removed.push(key);
+            }
+        }
+
+        if table.is_empty() {
+            Ok(entries)
+        } else {
+            Err(PeelFailed)
+        }
+    }
+
+    /// Serializes the cells. As with the filters, the hasher itself is not
+    /// stored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(40 + self.memory_usage());
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&[0; 3]);
+        bytes.extend_from_slice(&self.num_hashes.to_le_bytes());
+        bytes.extend_from_slice(&(self.num_cells() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.max_key_len as u64).to_le_bytes());
+
+        for cell in 0..self.num_cells() {
+            bytes.extend_from_slice(&self.counts[cell].to_le_bytes());
+            bytes.extend_from_slice(&self.len_sums[cell].to_le_bytes());
+            bytes.extend_from_slice(&self.hash_sums[cell].to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.key_sums);
+
+        let checksum = checksum(&[&bytes]);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn from_bytes_with_hasher(bytes: &[u8], hash_builder: S) -> Result<Self, DecodeError> {
+        if bytes.len() < 8 {
+            return Err(DecodeError::Truncated);
+        }
+        let (body, expected_checksum) = bytes.split_at(bytes.len() - 8);
+
+        let mut reader = Reader::new(body);
+        let version = reader.read_header(MAGIC)?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        reader.read_bytes(3)?;
+        let num_hashes = reader.read_u32()?;
+        let num_cells = reader.read_usize()?;
+        let max_key_len = reader.read_usize()?;
+
+        if num_hashes == 0
+            || num_cells == 0
+            || max_key_len == 0
+            || num_cells % num_hashes as usize != 0
+        {
+            return Err(DecodeError::Invalid("bad table parameters"));
+        }
+
+        let cell_len = max_key_len
+            .checked_add(24)
+            .ok_or(DecodeError::Invalid("key length overflows usize"))?;
+        if reader.remaining() / cell_len < num_cells {
+            return Err(DecodeError::Truncated);
+        }
+
+        if checksum(&[body]) != u64::from_le_bytes(expected_checksum.try_into().unwrap()) {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        let mut table =
+            Self::with_params_and_hasher(num_cells, num_hashes, max_key_len, hash_builder);
+        for cell in 0..num_cells {
+            table.counts[cell] = reader.read_u64()? as i64;
+            table.len_sums[cell] = reader.read_u64()?;
+            table.hash_sums[cell] = reader.read_u64()?;
+        }
+        table
+            .key_sums
+            .copy_from_slice(reader.read_bytes(num_cells * max_key_len)?);
+        reader.finish()?;
+
+        Ok(table)
+    }
+
+    fn update(&mut self, key: &[u8], sign: i64) {
+        assert!(
+            key.len() <= self.max_key_len,
+            "key of {} bytes exceeds the maximum of {}",
+            key.len(),
+            self.max_key_len
+        );
+
+        for index in self.cells(key) {
+            self.apply(index, key, sign);
+        }
+    }
+
+    fn apply(&mut self, index: usize, key: &[u8], sign: i64) {
+        self.counts[index] = self.counts[index].wrapping_add(sign);
+        self.len_sums[index] ^= key.len() as u64;
+        self.hash_sums[index] ^= self.check_hash(key);
+
+        let start = index * self.max_key_len;
+        for (sum, byte) in self.key_sums[start..].iter_mut().zip(key) {
+            *sum ^= byte;
+        }
+    }
+
+    /// One cell in each of the `k` parts of the table
+    fn cells(&self, key: &[u8]) -> impl Iterator<Item = usize> + use<S> {
+        let part_len = (self.num_cells() / self.num_hashes as usize) as u64;
+
+        Probes::new(hash128(&self.hash_builder, key), self.num_hashes)
+            .enumerate()
+            .map(move |(part, hash)| part * part_len as usize + (hash % part_len) as usize)
+    }
+
+    fn check_hash(&self, key: &[u8]) -> u64 {
+        self.hash_builder.hash_one((CHECK_TAG, key))
+    }
+
+    /// The key held by `cell` if it holds exactly one, inserted or removed
+    fn pure_key(&self, cell: usize) -> Option<Vec<u8>> {
+        if self.counts[cell].unsigned_abs() != 1 {
+            return None;
+        }
+
+        let len = usize::try_from(self.len_sums[cell])
+            .ok()
+            .filter(|&len| len <= self.max_key_len)?;
+        let sums = &self.key_sums[cell * self.max_key_len..(cell + 1) * self.max_key_len];
+        if sums[len..].iter().any(|&byte| byte != 0) {
+            return None;
+        }
+
+        let key = &sums[..len];
+        if self.hash_sums[cell] != self.check_hash(key)
+            || !self.cells(key).any(|index| index == cell)
+        {
+            return None;
+        }
+
+        Some(key.to_vec())
+    }
+}
+
+impl<S: BuildHasher + Clone + PartialEq> InvertibleBloomLookupTable<S> {
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.num_cells() == other.num_cells()
+            && self.num_hashes == other.num_hashes
+            && self.max_key_len == other.max_key_len
+            && self.hash_builder == other.hash_builder
+    }
+
+    /// Table holding the keys of `self` as inserted and those of `other` as
+    /// removed, with the keys common to both cancelled out
+    pub fn subtract(&self, other: &Self) -> Result<Self, IncompatibleFilters> {
+        let mut result = self.clone();
+        result.subtract_with(other)?;
+        Ok(result)
+    }
+
+    pub fn subtract_with(&mut self, other: &Self) -> Result<(), IncompatibleFilters> {
+        if !self.is_compatible(other) {
+            return Err(IncompatibleFilters);
+        }
+
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count = count.wrapping_sub(*other);
+        }
+        for (sum, other) in self.len_sums.iter_mut().zip(&other.len_sums) {
+            *sum ^= other;
+        }
+        for (sum, other) in self.hash_sums.iter_mut().zip(&other.hash_sums) {
+            *sum ^= other;
+        }
+        for (sum, other) in self.key_sums.iter_mut().zip(&other.key_sums) {
+            *sum ^= other;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Clone> Clone for InvertibleBloomLookupTable<S> {
+    fn clone(&self) -> Self {
+        Self {
+            counts: self.counts.clone(),
+            len_sums: self.len_sums.clone(),
+            hash_sums: self.hash_sums.clone(),
+            key_sums: self.key_sums.clone(),
+            num_hashes: self.num_hashes,
+            max_key_len: self.max_key_len,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::BuildXxHash64;
+
+    fn key(i: u32) -> Vec<u8> {
+        format!("key-{i}").into_bytes()
+    }
+
+    fn sorted(mut keys: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_insert_and_list() {
+        let mut table = InvertibleBloomLookupTable::with_capacity(50, 16);
+        for i in 0..50 {
+            table.insert(&key(i));
+        }
+
+        let entries = table.list_entries().unwrap();
+        assert_eq!(sorted(entries.inserted), sorted((0..50).map(key).collect()));
+        assert!(entries.removed.is_empty());
+    }
+
+    #[test]
+    fn test_remove_cancels_insert() {
+        let mut table = InvertibleBloomLookupTable::with_capacity(10, 16);
+
+        table.insert(b"hello");
+        table.insert(b"");
+        table.remove(b"hello");
+        table.remove(b"");
+        assert!(table.is_empty());
+
+        table.remove(b"missing");
+        let entries = table.list_entries().unwrap();
+        assert_eq!(entries.removed, vec![b"missing".to_vec()]);
+    }
+
+    #[test]
+    fn test_set_reconciliation() {
+        let mut a = InvertibleBloomLookupTable::with_capacity(100, 16);
+        let mut b = InvertibleBloomLookupTable::with_capacity(100, 16);
+
+        // 10000 common keys, 50 only in each replica
+        for i in 0..10_000 {
+            a.insert(&key(i));
+            b.insert(&key(i));
+        }
+        for i in 10_000..10_050 {
+            a.insert(&key(i));
+        }
+        for i in 20_000..20_050 {
+            b.insert(&key(i));
+        }
+
+        let entries = a.subtract(&b).unwrap().list_entries().unwrap();
+        assert_eq!(
+            sorted(entries.inserted),
+            sorted((10_000..10_050).map(key).collect())
+        );
+        assert_eq!(
+            sorted(entries.removed),
+            sorted((20_000..20_050).map(key).collect())
+        );
+    }
+
+    #[test]
+    fn test_overloaded_table_fails_to_peel() {
+        let mut table = InvertibleBloomLookupTable::with_params(30, 3, 16);
+        for i in 0..1_000 {
+            table.insert(&key(i));
+        }
+
+        assert_eq!(table.list_entries(), Err(PeelFailed));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the maximum")]
+    fn test_key_too_long() {
+        let mut table = InvertibleBloomLookupTable::with_capacity(10, 4);
+        table.insert(b"too long");
+    }
+
+    #[test]
+    fn test_incompatible_tables() {
+        let a = InvertibleBloomLookupTable::with_capacity(10, 16);
+        let b = InvertibleBloomLookupTable::with_capacity(20, 16);
+        let c = InvertibleBloomLookupTable::with_capacity_and_hasher(
+            10,
+            16,
+            BuildXxHash64::with_seed(7),
+        );
+
+        assert_eq!(a.subtract(&b).err(), Some(IncompatibleFilters));
+        assert!(
+            !c.is_compatible(&InvertibleBloomLookupTable::with_capacity_and_hasher(
+                10,
+                16,
+                BuildXxHash64::with_seed(8),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut table = InvertibleBloomLookupTable::with_capacity(20, 12);
+        for i in 0..20 {
+            table.insert(&key(i));
+        }
+        table.remove(b"gone");
+
+        let bytes = table.to_bytes();
+        let decoded = InvertibleBloomLookupTable::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.list_entries(), table.list_entries());
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        assert!(InvertibleBloomLookupTable::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert_eq!(
+            InvertibleBloomLookupTable::from_bytes(&bytes[..4]).err(),
+            Some(DecodeError::Truncated)
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[50] ^= 1;
+        assert_eq!(
+            InvertibleBloomLookupTable::from_bytes(&corrupted).err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+    }
+}
@@ -1,3 +1,38 @@
+mod atomic;
+mod blocked;
 mod bloom;
+#[cfg(test)]
+mod conformance;
+mod counting;
+mod cuckoo;
+mod error;
+mod fuse;
+mod iblt;
+mod packed;
+mod params;
+pub(crate) mod probe;
+mod quotient;
+mod scalable;
+mod sliding;
+mod stable;
+mod traits;
+mod view;
+mod xor;
 
+pub use atomic::AtomicBloomFilter;
+pub use blocked::BlockedBloomFilter;
 pub use bloom::BloomFilter;
+pub use counting::{CounterWidth, CountingBloomFilter};
+pub use cuckoo::CuckooFilter;
+pub use error::{BuildFailed, FilterFull, IncompatibleFilters, MergeError, PeelFailed};
+pub use fuse::{BinaryFuseFilter, BinaryFuseFilter8, BinaryFuseFilter16};
+pub use iblt::{InvertibleBloomLookupTable, ListedEntries};
+pub use quotient::QuotientFilter;
+pub use scalable::ScalableBloomFilter;
+pub use sliding::{SlidingBloomFilter, Window};
+pub use stable::StableBloomFilter;
+pub use traits::{DeletableFilter, Filter};
+pub use view::BloomFilterView;
+#[cfg(feature = "mmap")]
+pub use view::MmapBloomFilter;
+pub use xor::{Fingerprint, XorFilter, XorFilter8, XorFilter16};
@@ -0,0 +1,105 @@
+/// Fixed-length array of `width`-bit unsigned values packed into `u64` words.
+#[derive(Clone)]
+pub(crate) struct PackedArray {
+    words: Vec<u64>,
+    width: u32,
+    len: usize,
+}
+
+impl PackedArray {
+    /// Panics unless `1 <= width <= 64`
+    pub(crate) fn new(len: usize, width: u32) -> Self {
+        assert!((1..=64).contains(&width), "width must be in 1..=64");
+
+        Self {
+            words: vec![0; (len * width as usize).div_ceil(64)],
+            width,
+            len,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub(crate) fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.words.as_slice())
+    }
+
+    pub(crate) fn get(&self, index: usize) -> u64 {
+        debug_assert!(index < self.len);
+
+        let bit = index * self.width as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+
+        let mut value = self.words[word] >> offset;
+        if offset + self.width as usize > 64 {
+            value |= self.words[word + 1] << (64 - offset);
+        }
+
+        value & self.mask()
+    }
+
+    pub(crate) fn set(&mut self, index: usize, value: u64) {
+        debug_assert!(index < self.len);
+        debug_assert!(value <= self.mask());
+
+        let bit = index * self.width as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+        let mask = self.mask();
+
+        self.words[word] = (self.words[word] & !(mask << offset)) | (value << offset);
+        if offset + self.width as usize > 64 {
+            let shift = 64 - offset;
+            self.words[word + 1] = (self.words[word + 1] & !(mask >> shift)) | (value >> shift);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_set_all_widths() {
+        for width in 1..=64 {
+            let mut array = PackedArray::new(100, width);
+            let mask = u64::MAX >> (64 - width);
+
+            for index in 0..100 {
+                array.set(
+                    index,
+                    (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) & mask,
+                );
+            }
+            for index in 0..100 {
+                let expected = (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) & mask;
+                assert_eq!(array.get(index), expected, "width {width}, index {index}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_does_not_touch_neighbours() {
+        let mut array = PackedArray::new(10, 13);
+        array.set(4, 0x1fff);
+        array.set(4, 0);
+
+        for index in 0..10 {
+            assert_eq!(array.get(index), 0);
+        }
+        assert_eq!(array.memory_usage(), 24);
+    }
+}
@@ -0,0 +1,54 @@
+use std::f64::consts::LN_2;
+
+/// Panics unless `0 < fp_rate < 1`
+pub(crate) fn check_fp_rate(fp_rate: f64) {
+    assert!(
+        fp_rate > 0.0 && fp_rate < 1.0,
+        "false positive rate must be in (0, 1), got {fp_rate}"
+    );
+}
+
+/// Number of cells minimizing the false positive rate of a Bloom-style
+/// filter holding `expected_items`: `m = -n ln p / (ln 2)^2`.
+pub(crate) fn optimal_num_cells(expected_items: usize, fp_rate: f64) -> usize {
+    check_fp_rate(fp_rate);
+
+    let expected_items = expected_items.max(1) as f64;
+    let num_cells = -expected_items * fp_rate.ln() / (LN_2 * LN_2);
+
+    (num_cells.ceil() as usize).max(1)
+}
+
+/// Number of hash functions minimizing the false positive rate for the
+/// given load: `k = m / n * ln 2`.
+pub(crate) fn optimal_num_hashes(num_cells: usize, expected_items: usize) -> u32 {
+    let expected_items = expected_items.max(1) as f64;
+    let num_hashes = num_cells as f64 / expected_items * LN_2;
+
+    (num_hashes.round() as u32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classic_sizing() {
+        // 1% at one million items needs ~9.59 bits per item and 7 hashes
+        let num_cells = optimal_num_cells(1_000_000, 0.01);
+        assert_eq!(num_cells, 9_585_059);
+        assert_eq!(optimal_num_hashes(num_cells, 1_000_000), 7);
+    }
+
+    #[test]
+    fn test_degenerate_inputs() {
+        assert!(optimal_num_cells(0, 0.5) >= 1);
+        assert_eq!(optimal_num_hashes(1, 1_000), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_fp_rate() {
+        optimal_num_cells(10, 1.0);
+    }
+}
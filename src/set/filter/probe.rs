@@ -0,0 +1,111 @@
+use crate::hash::hash128;
+use std::hash::{BuildHasher, Hash};
+
+/// Maps `item` to `num_hashes` cell indices in `0..num_cells`.
+pub(crate) fn cell_indices<T: Hash + ?Sized, S: BuildHasher>(
+    hash_builder: &S,
+    item: &T,
+    num_hashes: u32,
+    num_cells: usize,
+) -> impl Iterator<Item = usize> + use<T, S> {
+    hash_indices(hash128(hash_builder, item), num_hashes, num_cells)
+}
+
+/// Same as [`cell_indices`] for an item already hashed with `hash128`
+pub(crate) fn hash_indices(
+    hash: (u64, u64),
+    num_hashes: u32,
+    num_cells: usize,
+) -> impl Iterator<Item = usize> {
+    let num_cells = num_cells as u64;
+
+    Probes::new(hash, num_hashes).map(move |hash| (hash % num_cells) as usize)
+}
+
+/// Hints the CPU to start loading `value` into cache. A no-op on
+/// architectures without a stable prefetch instruction.
+#[inline(always)]
+pub(crate) fn prefetch<T>(value: &T) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::{_MM_HINT_T0, _mm_prefetch};
+        // SAFETY: prefetching never faults, and SSE is part of the x86_64 baseline
+        unsafe { _mm_prefetch::<_MM_HINT_T0>((value as *const T).cast()) };
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = value;
+}
+
+/// Enhanced double hashing (Kirsch–Mitzenmacher, Dillinger–Manolios).
+///
+/// Yields `k` hash values `h1 + i * h2 + (i^3 - i) / 6` derived from a single
+/// 128-bit digest, so an item is hashed once no matter how many probes a
+/// filter needs. The cubic term breaks up the collisions plain double hashing
+/// suffers from when `h2` is a multiple of the table size.
+#[derive(Clone)]
+pub(crate) struct Probes {
+    x: u64,
+    y: u64,
+    i: u64,
+    remaining: u32,
+}
+
+impl Probes {
+    pub(crate) fn new((h1, h2): (u64, u64), count: u32) -> Self {
+        Self {
+            x: h1,
+            y: h2,
+            i: 0,
+            remaining: count,
+        }
+    }
+}
+
+impl Iterator for Probes {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let result = self.x;
+        self.x = self.x.wrapping_add(self.y);
+        self.i += 1;
+        self.y = self.y.wrapping_add(self.i);
+        self.remaining -= 1;
+
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl ExactSizeIterator for Probes {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_closed_form() {
+        let (h1, h2) = (0x1234_5678_9abc_def0u64, 0x0fed_cba9_8765_4321u64);
+
+        for (i, probe) in Probes::new((h1, h2), 20).enumerate() {
+            let i = i as u64;
+            let expected = h1
+                .wrapping_add(i.wrapping_mul(h2))
+                .wrapping_add((i * i * i - i) / 6);
+            assert_eq!(probe, expected);
+        }
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(Probes::new((1, 2), 0).count(), 0);
+        assert_eq!(Probes::new((1, 2), 7).len(), 7);
+    }
+}
@@ -0,0 +1,759 @@
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::filter::packed::PackedArray;
+use crate::set::filter::params::check_fp_rate;
+use crate::set::filter::{FilterFull, IncompatibleFilters, MergeError};
+use std::hash::{BuildHasher, Hash};
+
+const MAX_LOAD_FACTOR: f64 = 0.95;
+const SIZING_LOAD_FACTOR: f64 = 0.75;
+
+const OCCUPIED: u64 = 1;
+const CONTINUATION: u64 = 2;
+const SHIFTED: u64 = 4;
+const METADATA_BITS: u32 = 3;
+
+/// Quotient filter (Bender et al., "Don't Thrash: How to Cache Your Hash on
+/// Flash").
+///
+/// A `p`-bit fingerprint is split into a `q`-bit quotient, the home slot, and
+/// an `r`-bit remainder stored in the table using linear probing with three
+/// metadata bits per slot. Since the full fingerprint can be recovered from
+/// the table, filters can be resized and merged without the original keys.
+///
+/// Fingerprints are stored as a multiset: every insert takes its own entry,
+/// even for an item already present, and `remove` drops exactly one, so
+/// removing one of two colliding items keeps the other.
+pub struct QuotientFilter<S = DefaultBuildHasher> {
+    slots: PackedArray,
+    quotient_bits: u32,
+    remainder_bits: u32,
+    len: usize,
+    hash_builder: S,
+}
+
+impl QuotientFilter {
+    pub fn new(quotient_bits: u32, remainder_bits: u32) -> Self {
+        Self::with_hasher(quotient_bits, remainder_bits, DefaultBuildHasher::default())
+    }
+
+    /// Sizes the filter to hold `expected_items` at the given false positive rate
+    pub fn with_capacity(expected_items: usize, fp_rate: f64) -> Self {
+        Self::with_capacity_and_hasher(expected_items, fp_rate, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> QuotientFilter<S> {
+    /// Picks the smallest table that stays under 75% load at `expected_items`
+    /// and a remainder of `ceil(log2(1 / fp_rate))` bits.
+    ///
+    /// Panics unless `0 < fp_rate < 1`
+    pub fn with_capacity_and_hasher(expected_items: usize, fp_rate: f64, hash_builder: S) -> Self {
+        check_fp_rate(fp_rate);
+
+        let num_slots = (expected_items as f64 / SIZING_LOAD_FACTOR).ceil() as usize;
+        let quotient_bits = num_slots.max(2).next_power_of_two().trailing_zeros();
+        let remainder_bits = (1.0 / fp_rate).log2().ceil().max(1.0) as u32;
+
+        Self::with_hasher(quotient_bits, remainder_bits, hash_builder)
+    }
+
+    /// Panics unless `1 <= quotient_bits`, `1 <= remainder_bits <= 61` and the
+    /// fingerprint fits in 64 bits
+    pub fn with_hasher(quotient_bits: u32, remainder_bits: u32, hash_builder: S) -> Self {
+        assert!(quotient_bits >= 1, "quotient must have at least one bit");
+        assert!(
+            (1..=64 - METADATA_BITS).contains(&remainder_bits),
+            "remainder must have 1 to 61 bits"
+        );
+        assert!(
+            quotient_bits + remainder_bits <= 64,
+            "fingerprint must fit in 64 bits"
+        );
+        assert!(
+            quotient_bits < usize::BITS,
+            "quotient does not fit the address space"
+        );
+
+        Self {
+            slots: PackedArray::new(1 << quotient_bits, remainder_bits + METADATA_BITS),
+            quotient_bits,
+            remainder_bits,
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn quotient_bits(&self) -> u32 {
+        self.quotient_bits
+    }
+
+    pub fn remainder_bits(&self) -> u32 {
+        self.remainder_bits
+    }
+
+    pub fn fingerprint_bits(&self) -> u32 {
+        self.quotient_bits + self.remainder_bits
+    }
+
+    pub fn num_slots(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Number of fingerprints the filter accepts before reporting it is full
+    pub fn capacity(&self) -> usize {
+        (self.num_slots() as f64 * MAX_LOAD_FACTOR) as usize
+    }
+
+    /// Number of fingerprints stored, counting duplicates
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes taken by the slot table
+    pub fn memory_usage(&self) -> usize {
+        self.slots.memory_usage()
+    }
+
+    /// Approximate false positive rate at the current load,
+    /// `1 - e^(-load / 2^r)`
+    pub fn fp_rate(&self) -> f64 {
+        let load = self.len as f64 / self.num_slots() as f64;
+        1.0 - (-load / (1u64 << self.remainder_bits) as f64).exp()
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        let (quotient, remainder) = self.split(self.fingerprint(item));
+        self.insert_split(quotient, remainder)
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let (quotient, remainder) = self.split(self.fingerprint(item));
+
+        if !self.is_occupied(quotient) {
+            return false;
+        }
+
+        let mut slot = self.find_run_start(quotient);
+        loop {
+            let current = self.remainder(slot);
+            if current == remainder {
+                return true;
+            }
+            if current > remainder {
+                return false;
+            }
+
+            slot = self.next(slot);
+            if !self.is_continuation(slot) {
+                return false;
+            }
+        }
+    }
+
+    /// Removes the fingerprint of `item`, returning whether it was present
+    pub fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let (quotient, remainder) = self.split(self.fingerprint(item));
+
+        if !self.is_occupied(quotient) {
+            return false;
+        }
+
+        let run_start = self.find_run_start(quotient);
+        let mut slot = run_start;
+        loop {
+            let current = self.remainder(slot);
+            if current == remainder {
+                break;
+            }
+
+            slot = self.next(slot);
+            if current > remainder || !self.is_continuation(slot) {
+                return false;
+            }
+        }
+
+        let removing_run_start = slot == run_start;
+        if removing_run_start && !self.is_continuation(self.next(slot)) {
+            // Last entry of the run, the home slot is no longer occupied
+            let home = self.slots.get(quotient);
+            self.slots.set(quotient, home & !OCCUPIED);
+        }
+
+        self.delete_entry(slot, quotient);
+
+        if removing_run_start {
+            let next = self.slots.get(slot);
+            let mut updated = next;
+
+            if updated & CONTINUATION != 0 {
+                // The next entry of the run becomes its start
+                updated &= !CONTINUATION;
+            }
+            if slot == quotient && Self::is_run_start(updated) {
+                updated &= !SHIFTED;
+            }
+            if updated != next {
+                self.slots.set(slot, updated);
+            }
+        }
+
+        self.len -= 1;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+
+    /// Doubles the number of slots by moving one bit of every fingerprint
+    /// from the remainder to the quotient. Neither the false positive rate
+    /// nor the stored items change.
+    ///
+    /// Panics if the remainder is already down to a single bit
+    pub fn resize(&mut self)
+    where
+        S: Clone,
+    {
+        assert!(
+            self.remainder_bits > 1,
+            "cannot resize a quotient filter with a one-bit remainder"
+        );
+
+        let mut resized = Self::with_hasher(
+            self.quotient_bits + 1,
+            self.remainder_bits - 1,
+            self.hash_builder.clone(),
+        );
+        resized
+            .extend_fingerprints(self.fingerprints())
+            .expect("twice the slots hold every fingerprint");
+
+        *self = resized;
+    }
+
+    /// Filter holding the fingerprints of both filters, with enough slots to
+    /// stay under the maximum load. Both filters must use the same fingerprint
+    /// size and hasher; the larger quotient of the two is grown by at least
+    /// one bit.
+    ///
+    /// Fails with [`MergeError::Full`] if the combined fingerprints cannot fit
+    /// even with a one-bit remainder
+    pub fn merge(&self, other: &Self) -> Result<Self, MergeError>
+    where
+        S: Clone + PartialEq,
+    {
+        if self.fingerprint_bits() != other.fingerprint_bits()
+            || self.hash_builder != other.hash_builder
+        {
+            return Err(IncompatibleFilters.into());
+        }
+
+        let fingerprint_bits = self.fingerprint_bits();
+        let mut quotient_bits = self.quotient_bits.max(other.quotient_bits) + 1;
+        while quotient_bits < fingerprint_bits - 1
+            && (((1usize << quotient_bits) as f64) * MAX_LOAD_FACTOR)
+                < (self.len + other.len) as f64
+        {
+            quotient_bits += 1;
+        }
+        let quotient_bits = quotient_bits.min(fingerprint_bits - 1);
+
+        let mut merged = Self::with_hasher(
+            quotient_bits,
+            fingerprint_bits - quotient_bits,
+            self.hash_builder.clone(),
+        );
+        merged.extend_fingerprints(self.fingerprints())?;
+        merged.extend_fingerprints(other.fingerprints())?;
+
+        Ok(merged)
+    }
+
+    fn fingerprint<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        let (hash, _) = hash128(&self.hash_builder, item);
+        hash & (u64::MAX >> (64 - self.fingerprint_bits()))
+    }
+
+    fn split(&self, fingerprint: u64) -> (usize, u64) {
+        (
+            (fingerprint >> self.remainder_bits) as usize,
+            fingerprint & ((1 << self.remainder_bits) - 1),
+        )
+    }
+
+    /// Every stored fingerprint, reconstructed from slot positions
+    fn fingerprints(&self) -> Vec<u64> {
+        let mut fingerprints = Vec::with_capacity(self.len);
+        if self.len == 0 {
+            return fingerprints;
+        }
+
+        // Below the maximum load there is always an empty slot, and the
+        // entry right after one always sits in its home slot
+        let empty = (0..self.num_slots())
+            .find(|&slot| self.slots.get(slot) & 7 == 0)
+            .unwrap();
+
+        let mut quotient = empty;
+        let mut slot = empty;
+        for _ in 0..self.num_slots() {
+            slot = self.next(slot);
+            let entry = self.slots.get(slot);
+
+            if entry & 7 == 0 {
+                continue;
+            }
+
+            if entry & SHIFTED == 0 {
+                quotient = slot;
+            } else if entry & CONTINUATION == 0 {
+                quotient = self.next(quotient);
+                while !self.is_occupied(quotient) {
+                    quotient = self.next(quotient);
+                }
+            }
+
+            fingerprints.push(((quotient as u64) << self.remainder_bits) | entry >> METADATA_BITS);
+        }
+
+        fingerprints
+    }
+
+    fn extend_fingerprints(&mut self, fingerprints: Vec<u64>) -> Result<(), FilterFull> {
+        for fingerprint in fingerprints {
+            let (quotient, remainder) = self.split(fingerprint);
+            self.insert_split(quotient, remainder)?;
+        }
+
+        Ok(())
+    }
+
+    fn insert_split(&mut self, quotient: usize, remainder: u64) -> Result<(), FilterFull> {
+        let home = self.slots.get(quotient);
+        let mut entry = remainder << METADATA_BITS;
+
+        if home & 7 == 0 {
+            if self.len >= self.capacity() {
+                return Err(FilterFull);
+            }
+
+            self.slots.set(quotient, entry | OCCUPIED);
+            self.len += 1;
+            return Ok(());
+        }
+
+        let was_occupied = home & OCCUPIED != 0;
+        let run_start = if was_occupied {
+            self.find_run_start(quotient)
+        } else {
+            // The run will start where the next run begins, find it before
+            // marking the home slot as occupied
+            self.slots.set(quotient, home | OCCUPIED);
+            self.find_run_start(quotient)
+        };
+
+        // Equal remainders are kept side by side, the new one goes after them
+        let mut slot = run_start;
+        if was_occupied {
+            loop {
+                if self.remainder(slot) > remainder {
+                    break;
+                }
+
+                slot = self.next(slot);
+                if !self.is_continuation(slot) {
+                    break;
+                }
+            }
+        }
+
+        if self.len >= self.capacity() {
+            if !was_occupied {
+                self.slots.set(quotient, home);
+            }
+            return Err(FilterFull);
+        }
+
+        if was_occupied {
+            if slot == run_start {
+                let old_start = self.slots.get(run_start);
+                self.slots.set(run_start, old_start | CONTINUATION);
+            } else {
+                entry |= CONTINUATION;
+            }
+        }
+
+        if slot != quotient {
+            entry |= SHIFTED;
+        }
+
+        self.insert_shifting(slot, entry);
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Writes `entry` at `slot`, shifting the following entries right up to
+    /// the next empty slot. Occupied bits belong to slots, not entries, so they
+    /// stay in place.
+    fn insert_shifting(&mut self, mut slot: usize, entry: u64) {
+        let mut current = entry;
+
+        loop {
+            let previous = self.slots.get(slot);
+            let empty = previous & 7 == 0;
+
+            let mut displaced = previous;
+            if !empty {
+                displaced |= SHIFTED;
+                if displaced & OCCUPIED != 0 {
+                    current |= OCCUPIED;
+                    displaced &= !OCCUPIED;
+                }
+            }
+
+            self.slots.set(slot, current);
+            current = displaced;
+            slot = self.next(slot);
+
+            if empty {
+                break;
+            }
+        }
+    }
+
+    /// Removes the entry at `slot`, shifting the rest of its cluster left.
+    /// `quotient` is the home slot of the run `slot` belongs to.
+    fn delete_entry(&mut self, mut slot: usize, mut quotient: usize) {
+        let origin = slot;
+        let mut current = self.slots.get(slot);
+        let mut following = self.next(slot);
+
+        loop {
+            let next = self.slots.get(following);
+            let current_occupied = current & OCCUPIED != 0;
+
+            if next & 7 == 0 || Self::is_cluster_start(next) || following == origin {
+                self.slots.set(slot, current & OCCUPIED);
+                return;
+            }
+
+            let mut updated = next;
+            if Self::is_run_start(next) {
+                quotient = self.next(quotient);
+                while !self.is_occupied(quotient) {
+                    quotient = self.next(quotient);
+                }
+
+                if quotient == slot {
+                    updated &= !SHIFTED;
+                }
+            }
+
+            if current_occupied {
+                updated |= OCCUPIED;
+            } else {
+                updated &= !OCCUPIED;
+            }
+
+            self.slots.set(slot, updated);
+            slot = following;
+            following = self.next(following);
+            current = next;
+        }
+    }
+
+    fn find_run_start(&self, quotient: usize) -> usize {
+        // Walk back to the start of the cluster
+        let mut bucket = quotient;
+        while self.slots.get(bucket) & SHIFTED != 0 {
+            bucket = self.prev(bucket);
+        }
+
+        // Walk forward run by run until reaching the run of `quotient`
+        let mut run = bucket;
+        while bucket != quotient {
+            loop {
+                run = self.next(run);
+                if !self.is_continuation(run) {
+                    break;
+                }
+            }
+
+            loop {
+                bucket = self.next(bucket);
+                if self.is_occupied(bucket) {
+                    break;
+                }
+            }
+        }
+
+        run
+    }
+
+    fn is_cluster_start(entry: u64) -> bool {
+        entry & (OCCUPIED | CONTINUATION | SHIFTED) == OCCUPIED
+    }
+
+    fn is_run_start(entry: u64) -> bool {
+        entry & CONTINUATION == 0 && entry & (OCCUPIED | SHIFTED) != 0
+    }
+
+    fn is_occupied(&self, slot: usize) -> bool {
+        self.slots.get(slot) & OCCUPIED != 0
+    }
+
+    fn is_continuation(&self, slot: usize) -> bool {
+        self.slots.get(slot) & CONTINUATION != 0
+    }
+
+    fn remainder(&self, slot: usize) -> u64 {
+        self.slots.get(slot) >> METADATA_BITS
+    }
+
+    fn next(&self, slot: usize) -> usize {
+        (slot + 1) & (self.num_slots() - 1)
+    }
+
+    fn prev(&self, slot: usize) -> usize {
+        slot.wrapping_sub(1) & (self.num_slots() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    /// `model` holds every stored fingerprint, duplicates included
+    fn check_against_model(filter: &QuotientFilter, model: &[u64]) -> bool {
+        let mut fingerprints = filter.fingerprints();
+        fingerprints.sort_unstable();
+        let mut model = model.to_vec();
+        model.sort_unstable();
+
+        filter.len() == model.len() && fingerprints == model
+    }
+
+    fn remove_one(model: &mut Vec<u64>, fingerprint: u64) -> bool {
+        match model.iter().position(|&stored| stored == fingerprint) {
+            Some(index) => {
+                model.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn test_insert_contains_remove() {
+        let mut filter = QuotientFilter::with_capacity(1_000, 0.001);
+        assert_eq!(filter.quotient_bits(), 11);
+        assert_eq!(filter.remainder_bits(), 10);
+
+        for i in 0..1_000 {
+            filter.insert(&i).unwrap();
+        }
+        for i in 0..1_000 {
+            assert!(filter.contains(&i));
+        }
+
+        for i in 0..500 {
+            assert!(filter.remove(&i));
+        }
+        for i in 500..1_000 {
+            assert!(filter.contains(&i));
+        }
+
+        let false_positives = (1_000..101_000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 100, "{false_positives}");
+
+        filter.clear();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&700));
+    }
+
+    #[test]
+    fn test_duplicates_are_counted() {
+        let mut filter = QuotientFilter::new(4, 8);
+
+        filter.insert(&"hello").unwrap();
+        filter.insert(&"hello").unwrap();
+        assert_eq!(filter.len(), 2);
+
+        assert!(filter.remove(&"hello"));
+        assert!(filter.contains(&"hello"));
+        assert!(filter.remove(&"hello"));
+        assert!(!filter.contains(&"hello"));
+        assert!(!filter.remove(&"hello"));
+    }
+
+    #[test]
+    fn test_colliding_items_survive_removal() {
+        // Four-bit fingerprints, so colliding items are easy to find
+        let mut filter = QuotientFilter::new(3, 1);
+        let first = 0u32;
+        let second = (1..)
+            .find(|item| filter.fingerprint(item) == filter.fingerprint(&first))
+            .unwrap();
+
+        filter.insert(&first).unwrap();
+        filter.insert(&second).unwrap();
+
+        assert!(filter.remove(&first));
+        assert!(filter.contains(&second));
+    }
+
+    #[test]
+    fn test_full() {
+        let mut filter = QuotientFilter::new(4, 16);
+        assert_eq!(filter.capacity(), 15);
+
+        for i in 0..15 {
+            filter.insert(&i).unwrap();
+        }
+        assert_eq!(filter.insert(&15), Err(FilterFull));
+        assert_eq!(filter.len(), 15);
+
+        // Every insert takes a slot, even for items already present
+        assert_eq!(filter.insert(&3), Err(FilterFull));
+
+        for i in 0..15 {
+            assert!(filter.contains(&i));
+        }
+    }
+
+    #[test]
+    fn test_dense_clusters_wrap_around() {
+        // Few quotient bits force long clusters that wrap around the table
+        let mut filter = QuotientFilter::new(3, 20);
+        let mut model = Vec::new();
+
+        for i in 0..7 {
+            filter.insert(&i).unwrap();
+            model.push(filter.fingerprint(&i));
+            assert!(check_against_model(&filter, &model));
+        }
+        for i in [3, 0, 6, 1, 5, 2, 4] {
+            assert!(filter.remove(&i));
+            remove_one(&mut model, filter.fingerprint(&i));
+            assert!(check_against_model(&filter, &model));
+        }
+        assert!(filter.is_empty());
+    }
+
+    #[quickcheck]
+    fn test_matches_model(operations: Vec<(bool, u8)>) -> bool {
+        let mut filter = QuotientFilter::new(6, 4);
+        let mut model = Vec::new();
+
+        for (insert, item) in operations {
+            let fingerprint = filter.fingerprint(&item);
+
+            if insert {
+                let result = filter.insert(&item);
+                if model.len() < filter.capacity() {
+                    model.push(fingerprint);
+                    if result.is_err() {
+                        return false;
+                    }
+                } else if result.is_ok() {
+                    return false;
+                }
+            } else if filter.remove(&item) != remove_one(&mut model, fingerprint) {
+                return false;
+            }
+
+            if !check_against_model(&filter, &model) {
+                return false;
+            }
+        }
+
+        (0..=u8::MAX)
+            .all(|item| filter.contains(&item) == model.contains(&filter.fingerprint(&item)))
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut filter = QuotientFilter::new(8, 12);
+        let mut inserted = 0;
+        while filter.insert(&inserted).is_ok() {
+            inserted += 1;
+        }
+        assert_eq!(filter.len(), filter.capacity());
+
+        let mut before = filter.fingerprints();
+        before.sort_unstable();
+
+        filter.resize();
+        assert_eq!(filter.quotient_bits(), 9);
+        assert_eq!(filter.remainder_bits(), 11);
+        assert_eq!(filter.len(), before.len());
+
+        let mut after = filter.fingerprints();
+        after.sort_unstable();
+        assert_eq!(before, after);
+
+        for i in 0..inserted {
+            assert!(filter.contains(&i));
+        }
+        filter.insert(&inserted).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_resize_needs_remainder_bits() {
+        QuotientFilter::new(8, 1).resize();
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = QuotientFilter::new(8, 12);
+        let mut b = QuotientFilter::new(6, 14);
+
+        for i in 0..200 {
+            a.insert(&i).unwrap();
+        }
+        for i in 150..210 {
+            b.insert(&i).unwrap();
+        }
+
+        let merged = a.merge(&b).unwrap();
+        assert_eq!(merged.fingerprint_bits(), 20);
+        assert_eq!(merged.quotient_bits(), 9);
+        // Items in both filters are kept twice
+        assert_eq!(merged.len(), 260);
+
+        for i in 0..210 {
+            assert!(merged.contains(&i));
+        }
+
+        assert_eq!(
+            a.merge(&QuotientFilter::new(8, 13)).err(),
+            Some(MergeError::Incompatible)
+        );
+    }
+
+    #[test]
+    fn test_merge_full() {
+        // Three-bit fingerprints allow at most a two-bit quotient, whose four
+        // slots cannot hold six fingerprints
+        let mut a = QuotientFilter::new(2, 1);
+        let mut b = QuotientFilter::new(2, 1);
+        for i in 0..3 {
+            a.insert(&i).unwrap();
+            b.insert(&i).unwrap();
+        }
+
+        assert_eq!(a.merge(&b).err(), Some(MergeError::Full));
+    }
+}
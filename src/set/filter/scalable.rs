@@ -0,0 +1,409 @@
+use crate::hash::DefaultBuildHasher;
+use crate::set::DecodeError;
+use crate::set::codec::{Reader, checksum};
+use crate::set::filter::BloomFilter;
+use crate::set::filter::params::check_fp_rate;
+use std::hash::{BuildHasher, Hash};
+
+const DEFAULT_GROWTH_FACTOR: usize = 2;
+const DEFAULT_TIGHTENING_RATIO: f64 = 0.85;
+
+const MAGIC: &[u8; 4] = b"SBLF";
+const VERSION: u8 = 2;
+
+/// Chain of Bloom filters that grows with the number of inserted items
+/// (Almeida et al., "Scalable Bloom Filters").
+///
+/// Slice `i` holds `initial_capacity * growth_factor^i` items at a false
+/// positive rate of `fp_rate * (1 - r) * r^i`, where `r` is the tightening
+/// ratio. The per-slice rates form a geometric series summing to `fp_rate`,
+/// which bounds the rate of the whole chain however long it gets.
+pub struct ScalableBloomFilter<S = DefaultBuildHasher> {
+    slices: Vec<BloomFilter<S>>,
+    initial_capacity: usize,
+    fp_rate: f64,
+    growth_factor: usize,
+    tightening_ratio: f64,
+    len: usize,
+    last_slice_len: usize,
+    hash_builder: S,
+}
+
+impl ScalableBloomFilter {
+    pub fn new(initial_capacity: usize, fp_rate: f64) -> Self {
+        Self::with_hasher(initial_capacity, fp_rate, DefaultBuildHasher::default())
+    }
+
+    pub fn with_growth(
+        initial_capacity: usize,
+        fp_rate: f64,
+        growth_factor: usize,
+        tightening_ratio: f64,
+    ) -> Self {
+        Self::with_growth_and_hasher(
+            initial_capacity,
+            fp_rate,
+            growth_factor,
+            tightening_ratio,
+            DefaultBuildHasher::default(),
+        )
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher + Clone> ScalableBloomFilter<S> {
+    pub fn with_hasher(initial_capacity: usize, fp_rate: f64, hash_builder: S) -> Self {
+        Self::with_growth_and_hasher(
+            initial_capacity,
+            fp_rate,
+            DEFAULT_GROWTH_FACTOR,
+            DEFAULT_TIGHTENING_RATIO,
+            hash_builder,
+        )
+    }
+
+    /// Panics if `initial_capacity` is zero, `growth_factor` is less than 1
+    /// or either rate is outside of `(0, 1)`
+    pub fn with_growth_and_hasher(
+        initial_capacity: usize,
+        fp_rate: f64,
+        growth_factor: usize,
+        tightening_ratio: f64,
+        hash_builder: S,
+    ) -> Self {
+        assert!(initial_capacity > 0, "initial capacity must be positive");
+        assert!(growth_factor >= 1, "growth factor must be at least 1");
+        check_fp_rate(fp_rate);
+        assert!(
+            tightening_ratio > 0.0 && tightening_ratio < 1.0,
+            "tightening ratio must be in (0, 1), got {tightening_ratio}"
+        );
+
+        let mut filter = Self {
+            slices: Vec::new(),
+            initial_capacity,
+            fp_rate,
+            growth_factor,
+            tightening_ratio,
+            len: 0,
+            last_slice_len: 0,
+            hash_builder,
+        };
+        filter.add_slice();
+
+        filter
+    }
+
+    /// Adds `item` unless it is (possibly falsely) reported as present already.
+    /// Returns whether it was added.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        if self.contains(item) {
+            return false;
+        }
+
+        if self.last_slice_len == self.slice_capacity(self.slices.len() - 1) {
+            self.add_slice();
+        }
+
+        self.slices.last_mut().unwrap().insert(item);
+        self.last_slice_len += 1;
+        self.len += 1;
+
+        true
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.slices.iter().rev().any(|slice| slice.contains(item))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn num_slices(&self) -> usize {
+        self.slices.len()
+    }
+
+    pub fn fp_rate(&self) -> f64 {
+        self.fp_rate
+    }
+
+    /// False positive rate at the current fill of every slice. Stays below
+    /// [`fp_rate`](Self::fp_rate) however many items are inserted.
+    pub fn current_fp_rate(&self) -> f64 {
+        1.0 - self
+            .slices
+            .iter()
+            .map(|slice| 1.0 - slice.fp_rate())
+            .product::<f64>()
+    }
+
+    /// Bytes taken by the bit arrays of all slices
+    pub fn memory_usage(&self) -> usize {
+        self.slices.iter().map(BloomFilter::memory_usage).sum()
+    }
+
+    /// Drops every slice but a fresh first one
+    pub fn clear(&mut self) {
+        self.slices.clear();
+        self.len = 0;
+        self.add_slice();
+    }
+
+    /// Serializes the growth parameters and every slice of the chain. As with
+    /// [`BloomFilter::to_bytes`], the hasher itself is not stored.
+    ///
+    /// The chain header is followed by its own checksum, and every slice
+    /// carries the checksum of its Bloom filter encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + self.memory_usage() + 40 * self.slices.len());
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.initial_capacity as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.fp_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.growth_factor as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.tightening_ratio.to_le_bytes());
+        bytes.extend_from_slice(&(self.len as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.last_slice_len as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.slices.len() as u64).to_le_bytes());
+
+        let checksum = checksum(&[&bytes]);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        for slice in &self.slices {
+            slice.encode(&mut bytes);
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes_with_hasher(bytes: &[u8], hash_builder: S) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+
+        let version = reader.read_header(MAGIC)?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let initial_capacity = reader.read_usize()?;
+        let fp_rate = reader.read_f64()?;
+        let growth_factor = reader.read_usize()?;
+        let tightening_ratio = reader.read_f64()?;
+        let len = reader.read_usize()?;
+        let last_slice_len = reader.read_usize()?;
+        let num_slices = reader.read_usize()?;
+
+        let header = &bytes[..bytes.len() - reader.remaining()];
+        if checksum(&[header]) != reader.read_u64()? {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        if initial_capacity == 0
+            || growth_factor == 0
+            || !(fp_rate > 0.0 && fp_rate < 1.0)
+            || !(tightening_ratio > 0.0 && tightening_ratio < 1.0)
+        {
+            return Err(DecodeError::Invalid("bad scalable bloom filter parameters"));
+        }
+
+        if num_slices == 0 {
+            return Err(DecodeError::Invalid("scalable bloom filter has no slices"));
+        }
+
+        // Every slice takes at least its 40-byte header
+        if reader.remaining() / 40 < num_slices {
+            return Err(DecodeError::Truncated);
+        }
+
+        let mut slices = Vec::with_capacity(num_slices);
+        for _ in 0..num_slices {
+            slices.push(BloomFilter::decode(&mut reader, hash_builder.clone())?);
+        }
+        reader.finish()?;
+
+        let filter = Self {
+            slices,
+            initial_capacity,
+            fp_rate,
+            growth_factor,
+            tightening_ratio,
+            len,
+            last_slice_len,
+            hash_builder,
+        };
+
+        if last_slice_len > filter.slice_capacity(num_slices - 1) || last_slice_len > len {
+            return Err(DecodeError::Invalid("slice length exceeds its capacity"));
+        }
+
+        Ok(filter)
+    }
+
+    fn slice_capacity(&self, index: usize) -> usize {
+        u32::try_from(index)
+            .ok()
+            .and_then(|index| self.growth_factor.checked_pow(index))
+            .and_then(|growth| self.initial_capacity.checked_mul(growth))
+            .unwrap_or(usize::MAX)
+    }
+
+    fn slice_fp_rate(&self, index: usize) -> f64 {
+        self.fp_rate * (1.0 - self.tightening_ratio) * self.tightening_ratio.powi(index as i32)
+    }
+
+    fn add_slice(&mut self) {
+        let index = self.slices.len();
+
+        self.slices.push(BloomFilter::with_capacity_and_hasher(
+            self.slice_capacity(index),
+            self.slice_fp_rate(index),
+            self.hash_builder.clone(),
+        ));
+        self.last_slice_len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grows_and_keeps_items() {
+        let mut filter = ScalableBloomFilter::new(100, 0.01);
+        assert!(filter.is_empty());
+        assert_eq!(filter.num_slices(), 1);
+
+        for i in 0..10_000 {
+            filter.insert(&i);
+        }
+
+        assert!(filter.num_slices() > 1);
+        assert!(filter.len() <= 10_000);
+        assert!(filter.len() > 9_900);
+
+        for i in 0..10_000 {
+            assert!(filter.contains(&i));
+        }
+    }
+
+    #[test]
+    fn test_insert_reports_duplicates() {
+        let mut filter = ScalableBloomFilter::new(10, 0.01);
+
+        assert!(filter.insert(&"hello"));
+        assert!(!filter.insert(&"hello"));
+        assert_eq!(filter.len(), 1);
+    }
+
+    #[test]
+    fn test_fp_rate_is_bounded() {
+        let mut filter = ScalableBloomFilter::new(64, 0.01);
+
+        for i in 0..50_000 {
+            filter.insert(&i);
+        }
+
+        let false_positives = (50_000..150_000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 1_000, "{false_positives}");
+    }
+
+    #[test]
+    fn test_memory_grows_geometrically() {
+        let mut filter = ScalableBloomFilter::with_growth(100, 0.01, 4, 0.5);
+        let initial = filter.memory_usage();
+
+        for i in 0..100 {
+            filter.insert(&i);
+        }
+        assert_eq!(filter.memory_usage(), initial);
+
+        for i in 100..400 {
+            filter.insert(&i);
+        }
+        assert_eq!(filter.num_slices(), 2);
+        assert!(filter.memory_usage() > 4 * initial);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut filter = ScalableBloomFilter::new(10, 0.01);
+        for i in 0..100 {
+            filter.insert(&i);
+        }
+
+        filter.clear();
+        assert!(filter.is_empty());
+        assert_eq!(filter.num_slices(), 1);
+        assert!(!filter.contains(&1));
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut filter = ScalableBloomFilter::new(50, 0.001);
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+
+        let bytes = filter.to_bytes();
+        let mut decoded = ScalableBloomFilter::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.len(), filter.len());
+        assert_eq!(decoded.num_slices(), filter.num_slices());
+        for i in 0..2_000 {
+            assert_eq!(decoded.contains(&i), filter.contains(&i));
+        }
+
+        // The decoded chain keeps growing like the original
+        for i in 1_000..2_000 {
+            filter.insert(&i);
+            decoded.insert(&i);
+        }
+        assert_eq!(decoded.to_bytes(), filter.to_bytes());
+    }
+
+    #[test]
+    fn test_deserialization_errors() {
+        let mut filter = ScalableBloomFilter::new(10, 0.01);
+        for i in 0..100 {
+            filter.insert(&i);
+        }
+        let bytes = filter.to_bytes();
+
+        for len in [0, 3, 20, bytes.len() - 1] {
+            assert!(ScalableBloomFilter::from_bytes(&bytes[..len]).is_err());
+        }
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 0x80;
+        assert_eq!(
+            ScalableBloomFilter::from_bytes(&corrupted).err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+
+        // Growth factor and tightening ratio that still pass the range checks
+        for offset in [21, 29] {
+            let mut corrupted = bytes.clone();
+            corrupted[offset] ^= 1;
+            assert_eq!(
+                ScalableBloomFilter::from_bytes(&corrupted).err(),
+                Some(DecodeError::ChecksumMismatch),
+                "{offset}"
+            );
+        }
+
+        let mut old_version = bytes;
+        old_version[4] = 1;
+        assert_eq!(
+            ScalableBloomFilter::from_bytes(&old_version).err(),
+            Some(DecodeError::UnsupportedVersion(1))
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use crate::hash::DefaultBuildHasher;
+use crate::set::filter::BloomFilter;
+use crate::set::filter::params::check_fp_rate;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hash};
+use std::time::{Duration, Instant};
+
+const DEFAULT_NUM_GENERATIONS: usize = 4;
+
+/// How long an item stays in a [`SlidingBloomFilter`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    /// The last `n` insertions
+    Items(usize),
+    /// Insertions made during the given time span
+    Duration(Duration),
+}
+
+/// Membership over a sliding window, kept as a ring of [`BloomFilter`]
+/// generations.
+///
+/// With `g` generations each one covers `window / (g - 1)`. New items go to
+/// the newest generation, and once it has covered its share of the window
+/// the oldest one is cleared and becomes the newest. An item is therefore
+/// reported for at least the whole window and at most one generation longer;
+/// expiry happens a generation at a time rather than all at once.
+pub struct SlidingBloomFilter<S = DefaultBuildHasher> {
+    generations: VecDeque<BloomFilter<S>>,
+    window: Window,
+    /// Insertions into the newest generation
+    newest_len: usize,
+    /// When the newest generation started covering its span, for duration
+    /// windows. Unset until the first insertion.
+    newest_start: Option<Instant>,
+}
+
+impl SlidingBloomFilter {
+    /// Sizes the generations for `expected_items` insertions per window at an
+    /// overall false positive rate of `fp_rate`
+    pub fn new(window: Window, expected_items: usize, fp_rate: f64) -> Self {
+        Self::with_hasher(
+            window,
+            expected_items,
+            fp_rate,
+            DefaultBuildHasher::default(),
+        )
+    }
+
+    pub fn with_generations(
+        window: Window,
+        expected_items: usize,
+        fp_rate: f64,
+        num_generations: usize,
+    ) -> Self {
+        Self::with_generations_and_hasher(
+            window,
+            expected_items,
+            fp_rate,
+            num_generations,
+            DefaultBuildHasher::default(),
+        )
+    }
+}
+
+impl<S: BuildHasher + Clone> SlidingBloomFilter<S> {
+    pub fn with_hasher(
+        window: Window,
+        expected_items: usize,
+        fp_rate: f64,
+        hash_builder: S,
+    ) -> Self {
+        Self::with_generations_and_hasher(
+            window,
+            expected_items,
+            fp_rate,
+            DEFAULT_NUM_GENERATIONS,
+            hash_builder,
+        )
+    }
+
+    /// Every generation is sized for its share of `expected_items` at
+    /// `fp_rate / num_generations`, since a lookup checks all of them.
+    ///
+    /// Panics if the window is empty, `num_generations` is less than 2 or
+    /// unless `0 < fp_rate < 1`
+    pub fn with_generations_and_hasher(
+        window: Window,
+        expected_items: usize,
+        fp_rate: f64,
+        num_generations: usize,
+        hash_builder: S,
+    ) -> Self {
+        check_fp_rate(fp_rate);
+        assert!(
+            num_generations >= 2,
+            "sliding filter needs at least two generations"
+        );
+        match window {
+            Window::Items(items) => assert!(items > 0, "window must hold at least one item"),
+            Window::Duration(duration) => assert!(!duration.is_zero(), "window must not be empty"),
+        }
+
+        let generation_items = expected_items.div_ceil(num_generations - 1).max(1);
+        let generation_fp_rate = fp_rate / num_generations as f64;
+
+        let generations = (0..num_generations)
+            .map(|_| {
+                BloomFilter::with_capacity_and_hasher(
+                    generation_items,
+                    generation_fp_rate,
+                    hash_builder.clone(),
+                )
+            })
+            .collect();
+
+        Self {
+            generations,
+            window,
+            newest_len: 0,
+            newest_start: None,
+        }
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    pub fn num_generations(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Bytes taken by the bit arrays of all generations
+    pub fn memory_usage(&self) -> usize {
+        self.generations.iter().map(BloomFilter::memory_usage).sum()
+    }
+
+    /// Adds `item` at time `now`, first expiring the generations that fell
+    /// out of the window. `now` is ignored for item-count windows and must
+    /// not go backwards for duration windows.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T, now: Instant) {
+        self.advance(now);
+
+        self.generations.back_mut().unwrap().insert(item);
+        self.newest_len += 1;
+    }
+
+    /// Reports whether `item` was inserted within the window ending at `now`
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T, now: Instant) -> bool {
+        let expired = match self.window {
+            Window::Items(_) => 0,
+            Window::Duration(_) => self.expired_generations(now),
+        };
+
+        self.generations
+            .iter()
+            .skip(expired)
+            .any(|generation| generation.contains(item))
+    }
+
+    pub fn clear(&mut self) {
+        for generation in &mut self.generations {
+            generation.clear();
+        }
+        self.newest_len = 0;
+        self.newest_start = None;
+    }
+
+    fn advance(&mut self, now: Instant) {
+        match self.window {
+            Window::Items(items) => {
+                if self.newest_len >= items.div_ceil(self.num_generations() - 1) {
+                    self.rotate();
+                }
+            }
+            Window::Duration(_) => {
+                let Some(start) = self.newest_start else {
+                    self.newest_start = Some(now);
+                    return;
+                };
+
+                let expired = self.expired_generations(now);
+                if expired >= self.num_generations() {
+                    self.clear();
+                    self.newest_start = Some(now);
+                    return;
+                }
+
+                for _ in 0..expired {
+                    self.rotate();
+                }
+                self.newest_start = Some(start + self.generation_span() * expired as u32);
+            }
+        }
+    }
+
+    /// Number of the oldest generations a duration window has moved past by
+    /// `now`, capped at the number of generations
+    fn expired_generations(&self, now: Instant) -> usize {
+        let Some(start) = self.newest_start else {
+            return 0;
+        };
+
+        let elapsed = now.saturating_duration_since(start).as_nanos();
+        let steps = elapsed / self.generation_span().as_nanos();
+
+        steps.min(self.num_generations() as u128) as usize
+    }
+
+    fn generation_span(&self) -> Duration {
+        match self.window {
+            Window::Duration(duration) => {
+                (duration / (self.num_generations() - 1) as u32).max(Duration::from_nanos(1))
+            }
+            Window::Items(_) => unreachable!("item windows have no time span"),
+        }
+    }
+
+    fn rotate(&mut self) {
+        let mut oldest = self.generations.pop_front().unwrap();
+        oldest.clear();
+        self.generations.push_back(oldest);
+        self.newest_len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_item_window() {
+        let now = Instant::now();
+        let mut filter = SlidingBloomFilter::new(Window::Items(300), 300, 0.01);
+
+        for i in 0..3_000u32 {
+            filter.insert(&i, now);
+
+            // The last 300 items are always present
+            let oldest = i.saturating_sub(299);
+            assert!((oldest..=i).all(|j| filter.contains(&j, now)));
+        }
+
+        // Items are gone at most one generation after leaving the window
+        let stale = (0..2_500).filter(|i| filter.contains(i, now)).count();
+        assert!(stale < 50, "{stale}");
+    }
+
+    #[test]
+    fn test_duration_window() {
+        let start = Instant::now();
+        let mut filter = SlidingBloomFilter::new(Window::Duration(10 * MINUTE), 1_000, 0.01);
+
+        filter.insert(&"first", start);
+        filter.insert(&"later", start + 7 * MINUTE);
+
+        assert!(filter.contains(&"first", start + 9 * MINUTE));
+        assert!(filter.contains(&"first", start + 10 * MINUTE - Duration::from_nanos(1)));
+        assert!(!filter.contains(&"first", start + 14 * MINUTE));
+
+        assert!(filter.contains(&"later", start + 14 * MINUTE));
+        assert!(!filter.contains(&"later", start + 21 * MINUTE));
+    }
+
+    #[test]
+    fn test_items_last_whole_window_in_any_phase() {
+        let start = Instant::now();
+        let window = 10 * MINUTE;
+
+        for offset_secs in [0, 1, 119, 199, 200, 201, 599] {
+            let mut filter = SlidingBloomFilter::new(Window::Duration(window), 1_000, 0.01);
+            filter.insert(&"anchor", start);
+
+            // A burst at an arbitrary phase of the generation cycle
+            let burst = start + Duration::from_secs(offset_secs);
+            for i in 0..100 {
+                filter.insert(&i, burst);
+            }
+
+            let end = burst + window - Duration::from_secs(1);
+            for t in (0..window.as_secs()).step_by(30) {
+                let now = (burst + Duration::from_secs(t)).min(end);
+                assert!((0..100).all(|i| filter.contains(&i, now)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_gap_expires_everything() {
+        let start = Instant::now();
+        let mut filter = SlidingBloomFilter::new(Window::Duration(MINUTE), 100, 0.01);
+
+        for i in 0..100 {
+            filter.insert(&i, start);
+        }
+
+        let later = start + 60 * MINUTE;
+        assert!((0..100).all(|i| !filter.contains(&i, later)));
+
+        filter.insert(&"new", later);
+        assert!(filter.contains(&"new", later));
+        assert!((0..100).all(|i| !filter.contains(&i, later)));
+    }
+
+    #[test]
+    fn test_fp_rate() {
+        let now = Instant::now();
+        let mut filter = SlidingBloomFilter::new(Window::Items(3_000), 3_000, 0.01);
+
+        for i in 0..10_000 {
+            filter.insert(&i, now);
+        }
+
+        let false_positives = (1u64 << 40..(1u64 << 40) + 100_000)
+            .filter(|i| filter.contains(i, now))
+            .count();
+        assert!(false_positives < 1_000, "{false_positives}");
+    }
+
+    #[test]
+    fn test_clear() {
+        let now = Instant::now();
+        let mut filter = SlidingBloomFilter::new(Window::Duration(MINUTE), 100, 0.01);
+
+        filter.insert(&"hello", now);
+        filter.clear();
+        assert!(!filter.contains(&"hello", now));
+    }
+}
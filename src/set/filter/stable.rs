@@ -0,0 +1,318 @@
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::filter::packed::PackedArray;
+use crate::set::filter::params::check_fp_rate;
+use crate::set::filter::probe::hash_indices;
+use std::hash::{BuildHasher, Hash};
+
+const DEFAULT_CELL_BITS: u32 = 1;
+
+/// Bloom filter for unbounded streams (Deng & Rafiei, "Approximately
+/// Detecting Duplicates for Streaming Data using Stable Bloom Filters").
+///
+/// Every insert first decrements `P` cells, then sets the item's `k` cells to
+/// their maximum. Old items fade out, so the fraction of zero cells converges
+/// to a fixed point instead of reaching zero, and the false positive rate
+/// stays bounded however long the stream runs. The price is false negatives:
+/// an item inserted long enough ago may be reported absent.
+pub struct StableBloomFilter<S = DefaultBuildHasher> {
+    cells: PackedArray,
+    num_hashes: u32,
+    decrements: usize,
+    rng_state: u64,
+    hash_builder: S,
+}
+
+impl StableBloomFilter {
+    /// One-bit cells, with `k` and `P` picked so the rate once the filter has
+    /// stabilized stays at or below `fp_rate`
+    pub fn new(num_cells: usize, fp_rate: f64) -> Self {
+        Self::with_hasher(num_cells, fp_rate, DefaultBuildHasher::default())
+    }
+
+    pub fn with_params(
+        num_cells: usize,
+        cell_bits: u32,
+        num_hashes: u32,
+        decrements: usize,
+    ) -> Self {
+        Self::with_params_and_hasher(
+            num_cells,
+            cell_bits,
+            num_hashes,
+            decrements,
+            DefaultBuildHasher::default(),
+        )
+    }
+}
+
+impl<S: BuildHasher> StableBloomFilter<S> {
+    /// Panics if `num_cells` is less than 2 or unless `0 < fp_rate < 1`
+    pub fn with_hasher(num_cells: usize, fp_rate: f64, hash_builder: S) -> Self {
+        check_fp_rate(fp_rate);
+        assert!(
+            num_cells >= 2,
+            "stable bloom filter needs at least two cells"
+        );
+
+        let max_hashes = u32::try_from(num_cells - 1).unwrap_or(u32::MAX);
+        let num_hashes = ((1.0 / fp_rate).log2().ceil() as u32).clamp(1, max_hashes);
+        let decrements = optimal_decrements(num_cells, num_hashes, DEFAULT_CELL_BITS, fp_rate);
+
+        Self::with_params_and_hasher(
+            num_cells,
+            DEFAULT_CELL_BITS,
+            num_hashes,
+            decrements,
+            hash_builder,
+        )
+    }
+
+    /// Panics if `num_cells`, `num_hashes` or `decrements` is zero, if
+    /// `num_hashes >= num_cells`, or unless `1 <= cell_bits <= 8`
+    pub fn with_params_and_hasher(
+        num_cells: usize,
+        cell_bits: u32,
+        num_hashes: u32,
+        decrements: usize,
+        hash_builder: S,
+    ) -> Self {
+        assert!(
+            num_cells > 0,
+            "stable bloom filter must have at least one cell"
+        );
+        assert!((1..=8).contains(&cell_bits), "cells must have 1 to 8 bits");
+        assert!(
+            num_hashes > 0,
+            "stable bloom filter must use at least one hash"
+        );
+        assert!(
+            (num_hashes as usize) < num_cells,
+            "stable bloom filter needs more cells than hashes"
+        );
+        assert!(
+            decrements > 0,
+            "stable bloom filter must decrement at least one cell"
+        );
+
+        Self {
+            cells: PackedArray::new(num_cells, cell_bits),
+            num_hashes,
+            decrements,
+            rng_state: 0x2545_f491_4f6c_dd1d,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cell_bits(&self) -> u32 {
+        self.cells.width()
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Number of cells decremented by every insert, `P` in the paper
+    pub fn decrements(&self) -> usize {
+        self.decrements
+    }
+
+    /// Bytes taken by the cells
+    pub fn memory_usage(&self) -> usize {
+        self.cells.memory_usage()
+    }
+
+    /// False positive rate the filter converges to, `(1 - Z)^k` where
+    /// `Z = (1 / (1 + 1 / (P * (1/k - 1/m))))^Max` is the limit of the
+    /// fraction of zero cells
+    pub fn stable_fp_rate(&self) -> f64 {
+        stable_fp_rate(
+            self.num_cells(),
+            self.num_hashes,
+            self.cell_bits(),
+            self.decrements,
+        )
+    }
+
+    /// Fraction of cells currently at zero
+    pub fn zero_fraction(&self) -> f64 {
+        let zeros = (0..self.num_cells())
+            .filter(|&index| self.cells.get(index) == 0)
+            .count();
+
+        zeros as f64 / self.num_cells() as f64
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let hash = hash128(&self.hash_builder, item);
+        self.add(hash);
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.is_set(hash128(&self.hash_builder, item))
+    }
+
+    /// Reports whether `item` was present, then inserts it. Hashes the item
+    /// only once.
+    pub fn test_and_add<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let hash = hash128(&self.hash_builder, item);
+        let present = self.is_set(hash);
+        self.add(hash);
+
+        present
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    fn is_set(&self, hash: (u64, u64)) -> bool {
+        hash_indices(hash, self.num_hashes, self.num_cells())
+            .all(|index| self.cells.get(index) != 0)
+    }
+
+    /// Decrements `P` consecutive cells from a random start, which ages cells
+    /// at the same rate as picking each at random and touches fewer cache
+    /// lines, then sets the item's cells
+    fn add(&mut self, hash: (u64, u64)) {
+        let num_cells = self.num_cells();
+        let start = (self.next_random() % num_cells as u64) as usize;
+
+        for offset in 0..self.decrements.min(num_cells) {
+            let index = (start + offset) % num_cells;
+            let value = self.cells.get(index);
+            if value > 0 {
+                self.cells.set(index, value - 1);
+            }
+        }
+
+        let max = self.max_value();
+        for index in hash_indices(hash, self.num_hashes, num_cells) {
+            self.cells.set(index, max);
+        }
+    }
+
+    fn max_value(&self) -> u64 {
+        (1 << self.cell_bits()) - 1
+    }
+
+    /// xorshift64*, only used to pick the cells to decrement
+    fn next_random(&mut self) -> u64 {
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        self.rng_state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+fn stable_fp_rate(num_cells: usize, num_hashes: u32, cell_bits: u32, decrements: usize) -> f64 {
+    let rate = decrements as f64 * (1.0 / num_hashes as f64 - 1.0 / num_cells as f64);
+    let max = (1u32 << cell_bits) - 1;
+    let zeros = (1.0 / (1.0 + 1.0 / rate)).powi(max as i32);
+
+    (1.0 - zeros).powi(num_hashes as i32)
+}
+
+/// Smallest `P` whose stable false positive rate is at most `fp_rate`,
+/// solving the fixed point of [`stable_fp_rate`] for `P`
+fn optimal_decrements(num_cells: usize, num_hashes: u32, cell_bits: u32, fp_rate: f64) -> usize {
+    let max = ((1u32 << cell_bits) - 1) as f64;
+    let zeros = (1.0 - fp_rate.powf(1.0 / num_hashes as f64)).powf(1.0 / max);
+    let denominator = (1.0 / zeros - 1.0) * (1.0 / num_hashes as f64 - 1.0 / num_cells as f64);
+
+    ((1.0 / denominator).ceil() as usize).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic]
+    fn test_needs_more_cells_than_hashes() {
+        StableBloomFilter::with_params(3, 3, 3, 1);
+    }
+
+    #[test]
+    fn test_params_from_fp_rate() {
+        let filter = StableBloomFilter::new(10_000, 0.01);
+
+        assert_eq!(filter.cell_bits(), 1);
+        assert_eq!(filter.num_hashes(), 7);
+        assert_eq!(filter.decrements(), 7);
+        assert!(filter.stable_fp_rate() <= 0.01);
+
+        // One fewer decrement would miss the target
+        let looser = StableBloomFilter::with_params(10_000, 1, 7, 6);
+        assert!(looser.stable_fp_rate() > 0.01);
+    }
+
+    #[test]
+    fn test_recent_items_are_present() {
+        let mut filter = StableBloomFilter::new(10_000, 0.01);
+
+        for i in 0..100_000 {
+            filter.insert(&i);
+            assert!(filter.contains(&i));
+        }
+    }
+
+    #[test]
+    fn test_and_add() {
+        let mut filter = StableBloomFilter::new(1_000, 0.01);
+
+        assert!(!filter.test_and_add(&"event"));
+        assert!(filter.test_and_add(&"event"));
+
+        filter.clear();
+        assert!(!filter.contains(&"event"));
+    }
+
+    #[test]
+    fn test_fp_rate_stays_bounded() {
+        for cell_bits in [1, 3] {
+            let mut filter = StableBloomFilter::with_params(10_000, cell_bits, 5, 10);
+            let expected = filter.stable_fp_rate();
+
+            // Far more items than a plain Bloom filter of this size could hold
+            for i in 0..500_000u64 {
+                filter.insert(&i);
+            }
+
+            // The limit of the zero fraction follows from the stable rate
+            let zeros = 1.0 - expected.powf(1.0 / 5.0);
+            assert!((filter.zero_fraction() - zeros).abs() < 0.02);
+
+            let false_positives = (1u64 << 40..(1u64 << 40) + 100_000)
+                .filter(|i| filter.contains(i))
+                .count();
+            let measured = false_positives as f64 / 100_000.0;
+            assert!(
+                (measured - expected).abs() < 0.2 * expected,
+                "measured {measured}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_old_items_fade() {
+        let mut filter = StableBloomFilter::new(1_000, 0.01);
+
+        for i in 0..100 {
+            filter.insert(&i);
+        }
+        for i in 100..100_000 {
+            filter.insert(&i);
+        }
+
+        let remembered = (0..100).filter(|i| filter.contains(i)).count();
+        assert!(remembered < 10, "{remembered}");
+    }
+}
@@ -0,0 +1,271 @@
+use crate::set::filter::{
+    AtomicBloomFilter, BlockedBloomFilter, BloomFilter, CountingBloomFilter, CuckooFilter,
+    FilterFull, QuotientFilter, ScalableBloomFilter,
+};
+use std::hash::{BuildHasher, Hash};
+
+/// Approximate membership structure: `contains` never returns a false
+/// negative for an inserted item but may return false positives.
+pub trait Filter {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull>;
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool;
+
+    fn clear(&mut self);
+
+    /// Number of items held, as counted by the implementation. Filters that
+    /// skip items they already report as present may count fewer than were
+    /// inserted.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes taken by the filter's table
+    fn memory_bytes(&self) -> usize;
+
+    /// False positive rate predicted for the filter's current contents
+    fn expected_fp_rate(&self) -> f64;
+}
+
+/// Filter that can forget an inserted item
+pub trait DeletableFilter: Filter {
+    /// Removes one occurrence of `item`. Returns `false` if it was
+    /// definitely absent.
+    ///
+    /// Removing an item that was never inserted can cause false negatives.
+    fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool;
+}
+
+impl<S: BuildHasher> Filter for BloomFilter<S> {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        BloomFilter::insert(self, item);
+        Ok(())
+    }
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        BloomFilter::contains(self, item)
+    }
+
+    fn clear(&mut self) {
+        BloomFilter::clear(self);
+    }
+
+    fn len(&self) -> usize {
+        BloomFilter::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.memory_usage()
+    }
+
+    fn expected_fp_rate(&self) -> f64 {
+        self.fp_rate()
+    }
+}
+
+impl<S: BuildHasher> Filter for AtomicBloomFilter<S> {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        AtomicBloomFilter::insert(self, item);
+        Ok(())
+    }
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        AtomicBloomFilter::contains(self, item)
+    }
+
+    fn clear(&mut self) {
+        AtomicBloomFilter::clear(self);
+    }
+
+    fn len(&self) -> usize {
+        AtomicBloomFilter::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.memory_usage()
+    }
+
+    fn expected_fp_rate(&self) -> f64 {
+        self.fp_rate()
+    }
+}
+
+impl<S: BuildHasher> Filter for BlockedBloomFilter<S> {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        BlockedBloomFilter::insert(self, item);
+        Ok(())
+    }
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        BlockedBloomFilter::contains(self, item)
+    }
+
+    fn clear(&mut self) {
+        BlockedBloomFilter::clear(self);
+    }
+
+    fn len(&self) -> usize {
+        BlockedBloomFilter::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.memory_usage()
+    }
+
+    fn expected_fp_rate(&self) -> f64 {
+        self.fp_rate_at(BlockedBloomFilter::len(self))
+    }
+}
+
+impl<S: BuildHasher> Filter for CountingBloomFilter<S> {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        CountingBloomFilter::insert(self, item);
+        Ok(())
+    }
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        CountingBloomFilter::contains(self, item)
+    }
+
+    fn clear(&mut self) {
+        CountingBloomFilter::clear(self);
+    }
+
+    fn len(&self) -> usize {
+        CountingBloomFilter::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.memory_usage()
+    }
+
+    fn expected_fp_rate(&self) -> f64 {
+        self.fp_rate()
+    }
+}
+
+impl<S: BuildHasher> DeletableFilter for CountingBloomFilter<S> {
+    fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        CountingBloomFilter::remove(self, item)
+    }
+}
+
+impl<S: BuildHasher + Clone> Filter for ScalableBloomFilter<S> {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        ScalableBloomFilter::insert(self, item);
+        Ok(())
+    }
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        ScalableBloomFilter::contains(self, item)
+    }
+
+    fn clear(&mut self) {
+        ScalableBloomFilter::clear(self);
+    }
+
+    fn len(&self) -> usize {
+        ScalableBloomFilter::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.memory_usage()
+    }
+
+    fn expected_fp_rate(&self) -> f64 {
+        self.current_fp_rate()
+    }
+}
+
+impl<S: BuildHasher> Filter for CuckooFilter<S> {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        CuckooFilter::insert(self, item)
+    }
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        CuckooFilter::contains(self, item)
+    }
+
+    fn clear(&mut self) {
+        CuckooFilter::clear(self);
+    }
+
+    fn len(&self) -> usize {
+        CuckooFilter::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.memory_usage()
+    }
+
+    fn expected_fp_rate(&self) -> f64 {
+        self.fp_rate()
+    }
+}
+
+impl<S: BuildHasher> DeletableFilter for CuckooFilter<S> {
+    fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        CuckooFilter::remove(self, item)
+    }
+}
+
+impl<S: BuildHasher> Filter for QuotientFilter<S> {
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> Result<(), FilterFull> {
+        QuotientFilter::insert(self, item)
+    }
+
+    fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        QuotientFilter::contains(self, item)
+    }
+
+    fn clear(&mut self) {
+        QuotientFilter::clear(self);
+    }
+
+    fn len(&self) -> usize {
+        QuotientFilter::len(self)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.memory_usage()
+    }
+
+    fn expected_fp_rate(&self) -> f64 {
+        self.fp_rate()
+    }
+}
+
+impl<S: BuildHasher> DeletableFilter for QuotientFilter<S> {
+    fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        QuotientFilter::remove(self, item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_all<F: Filter>(filter: &mut F, items: std::ops::Range<u32>) {
+        for item in items {
+            filter.insert(&item).unwrap();
+        }
+    }
+
+    fn contains_all<F: Filter>(filter: &F, items: std::ops::Range<u32>) -> bool {
+        items.into_iter().all(|item| filter.contains(&item))
+    }
+
+    #[test]
+    fn test_generic_usage() {
+        let mut bloom = BloomFilter::with_capacity(1_000, 0.01);
+        let mut cuckoo = CuckooFilter::new(1_000);
+
+        insert_all(&mut bloom, 0..500);
+        insert_all(&mut cuckoo, 0..500);
+
+        assert!(contains_all(&bloom, 0..500));
+        assert!(contains_all(&cuckoo, 0..500));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::hash::DefaultBuildHasher;
+use crate::set::DecodeError;
+use crate::set::codec::Reader;
+use crate::set::filter::BloomFilter;
+use crate::set::filter::bloom::EncodedParts;
+use crate::set::filter::probe::cell_indices;
+use std::hash::{BuildHasher, Hash};
+
+/// Read-only [`BloomFilter`] queried in place from its serialized bytes.
+///
+/// `B` is whatever owns or borrows the bytes: a `&[u8]`, a `Vec<u8>` or, with
+/// the `mmap` feature, a memory-mapped file (see [`BloomFilter::open_mmap`]).
+/// The header and checksum are validated once, when the view is created;
+/// lookups then read the bits straight from `B` without copying them.
+pub struct BloomFilterView<B, S = DefaultBuildHasher> {
+    bytes: B,
+    words_offset: usize,
+    num_bits: usize,
+    num_hashes: u32,
+    len: usize,
+    hash_builder: S,
+}
+
+impl<B: AsRef<[u8]>> BloomFilterView<B> {
+    /// `bytes` must hold exactly what [`BloomFilter::to_bytes`] produced
+    pub fn new(bytes: B) -> Result<Self, DecodeError> {
+        Self::with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<B: AsRef<[u8]>, S: BuildHasher> BloomFilterView<B, S> {
+    /// The hasher must match the one the filter was built with
+    pub fn with_hasher(bytes: B, hash_builder: S) -> Result<Self, DecodeError> {
+        let slice = bytes.as_ref();
+        let mut reader = Reader::new(slice);
+        let parts = EncodedParts::read(&mut reader)?;
+        reader.finish()?;
+
+        let words_offset = parts.words.as_ptr() as usize - slice.as_ptr() as usize;
+
+        Ok(Self {
+            words_offset,
+            num_bits: parts.num_bits,
+            num_hashes: parts.num_hashes,
+            len: parts.len,
+            bytes,
+            hash_builder,
+        })
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Number of insertions recorded when the filter was serialized
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Words are stored little-endian, so bit `i` is bit `i % 8` of byte
+    /// `i / 8` and no alignment is needed
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let words = &self.bytes.as_ref()[self.words_offset..];
+
+        cell_indices(&self.hash_builder, item, self.num_hashes, self.num_bits)
+            .all(|index| words[index / 8] & (1 << (index % 8)) != 0)
+    }
+
+    /// Copies the bits into an owned, mutable filter
+    pub fn to_bloom_filter(&self) -> BloomFilter<S>
+    where
+        S: Clone,
+    {
+        let bits = self.bytes.as_ref()[self.words_offset..]
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+
+        BloomFilter::from_parts(
+            bits,
+            self.num_bits,
+            self.num_hashes,
+            self.len,
+            self.hash_builder.clone(),
+        )
+    }
+}
+
+#[cfg(feature = "mmap")]
+mod mmap {
+    use super::BloomFilterView;
+    use crate::hash::DefaultBuildHasher;
+    use crate::set::filter::BloomFilter;
+    use memmap2::Mmap;
+    use std::fs::File;
+    use std::hash::BuildHasher;
+    use std::io;
+    use std::path::Path;
+
+    /// [`BloomFilterView`] over a memory-mapped file
+    pub type MmapBloomFilter<S = DefaultBuildHasher> = BloomFilterView<Mmap, S>;
+
+    impl BloomFilter {
+        /// Maps a file written from [`BloomFilter::to_bytes`] and queries it
+        /// in place. Pages are loaded by the OS as lookups touch them.
+        ///
+        /// Fails with [`io::ErrorKind::InvalidData`] wrapping a
+        /// [`DecodeError`](crate::set::DecodeError) if the file is truncated
+        /// or corrupt. The whole file is read once to verify its checksum.
+        pub fn open_mmap(path: impl AsRef<Path>) -> io::Result<MmapBloomFilter> {
+            Self::open_mmap_with_hasher(path, DefaultBuildHasher::default())
+        }
+    }
+
+    impl<S: BuildHasher> BloomFilter<S> {
+        /// The file must not be modified while it is mapped
+        pub fn open_mmap_with_hasher(
+            path: impl AsRef<Path>,
+            hash_builder: S,
+        ) -> io::Result<MmapBloomFilter<S>> {
+            let file = File::open(path)?;
+            // SAFETY: the mapping is read-only, and the caller guarantees the
+            // file is not changed underneath it
+            let map = unsafe { Mmap::map(&file)? };
+
+            BloomFilterView::with_hasher(map, hash_builder)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::set::DecodeError;
+        use std::io::Write;
+
+        fn temp_path(name: &str) -> std::path::PathBuf {
+            std::env::temp_dir().join(format!("basalgo-{}-{name}", std::process::id()))
+        }
+
+        #[test]
+        fn test_open_mmap() {
+            let mut filter = BloomFilter::with_capacity(10_000, 0.01);
+            for i in 0..10_000 {
+                filter.insert(&i);
+            }
+
+            let path = temp_path("open.blmf");
+            std::fs::write(&path, filter.to_bytes()).unwrap();
+
+            let mapped = BloomFilter::open_mmap(&path).unwrap();
+            assert_eq!(mapped.len(), 10_000);
+            for i in 0..20_000 {
+                assert_eq!(mapped.contains(&i), filter.contains(&i));
+            }
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_open_truncated_file() {
+            let mut filter = BloomFilter::with_capacity(1_000, 0.01);
+            filter.insert(&"hello");
+            let bytes = filter.to_bytes();
+
+            let path = temp_path("truncated.blmf");
+            let mut file = File::create(&path).unwrap();
+            file.write_all(&bytes[..bytes.len() / 2]).unwrap();
+            drop(file);
+
+            let error = BloomFilter::open_mmap(&path).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(
+                error.get_ref().unwrap().downcast_ref::<DecodeError>(),
+                Some(&DecodeError::Truncated)
+            );
+
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(
+                BloomFilter::open_mmap(&path).err().unwrap().kind(),
+                io::ErrorKind::NotFound
+            );
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+pub use mmap::MmapBloomFilter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> BloomFilter {
+        let mut filter = BloomFilter::with_capacity(1_000, 0.01);
+        for i in 0..1_000 {
+            filter.insert(&i);
+        }
+        filter
+    }
+
+    #[test]
+    fn test_matches_owned_filter() {
+        let filter = filter();
+        let bytes = filter.to_bytes();
+        let view = BloomFilterView::new(bytes.as_slice()).unwrap();
+
+        assert_eq!(view.num_bits(), filter.num_bits());
+        assert_eq!(view.num_hashes(), filter.num_hashes());
+        assert_eq!(view.len(), 1_000);
+        for i in 0..5_000 {
+            assert_eq!(view.contains(&i), filter.contains(&i));
+        }
+
+        assert_eq!(view.to_bloom_filter().to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_unaligned_bytes() {
+        let bytes = filter().to_bytes();
+        let mut shifted = vec![0];
+        shifted.extend_from_slice(&bytes);
+
+        let view = BloomFilterView::new(&shifted[1..]).unwrap();
+        assert!((0..1_000).all(|i| view.contains(&i)));
+    }
+
+    #[test]
+    fn test_validation() {
+        let bytes = filter().to_bytes();
+
+        assert_eq!(
+            BloomFilterView::new(&bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::Truncated)
+        );
+        assert_eq!(
+            BloomFilterView::new(&bytes[..20]).err(),
+            Some(DecodeError::Truncated)
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[100] ^= 1;
+        assert_eq!(
+            BloomFilterView::new(corrupted).err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(BloomFilterView::new(trailing).is_err());
+    }
+}
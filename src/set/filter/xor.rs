@@ -0,0 +1,441 @@
+use crate::hash::{DefaultBuildHasher, murmur64, splitmix64};
+use crate::set::DecodeError;
+use crate::set::codec::{Reader, checksum};
+use crate::set::filter::BuildFailed;
+use std::hash::{BuildHasher, Hash};
+
+pub(crate) const DEFAULT_SEED: u64 = 0x726b_2b9d_438b_9d4d;
+pub(crate) const MAX_ATTEMPTS: usize = 100;
+
+const MAGIC: &[u8; 4] = b"XORF";
+const VERSION: u8 = 2;
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+}
+
+/// Fingerprint stored per slot of the static filters, implemented for `u8`
+/// and `u16`.
+pub trait Fingerprint:
+    private::Sealed + Copy + Default + Eq + std::ops::BitXor<Output = Self>
+{
+    const BITS: u32;
+
+    fn from_hash(hash: u64) -> Self;
+
+    fn to_le(self, out: &mut Vec<u8>);
+
+    fn from_le(bytes: &[u8]) -> Self;
+}
+
+impl Fingerprint for u8 {
+    const BITS: u32 = 8;
+
+    fn from_hash(hash: u64) -> Self {
+        (hash ^ (hash >> 32)) as u8
+    }
+
+    fn to_le(self, out: &mut Vec<u8>) {
+        out.push(self);
+    }
+
+    fn from_le(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl Fingerprint for u16 {
+    const BITS: u32 = 16;
+
+    fn from_hash(hash: u64) -> Self {
+        (hash ^ (hash >> 32)) as u16
+    }
+
+    fn to_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn from_le(bytes: &[u8]) -> Self {
+        u16::from_le_bytes([bytes[0], bytes[1]])
+    }
+}
+
+/// Hashes every key once and drops duplicates, which would otherwise make
+/// peeling impossible
+pub(crate) fn unique_key_hashes<T: Hash, S: BuildHasher>(keys: &[T], hash_builder: &S) -> Vec<u64> {
+    let mut hashes: Vec<u64> = keys.iter().map(|key| hash_builder.hash_one(key)).collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
+
+/// Peels the 3-uniform hypergraph whose edges are `positions(hash)` for every
+/// hash, returning the edges in peeling order along with the slot each one
+/// was peeled from, or `None` if the graph has a non-empty 2-core.
+pub(crate) fn peel(
+    hashes: &[u64],
+    num_slots: usize,
+    positions: impl Fn(u64) -> [usize; 3],
+) -> Option<Vec<(u64, usize)>> {
+    let mut counts = vec![0u32; num_slots];
+    let mut xors = vec![0u64; num_slots];
+
+    for &hash in hashes {
+        for slot in positions(hash) {
+            counts[slot] += 1;
+            xors[slot] ^= hash;
+        }
+    }
+
+    let mut queue: Vec<usize> = (0..num_slots).filter(|&slot| counts[slot] == 1).collect();
+    let mut stack = Vec::with_capacity(hashes.len());
+
+    while let Some(slot) = queue.pop() {
+        if counts[slot] != 1 {
+            continue;
+        }
+
+        let hash = xors[slot];
+        stack.push((hash, slot));
+
+        for other in positions(hash) {
+            counts[other] -= 1;
+            xors[other] ^= hash;
+            if counts[other] == 1 {
+                queue.push(other);
+            }
+        }
+    }
+
+    (stack.len() == hashes.len()).then_some(stack)
+}
+
+/// Fills the slots in reverse peeling order so that the fingerprints at the
+/// three positions of every hash xor to its fingerprint
+pub(crate) fn assign<F: Fingerprint>(
+    stack: &[(u64, usize)],
+    num_slots: usize,
+    positions: impl Fn(u64) -> [usize; 3],
+) -> Vec<F> {
+    let mut fingerprints = vec![F::default(); num_slots];
+
+    for &(hash, slot) in stack.iter().rev() {
+        let [a, b, c] = positions(hash);
+        fingerprints[slot] =
+            F::from_hash(hash) ^ fingerprints[a] ^ fingerprints[b] ^ fingerprints[c];
+    }
+
+    fingerprints
+}
+
+/// Appends the fingerprints and a checksum over everything in `out`, which
+/// holds just the header
+pub(crate) fn encode_fingerprints<F: Fingerprint>(fingerprints: &[F], out: &mut Vec<u8>) {
+    for &fingerprint in fingerprints {
+        fingerprint.to_le(out);
+    }
+
+    let checksum = checksum(&[out]);
+    out.extend_from_slice(&checksum.to_le_bytes());
+}
+
+/// Reads the fingerprints and checks them against the checksum, which also
+/// covers `header`, the bytes before them
+pub(crate) fn decode_fingerprints<F: Fingerprint>(
+    reader: &mut Reader<'_>,
+    header: &[u8],
+    len: usize,
+) -> Result<Vec<F>, DecodeError> {
+    let width = F::BITS as usize / 8;
+    if reader.remaining() / width < len {
+        return Err(DecodeError::Truncated);
+    }
+
+    let bytes = reader.read_bytes(len * width)?;
+    if checksum(&[header, bytes]) != reader.read_u64()? {
+        return Err(DecodeError::ChecksumMismatch);
+    }
+
+    Ok(bytes.chunks_exact(width).map(F::from_le).collect())
+}
+
+/// Xor filter (Graf, Lemire, "Xor Filters: Faster and Smaller Than Bloom and
+/// Cuckoo Filters") over an immutable set of keys.
+///
+/// Each key maps to one slot in each third of a table of `1.23 * n + 32`
+/// fingerprints, and the table is solved so that the three slots of every
+/// key xor to its fingerprint. With 8-bit fingerprints that is ~9.84 bits
+/// per key for a false positive rate of ~0.39%.
+pub struct XorFilter<F, S = DefaultBuildHasher> {
+    fingerprints: Vec<F>,
+    block_length: usize,
+    seed: u64,
+    len: usize,
+    hash_builder: S,
+}
+
+pub type XorFilter8<S = DefaultBuildHasher> = XorFilter<u8, S>;
+pub type XorFilter16<S = DefaultBuildHasher> = XorFilter<u16, S>;
+
+impl<F: Fingerprint> XorFilter<F> {
+    pub fn build<T: Hash>(keys: &[T]) -> Result<Self, BuildFailed> {
+        Self::build_with_hasher(keys, DefaultBuildHasher::default())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<F: Fingerprint, S: BuildHasher> XorFilter<F, S> {
+    pub fn build_with_hasher<T: Hash>(keys: &[T], hash_builder: S) -> Result<Self, BuildFailed> {
+        Self::build_with_seed_and_hasher(keys, DEFAULT_SEED, hash_builder)
+    }
+
+    /// Tries up to 100 seeds derived from `seed`. The same keys, seed and
+    /// hasher always produce the same filter.
+    pub fn build_with_seed_and_hasher<T: Hash>(
+        keys: &[T],
+        seed: u64,
+        hash_builder: S,
+    ) -> Result<Self, BuildFailed> {
+        let hashes = unique_key_hashes(keys, &hash_builder);
+        let block_length = (hashes.len() * 123 / 100 + 32).div_ceil(3);
+
+        let mut rng_state = seed;
+        for _ in 0..MAX_ATTEMPTS {
+            let seed = splitmix64(&mut rng_state);
+            let positions = |hash| Self::positions(hash, block_length);
+
+            let seeded: Vec<u64> = hashes
+                .iter()
+                .map(|&hash| murmur64(hash.wrapping_add(seed)))
+                .collect();
+
+            if let Some(stack) = peel(&seeded, 3 * block_length, positions) {
+                return Ok(Self {
+                    fingerprints: assign(&stack, 3 * block_length, positions),
+                    block_length,
+                    seed,
+                    len: hashes.len(),
+                    hash_builder,
+                });
+            }
+        }
+
+        Err(BuildFailed)
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let hash = murmur64(self.hash_builder.hash_one(item).wrapping_add(self.seed));
+        let [a, b, c] = Self::positions(hash, self.block_length);
+
+        F::from_hash(hash) == self.fingerprints[a] ^ self.fingerprints[b] ^ self.fingerprints[c]
+    }
+
+    /// Number of distinct keys the filter was built from
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Seed that produced the filter
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Bytes taken by the fingerprint table
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.fingerprints.as_slice())
+    }
+
+    pub fn bits_per_key(&self) -> f64 {
+        (self.memory_usage() * 8) as f64 / self.len.max(1) as f64
+    }
+
+    /// Serializes the seed and fingerprint table. The hasher is not stored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(40 + self.memory_usage());
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(F::BITS as u8);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.len as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.block_length as u64).to_le_bytes());
+        encode_fingerprints(&self.fingerprints, &mut bytes);
+
+        bytes
+    }
+
+    pub fn from_bytes_with_hasher(bytes: &[u8], hash_builder: S) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+
+        let version = reader.read_header(MAGIC)?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        if reader.read_u8()? as u32 != F::BITS {
+            return Err(DecodeError::Invalid("fingerprint width mismatch"));
+        }
+
+        let seed = reader.read_u64()?;
+        let len = reader.read_usize()?;
+        let block_length = reader.read_usize()?;
+
+        if block_length == 0 {
+            return Err(DecodeError::Invalid("empty xor filter"));
+        }
+        let num_slots = block_length
+            .checked_mul(3)
+            .ok_or(DecodeError::Invalid("xor filter too large"))?;
+
+        let header = &bytes[..bytes.len() - reader.remaining()];
+        let fingerprints = decode_fingerprints(&mut reader, header, num_slots)?;
+        reader.finish()?;
+
+        Ok(Self {
+            fingerprints,
+            block_length,
+            seed,
+            len,
+            hash_builder,
+        })
+    }
+
+    fn positions(hash: u64, block_length: usize) -> [usize; 3] {
+        let reduce = |hash: u64| ((hash as u32 as u64 * block_length as u64) >> 32) as usize;
+
+        [
+            reduce(hash),
+            reduce(hash.rotate_left(21)) + block_length,
+            reduce(hash.rotate_left(42)) + 2 * block_length,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_false_negatives() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let filter = XorFilter8::build(&keys).unwrap();
+        assert_eq!(filter.len(), 10_000);
+        assert!(keys.iter().all(|key| filter.contains(key)));
+
+        let filter = XorFilter16::build(&keys).unwrap();
+        assert!(keys.iter().all(|key| filter.contains(key)));
+    }
+
+    #[test]
+    fn test_fp_rate_and_size() {
+        let keys: Vec<u64> = (0..100_000).collect();
+
+        let filter = XorFilter8::build(&keys).unwrap();
+        let false_positives = (100_000..1_100_000u64)
+            .filter(|key| filter.contains(key))
+            .count();
+        assert!(false_positives < 5_000, "{false_positives}");
+        assert!(filter.bits_per_key() < 10.0);
+
+        let filter = XorFilter16::build(&keys).unwrap();
+        let false_positives = (100_000..1_100_000u64)
+            .filter(|key| filter.contains(key))
+            .count();
+        assert!(false_positives < 50, "{false_positives}");
+    }
+
+    #[test]
+    fn test_duplicates_and_empty_input() {
+        let keys = ["a", "b", "a", "c", "b"];
+        let filter = XorFilter8::build(&keys).unwrap();
+        assert_eq!(filter.len(), 3);
+        assert!(keys.iter().all(|key| filter.contains(key)));
+
+        let filter = XorFilter8::build::<u64>(&[]).unwrap();
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let keys: Vec<u64> = (0..1_000).collect();
+
+        let a = XorFilter8::build(&keys).unwrap();
+        let b = XorFilter8::build(&keys).unwrap();
+        assert_eq!(a.seed(), b.seed());
+        assert_eq!(a.fingerprints, b.fingerprints);
+
+        let c = XorFilter8::build_with_seed_and_hasher(&keys, 42, DefaultBuildHasher::default())
+            .unwrap();
+        assert_ne!(a.seed(), c.seed());
+    }
+
+    #[test]
+    fn test_peel_failure() {
+        // Two edges over the same three slots form a 2-core
+        assert!(peel(&[1, 2], 3, |_| [0, 1, 2]).is_none());
+
+        let stack = peel(&[7], 3, |_| [0, 1, 2]).unwrap();
+        let fingerprints: Vec<u8> = assign(&stack, 3, |_| [0, 1, 2]);
+        assert_eq!(
+            fingerprints[0] ^ fingerprints[1] ^ fingerprints[2],
+            u8::from_hash(7)
+        );
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = XorFilter16::build(&keys).unwrap();
+
+        let bytes = filter.to_bytes();
+        let decoded = XorFilter16::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.fingerprints, filter.fingerprints);
+        assert_eq!(decoded.len(), 1_000);
+        assert!(keys.iter().all(|key| decoded.contains(key)));
+
+        assert_eq!(
+            XorFilter8::from_bytes(&bytes).err(),
+            Some(DecodeError::Invalid("fingerprint width mismatch"))
+        );
+        assert_eq!(
+            XorFilter16::from_bytes(&bytes[..bytes.len() - 9]).err(),
+            Some(DecodeError::Truncated)
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[40] ^= 1;
+        assert_eq!(
+            XorFilter16::from_bytes(&corrupted).err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+
+        // The seed is covered by the checksum too
+        let mut corrupted = bytes.clone();
+        corrupted[6] ^= 1;
+        assert_eq!(
+            XorFilter16::from_bytes(&corrupted).err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+
+        let mut old_version = bytes;
+        old_version[4] = 1;
+        assert_eq!(
+            XorFilter16::from_bytes(&old_version).err(),
+            Some(DecodeError::UnsupportedVersion(1))
+        );
+    }
+}
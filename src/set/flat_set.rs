@@ -0,0 +1,135 @@
+use crate::tree::FlatMap;
+use crate::tree::flat_map::FlatMapKeyIterator;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// Ordered set stored as a sorted `Vec`, the set counterpart of
+/// [`FlatMap`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlatSet<T> {
+    map: FlatMap<T, ()>,
+}
+
+impl<T: Ord> FlatSet<T> {
+    pub fn new() -> Self {
+        Self {
+            map: FlatMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            map: FlatMap::with_capacity(capacity),
+        }
+    }
+
+    /// Returns whether the item was newly added
+    pub fn insert(&mut self, item: T) -> bool {
+        self.map.insert(item, ()).is_none()
+    }
+
+    pub fn contains<Q: Borrow<T>>(&self, item: &Q) -> bool {
+        self.map.contains(item)
+    }
+
+    /// Returns whether the item was present
+    pub fn remove<Q: Borrow<T>>(&mut self, item: &Q) -> bool {
+        self.map.remove(item).is_some()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.map.min().map(|(item, _)| item)
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.map.max().map(|(item, _)| item)
+    }
+
+    pub fn iter(&self) -> FlatMapKeyIterator<'_, T, ()> {
+        self.map.keys()
+    }
+
+    /// Items in `range`, in order
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> impl DoubleEndedIterator<Item = &T> {
+        self.map.range(range).map(|(item, _)| item)
+    }
+}
+
+impl<T> FlatSet<T> {
+    pub fn size(&self) -> usize {
+        self.map.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<T: Ord> Default for FlatSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> Extend<T> for FlatSet<T> {
+    /// Sorts the new items and merges them in one pass, see
+    /// [`FlatMap`]'s `Extend`
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|item| (item, ())));
+    }
+}
+
+impl<T: Ord> FromIterator<T> for FlatSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a FlatSet<T> {
+    type Item = &'a T;
+    type IntoIter = FlatMapKeyIterator<'a, T, ()>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for FlatSet<T> {
+    type Item = T;
+    type IntoIter = std::iter::Map<std::vec::IntoIter<(T, ())>, fn((T, ())) -> T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter().map(|(item, ())| item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut set: FlatSet<i32> = [5, 1, 9, 5].into_iter().collect();
+        assert_eq!(set.size(), 3);
+
+        assert!(set.insert(3));
+        assert!(!set.insert(9));
+        assert!(set.contains(&3));
+        assert!(set.remove(&1));
+        assert!(!set.remove(&1));
+
+        assert_eq!(set.min(), Some(&3));
+        assert_eq!(set.max(), Some(&9));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![3, 5, 9]);
+        assert_eq!(set.range(4..).copied().collect::<Vec<_>>(), vec![5, 9]);
+
+        set.extend([0, 5, 7]);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![0, 3, 5, 7, 9]);
+    }
+}
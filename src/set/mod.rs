@@ -1 +1,13 @@
+mod bitset;
+mod codec;
+mod disjoint_set;
 pub mod filter;
+mod flat_set;
+mod roaring;
+pub mod sketch;
+
+pub use bitset::BitSet;
+pub use codec::DecodeError;
+pub use disjoint_set::{DisjointSet, KeyedDisjointSet};
+pub use flat_set::FlatSet;
+pub use roaring::{RoaringBitmap, RoaringTreemap};
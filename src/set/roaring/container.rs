@@ -0,0 +1,582 @@
+use crate::set::bitset::{self, count_ones};
+use std::iter::FlatMap;
+use std::ops::RangeInclusive;
+use std::slice;
+
+/// Largest array container; anything bigger is cheaper as a bitmap
+pub(super) const ARRAY_LIMIT: usize = 4096;
+pub(super) const BITMAP_WORDS: usize = 1024;
+
+pub(super) type Words = Box<[u64; BITMAP_WORDS]>;
+
+/// The low 16 bits of the values sharing one high 16-bit key.
+///
+/// Arrays hold at most [`ARRAY_LIMIT`] values and bitmaps more than that.
+/// Run containers may hold any number and only come from
+/// [`Container::optimize`] or deserialization.
+#[derive(Clone, Debug)]
+pub(super) enum Container {
+    /// Sorted values
+    Array(Vec<u16>),
+    /// One bit per value, and the number of bits set
+    Bitmap(Words, usize),
+    /// Sorted, non-overlapping runs `(start, length - 1)`
+    Run(Vec<(u16, u16)>),
+}
+
+impl Container {
+    pub(super) fn new() -> Self {
+        Container::Array(Vec::new())
+    }
+
+    /// Array or bitmap, whichever fits; `None` if `values` is empty
+    pub(super) fn from_sorted(values: Vec<u16>) -> Option<Self> {
+        match values.len() {
+            0 => None,
+            len if len <= ARRAY_LIMIT => Some(Container::Array(values)),
+            len => {
+                let mut words = empty_words();
+                for value in values {
+                    set_bit(&mut words, value);
+                }
+                Some(Container::Bitmap(words, len))
+            }
+        }
+    }
+
+    /// Array or bitmap, whichever fits; `None` if no bit is set
+    pub(super) fn from_words(words: Words) -> Option<Self> {
+        match count_ones(words.as_slice()) {
+            0 => None,
+            len if len <= ARRAY_LIMIT => Some(Container::Array(bits(words.as_slice()).collect())),
+            len => Some(Container::Bitmap(words, len)),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        match self {
+            Container::Array(values) => values.len(),
+            Container::Bitmap(_, len) => *len,
+            Container::Run(runs) => runs.iter().map(|&(_, length)| length as usize + 1).sum(),
+        }
+    }
+
+    pub(super) fn contains(&self, value: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&value).is_ok(),
+            Container::Bitmap(words, _) => words[value as usize / 64] & (1 << (value % 64)) != 0,
+            Container::Run(runs) => find_run(runs, value).is_ok(),
+        }
+    }
+
+    /// Returns whether the value was newly added
+    pub(super) fn insert(&mut self, value: u16) -> bool {
+        match self {
+            Container::Array(values) => {
+                let Err(index) = values.binary_search(&value) else {
+                    return false;
+                };
+                values.insert(index, value);
+                if values.len() > ARRAY_LIMIT {
+                    *self = Container::from_sorted(std::mem::take(values)).unwrap();
+                }
+                true
+            }
+            Container::Bitmap(words, len) => {
+                let added = !get_bit(words, value);
+                set_bit(words, value);
+                *len += usize::from(added);
+                added
+            }
+            Container::Run(runs) => {
+                let Err(index) = find_run(runs, value) else {
+                    return false;
+                };
+                let joins_previous = index > 0 && run_end(runs[index - 1]) + 1 == value as u32;
+                let joins_next = index < runs.len() && runs[index].0 as u32 == value as u32 + 1;
+
+                match (joins_previous, joins_next) {
+                    (true, true) => {
+                        let next = runs.remove(index);
+                        runs[index - 1].1 += next.1 + 2;
+                    }
+                    (true, false) => runs[index - 1].1 += 1,
+                    (false, true) => runs[index] = (value, runs[index].1 + 1),
+                    (false, false) => runs.insert(index, (value, 0)),
+                }
+                true
+            }
+        }
+    }
+
+    /// Returns whether the value was present
+    pub(super) fn remove(&mut self, value: u16) -> bool {
+        match self {
+            Container::Array(values) => match values.binary_search(&value) {
+                Ok(index) => {
+                    values.remove(index);
+                    true
+                }
+                Err(_) => false,
+            },
+            Container::Bitmap(words, len) => {
+                if !get_bit(words, value) {
+                    return false;
+                }
+                words[value as usize / 64] &= !(1 << (value % 64));
+                *len -= 1;
+                if *len <= ARRAY_LIMIT {
+                    *self = Container::Array(bits(words.as_slice()).collect());
+                }
+                true
+            }
+            Container::Run(runs) => {
+                let Ok(index) = find_run(runs, value) else {
+                    return false;
+                };
+                let (start, length) = runs[index];
+                let end = start + length;
+
+                match (value == start, value == end) {
+                    (true, true) => {
+                        runs.remove(index);
+                    }
+                    (true, false) => runs[index] = (start + 1, length - 1),
+                    (false, true) => runs[index].1 -= 1,
+                    (false, false) => {
+                        runs[index].1 = value - start - 1;
+                        runs.insert(index + 1, (value + 1, end - value - 1));
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    pub(super) fn min(&self) -> Option<u16> {
+        self.iter().next()
+    }
+
+    pub(super) fn max(&self) -> Option<u16> {
+        match self {
+            Container::Array(values) => values.last().copied(),
+            Container::Bitmap(words, _) => words
+                .iter()
+                .rposition(|&word| word != 0)
+                .map(|index| (index * 64 + 63 - words[index].leading_zeros() as usize) as u16),
+            Container::Run(runs) => runs.last().map(|&(start, length)| start + length),
+        }
+    }
+
+    /// Number of values less than or equal to `value`
+    pub(super) fn rank(&self, value: u16) -> usize {
+        match self {
+            Container::Array(values) => values.partition_point(|&v| v <= value),
+            Container::Bitmap(words, _) => bitset::rank(words.as_slice(), value as usize),
+            Container::Run(runs) => runs
+                .iter()
+                .take_while(|&&(start, _)| start <= value)
+                .map(|&(start, length)| (length.min(value - start)) as usize + 1)
+                .sum(),
+        }
+    }
+
+    /// The `n`-th smallest value, counting from zero
+    pub(super) fn select(&self, mut n: usize) -> Option<u16> {
+        match self {
+            Container::Array(values) => values.get(n).copied(),
+            Container::Bitmap(words, _) => {
+                bitset::select(words.as_slice(), n).map(|value| value as u16)
+            }
+            Container::Run(runs) => {
+                for &(start, length) in runs {
+                    if n <= length as usize {
+                        return Some(start + n as u16);
+                    }
+                    n -= length as usize + 1;
+                }
+                None
+            }
+        }
+    }
+
+    pub(super) fn iter(&self) -> Iter<'_> {
+        match self {
+            Container::Array(values) => Iter::Array(values.iter()),
+            Container::Bitmap(words, _) => Iter::Bitmap(bitset::Iter::new(words.as_slice())),
+            Container::Run(runs) => Iter::Run(runs.iter().flat_map(run_values)),
+        }
+    }
+
+    /// The values as a bitmap, whatever the representation
+    pub(super) fn words(&self) -> Words {
+        match self {
+            Container::Bitmap(words, _) => words.clone(),
+            Container::Array(values) => {
+                let mut words = empty_words();
+                for &value in values {
+                    set_bit(&mut words, value);
+                }
+                words
+            }
+            Container::Run(runs) => {
+                let mut words = empty_words();
+                for &(start, length) in runs {
+                    set_range(&mut words, start as usize, start as usize + length as usize);
+                }
+                words
+            }
+        }
+    }
+
+    pub(super) fn and(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Container::Array(left), Container::Array(right)) => {
+                Container::from_sorted(merge(left, right, |in_left, in_right| in_left && in_right))
+            }
+            (Container::Array(values), other) | (other, Container::Array(values)) => {
+                Container::from_sorted(
+                    values
+                        .iter()
+                        .copied()
+                        .filter(|&value| other.contains(value))
+                        .collect(),
+                )
+            }
+            _ => self.combine_words(other, |left, right| left & right),
+        }
+    }
+
+    pub(super) fn or(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Container::Array(left), Container::Array(right))
+                if left.len() + right.len() <= ARRAY_LIMIT =>
+            {
+                Container::from_sorted(merge(left, right, |in_left, in_right| in_left || in_right))
+            }
+            _ => self.combine_words(other, |left, right| left | right),
+        }
+    }
+
+    pub(super) fn xor(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Container::Array(left), Container::Array(right)) => {
+                Container::from_sorted(merge(left, right, |in_left, in_right| in_left != in_right))
+            }
+            _ => self.combine_words(other, |left, right| left ^ right),
+        }
+    }
+
+    pub(super) fn andnot(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Container::Array(values), other) => Container::from_sorted(
+                values
+                    .iter()
+                    .copied()
+                    .filter(|&value| !other.contains(value))
+                    .collect(),
+            ),
+            _ => self.combine_words(other, |left, right| left & !right),
+        }
+    }
+
+    /// Switches to whichever of the array, bitmap and run representations
+    /// serializes smallest, preferring arrays and bitmaps on ties. Returns
+    /// whether the container is now a run container.
+    pub(super) fn optimize(&mut self) -> bool {
+        let len = self.len();
+        let num_runs = self.count_runs();
+        let plain_size = if len <= ARRAY_LIMIT {
+            2 * len
+        } else {
+            8 * BITMAP_WORDS
+        };
+
+        if run_size(num_runs) < plain_size {
+            if !matches!(self, Container::Run(_)) {
+                *self = Container::Run(runs(self.iter()));
+            }
+            true
+        } else {
+            if let Container::Run(_) = self {
+                *self = Container::from_words(self.words()).unwrap();
+            }
+            false
+        }
+    }
+
+    /// Bytes taken by the container in the portable format
+    pub(super) fn serialized_size(&self) -> usize {
+        match self {
+            Container::Array(values) => 2 * values.len(),
+            Container::Bitmap(..) => 8 * BITMAP_WORDS,
+            Container::Run(runs) => run_size(runs.len()),
+        }
+    }
+
+    fn count_runs(&self) -> usize {
+        match self {
+            Container::Run(runs) => runs.len(),
+            Container::Bitmap(words, _) => {
+                // A run starts at every set bit whose predecessor is clear
+                let mut previous_top = 0;
+                words
+                    .iter()
+                    .map(|&word| {
+                        let starts = word & !((word << 1) | previous_top);
+                        previous_top = word >> 63;
+                        starts.count_ones() as usize
+                    })
+                    .sum()
+            }
+            Container::Array(values) => runs(values.iter().copied()).len(),
+        }
+    }
+
+    fn combine_words(&self, other: &Self, combine: impl Fn(u64, u64) -> u64) -> Option<Self> {
+        let mut words = self.words();
+        for (word, other) in words.iter_mut().zip(other.words().iter()) {
+            *word = combine(*word, *other);
+        }
+        Container::from_words(words)
+    }
+}
+
+impl Default for Container {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type RunValues<'a> = FlatMap<slice::Iter<'a, (u16, u16)>, RangeInclusive<u16>, RunFn>;
+type RunFn = fn(&(u16, u16)) -> RangeInclusive<u16>;
+
+/// Values of one container in increasing order
+pub(super) enum Iter<'a> {
+    Array(slice::Iter<'a, u16>),
+    Bitmap(bitset::Iter<'a>),
+    Run(RunValues<'a>),
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Array(values) => values.next().copied(),
+            Iter::Bitmap(bits) => bits.next().map(|value| value as u16),
+            Iter::Run(values) => values.next(),
+        }
+    }
+}
+
+fn bits(words: &[u64]) -> impl Iterator<Item = u16> + '_ {
+    bitset::Iter::new(words).map(|value| value as u16)
+}
+
+fn run_values(&(start, length): &(u16, u16)) -> RangeInclusive<u16> {
+    start..=start + length
+}
+
+fn run_end((start, length): (u16, u16)) -> u32 {
+    start as u32 + length as u32
+}
+
+fn run_size(num_runs: usize) -> usize {
+    2 + 4 * num_runs
+}
+
+/// `Ok` with the run containing `value`, or `Err` with the index a run
+/// starting at `value` would be inserted at
+fn find_run(runs: &[(u16, u16)], value: u16) -> Result<usize, usize> {
+    let index = runs.partition_point(|&(start, _)| start <= value);
+    if index > 0 && run_end(runs[index - 1]) >= value as u32 {
+        Ok(index - 1)
+    } else {
+        Err(index)
+    }
+}
+
+/// Runs of consecutive values in a sorted sequence
+fn runs(values: impl Iterator<Item = u16>) -> Vec<(u16, u16)> {
+    let mut runs: Vec<(u16, u16)> = Vec::new();
+    for value in values {
+        match runs.last_mut() {
+            Some(run) if run_end(*run) + 1 == value as u32 => run.1 += 1,
+            _ => runs.push((value, 0)),
+        }
+    }
+    runs
+}
+
+/// Values of either sorted slice for which `keep(in_left, in_right)` holds
+fn merge(left: &[u16], right: &[u16], keep: impl Fn(bool, bool) -> bool) -> Vec<u16> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < left.len() || j < right.len() {
+        let (value, in_left, in_right) = match (left.get(i), right.get(j)) {
+            (Some(&a), Some(&b)) if a == b => (a, true, true),
+            (Some(&a), Some(&b)) if a < b => (a, true, false),
+            (Some(&a), None) => (a, true, false),
+            (_, Some(&b)) => (b, false, true),
+            (None, None) => unreachable!(),
+        };
+
+        i += usize::from(in_left);
+        j += usize::from(in_right);
+        if keep(in_left, in_right) {
+            result.push(value);
+        }
+    }
+
+    result
+}
+
+fn empty_words() -> Words {
+    Box::new([0; BITMAP_WORDS])
+}
+
+fn get_bit(words: &[u64; BITMAP_WORDS], value: u16) -> bool {
+    words[value as usize / 64] & (1 << (value % 64)) != 0
+}
+
+fn set_bit(words: &mut [u64; BITMAP_WORDS], value: u16) {
+    words[value as usize / 64] |= 1 << (value % 64);
+}
+
+/// Sets every bit in `start..=end`
+fn set_range(words: &mut [u64; BITMAP_WORDS], start: usize, end: usize) {
+    let (first, last) = (start / 64, end / 64);
+    let first_mask = u64::MAX << (start % 64);
+    let last_mask = u64::MAX >> (63 - end % 64);
+
+    if first == last {
+        words[first] |= first_mask & last_mask;
+        return;
+    }
+
+    words[first] |= first_mask;
+    words[first + 1..last].fill(u64::MAX);
+    words[last] |= last_mask;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(container: &Container) -> Vec<u16> {
+        container.iter().collect()
+    }
+
+    fn all_forms(values: &[u16]) -> Vec<Container> {
+        let array = Container::Array(values.to_vec());
+        let bitmap = Container::Bitmap(array.words(), values.len());
+        let run = Container::Run(runs(values.iter().copied()));
+        vec![array, bitmap, run]
+    }
+
+    #[test]
+    fn test_forms_agree() {
+        let input: Vec<u16> = (0..3_000)
+            .map(|i| i * 7 % 9_000)
+            .chain(60_000..=65_535)
+            .collect();
+        let mut sorted = input.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        for container in all_forms(&sorted) {
+            assert_eq!(values(&container), sorted);
+            assert_eq!(container.len(), sorted.len());
+            assert_eq!(container.min(), sorted.first().copied());
+            assert_eq!(container.max(), sorted.last().copied());
+
+            for probe in [0, 6, 7, 8_999, 59_999, 60_000, 65_535] {
+                assert_eq!(container.contains(probe), sorted.contains(&probe));
+                assert_eq!(
+                    container.rank(probe),
+                    sorted.iter().filter(|&&v| v <= probe).count()
+                );
+            }
+            for n in [0, 1, 2_999, sorted.len() - 1, sorted.len()] {
+                assert_eq!(container.select(n), sorted.get(n).copied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_array_becomes_bitmap_and_back() {
+        let mut container = Container::new();
+        for value in 0..=ARRAY_LIMIT as u16 {
+            assert!(container.insert(value * 2));
+        }
+        assert!(matches!(container, Container::Bitmap(_, 4_097)));
+        assert!(!container.insert(0));
+
+        assert!(container.remove(0));
+        assert!(matches!(container, Container::Array(ref values) if values.len() == 4_096));
+        assert!(!container.remove(0));
+    }
+
+    #[test]
+    fn test_run_insert_and_remove() {
+        let mut container = Container::Run(vec![(10, 4), (20, 0)]);
+
+        assert!(container.insert(15));
+        assert!(container.insert(19));
+        assert!(container.insert(17));
+        assert!(!container.insert(12));
+        assert!(matches!(&container, Container::Run(runs) if runs == &[(10, 5), (17, 0), (19, 1)]));
+
+        assert!(container.insert(16));
+        assert!(container.insert(18));
+        assert!(matches!(&container, Container::Run(runs) if runs == &[(10, 10)]));
+
+        assert!(container.remove(15));
+        assert!(container.remove(10));
+        assert!(container.remove(20));
+        assert!(!container.remove(15));
+        assert_eq!(values(&container), vec![11, 12, 13, 14, 16, 17, 18, 19]);
+
+        assert!(container.insert(0));
+        assert!(container.insert(65_535));
+        assert_eq!(container.max(), Some(65_535));
+        assert!(container.remove(65_535));
+        assert!(container.remove(0));
+    }
+
+    #[test]
+    fn test_operations_across_forms() {
+        let left: Vec<u16> = (0..10_000).step_by(3).chain(20_000..30_000).collect();
+        let right: Vec<u16> = (0..10_000).step_by(2).chain(25_000..26_000).collect();
+
+        let expected = |keep: fn(bool, bool) -> bool| merge(&left, &right, keep);
+        for a in all_forms(&left) {
+            for b in all_forms(&right) {
+                let result =
+                    |container: Option<Container>| container.map_or(vec![], |c| values(&c));
+
+                assert_eq!(result(a.and(&b)), expected(|l, r| l && r));
+                assert_eq!(result(a.or(&b)), expected(|l, r| l || r));
+                assert_eq!(result(a.xor(&b)), expected(|l, r| l != r));
+                assert_eq!(result(a.andnot(&b)), expected(|l, r| l && !r));
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut dense_runs = Container::from_sorted((0..10_000).collect()).unwrap();
+        assert!(dense_runs.optimize());
+        assert!(matches!(&dense_runs, Container::Run(runs) if runs == &[(0, 9_999)]));
+
+        let mut scattered = Container::from_sorted((0..1_000).map(|i| i * 3).collect()).unwrap();
+        assert!(!scattered.optimize());
+        assert!(matches!(scattered, Container::Array(_)));
+
+        let mut demoted = Container::Run((0..5_000).map(|i| (i * 4, 1)).collect());
+        assert!(!demoted.optimize());
+        assert!(matches!(demoted, Container::Bitmap(_, 10_000)));
+    }
+}
@@ -0,0 +1,728 @@
+mod container;
+mod treemap;
+
+use crate::set::DecodeError;
+use crate::set::bitset::count_ones;
+use crate::set::codec::Reader;
+use container::{ARRAY_LIMIT, BITMAP_WORDS, Container};
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
+
+pub use treemap::RoaringTreemap;
+
+const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
+const SERIAL_COOKIE: u16 = 12347;
+/// Run-format streams with fewer containers than this omit the offsets
+const NO_OFFSET_THRESHOLD: usize = 4;
+
+/// Compressed set of `u32` values (Chambi, Lemire, Kaser, Godin).
+///
+/// Values are grouped by their high 16 bits into containers holding the low
+/// 16 bits, each stored as whichever of a sorted array, a 65536-bit bitmap
+/// or a list of runs suits its contents. Sparse groups cost about two bytes
+/// per value, dense ones at most one bit, and runs four bytes each after
+/// [`run_optimize`](Self::run_optimize).
+///
+/// [`to_bytes`](Self::to_bytes) writes the portable Roaring format, which
+/// other Roaring implementations (CRoaring, Java, Go, `roaring-rs`) read and
+/// write as well.
+#[derive(Clone, Default)]
+pub struct RoaringBitmap {
+    /// Sorted by key, never holding empty containers
+    containers: Vec<(u16, Container)>,
+}
+
+impl RoaringBitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values in the set
+    pub fn len(&self) -> u64 {
+        self.containers
+            .iter()
+            .map(|(_, container)| container.len() as u64)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty()
+    }
+
+    /// Returns whether the value was newly added
+    pub fn insert(&mut self, value: u32) -> bool {
+        let (key, low) = split(value);
+        let index = match self.find(key) {
+            Ok(index) => index,
+            Err(index) => {
+                self.containers.insert(index, (key, Container::new()));
+                index
+            }
+        };
+
+        self.containers[index].1.insert(low)
+    }
+
+    /// Returns whether the value was present
+    pub fn remove(&mut self, value: u32) -> bool {
+        let (key, low) = split(value);
+        let Ok(index) = self.find(key) else {
+            return false;
+        };
+
+        let container = &mut self.containers[index].1;
+        let removed = container.remove(low);
+        if container.len() == 0 {
+            self.containers.remove(index);
+        }
+        removed
+    }
+
+    pub fn contains(&self, value: u32) -> bool {
+        let (key, low) = split(value);
+        self.find(key)
+            .is_ok_and(|index| self.containers[index].1.contains(low))
+    }
+
+    pub fn clear(&mut self) {
+        self.containers.clear();
+    }
+
+    pub fn min(&self) -> Option<u32> {
+        let (key, container) = self.containers.first()?;
+        container.min().map(|low| join(*key, low))
+    }
+
+    pub fn max(&self) -> Option<u32> {
+        let (key, container) = self.containers.last()?;
+        container.max().map(|low| join(*key, low))
+    }
+
+    /// Number of values less than or equal to `value`
+    pub fn rank(&self, value: u32) -> u64 {
+        let (key, low) = split(value);
+        let mut rank = 0;
+
+        for (container_key, container) in &self.containers {
+            if *container_key > key {
+                break;
+            }
+            rank += if *container_key < key {
+                container.len()
+            } else {
+                container.rank(low)
+            } as u64;
+        }
+
+        rank
+    }
+
+    /// The `n`-th smallest value, counting from zero
+    pub fn select(&self, mut n: u64) -> Option<u32> {
+        for (key, container) in &self.containers {
+            let len = container.len() as u64;
+            if n < len {
+                return container.select(n as usize).map(|low| join(*key, low));
+            }
+            n -= len;
+        }
+
+        None
+    }
+
+    /// Values in increasing order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            containers: self.containers.iter(),
+            current: None,
+        }
+    }
+
+    /// Values in either set
+    pub fn or(&self, other: &Self) -> Self {
+        self.combine(other, true, true, Container::or)
+    }
+
+    /// Values in both sets
+    pub fn and(&self, other: &Self) -> Self {
+        self.combine(other, false, false, Container::and)
+    }
+
+    /// Values in exactly one of the sets
+    pub fn xor(&self, other: &Self) -> Self {
+        self.combine(other, true, true, Container::xor)
+    }
+
+    /// Values in this set but not in `other`
+    pub fn andnot(&self, other: &Self) -> Self {
+        self.combine(other, true, false, Container::andnot)
+    }
+
+    /// Converts containers to run containers where that is smaller, and
+    /// back where it no longer is. Returns whether any run container is
+    /// left. Worth calling before serializing sets with long stretches of
+    /// consecutive values.
+    pub fn run_optimize(&mut self) -> bool {
+        let mut has_runs = false;
+        for (_, container) in &mut self.containers {
+            has_runs |= container.optimize();
+        }
+        has_runs
+    }
+
+    /// Length of [`to_bytes`](Self::to_bytes) output
+    pub fn serialized_size(&self) -> usize {
+        let len = self.containers.len();
+        let has_runs = self.has_run_containers();
+
+        let header = if has_runs {
+            4 + len.div_ceil(8)
+                + 4 * len
+                + if len >= NO_OFFSET_THRESHOLD {
+                    4 * len
+                } else {
+                    0
+                }
+        } else {
+            8 + 8 * len
+        };
+
+        header
+            + self
+                .containers
+                .iter()
+                .map(|(_, container)| container.serialized_size())
+                .sum::<usize>()
+    }
+
+    /// Serializes in the portable Roaring format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = self.containers.len();
+        let has_runs = self.has_run_containers();
+        let mut out = Vec::with_capacity(self.serialized_size());
+
+        if has_runs {
+            out.extend_from_slice(&SERIAL_COOKIE.to_le_bytes());
+            out.extend_from_slice(&((len - 1) as u16).to_le_bytes());
+
+            let mut run_flags = vec![0u8; len.div_ceil(8)];
+            for (index, (_, container)) in self.containers.iter().enumerate() {
+                if let Container::Run(_) = container {
+                    run_flags[index / 8] |= 1 << (index % 8);
+                }
+            }
+            out.extend_from_slice(&run_flags);
+        } else {
+            out.extend_from_slice(&SERIAL_COOKIE_NO_RUNCONTAINER.to_le_bytes());
+            out.extend_from_slice(&(len as u32).to_le_bytes());
+        }
+
+        for (key, container) in &self.containers {
+            out.extend_from_slice(&key.to_le_bytes());
+            out.extend_from_slice(&((container.len() - 1) as u16).to_le_bytes());
+        }
+
+        if !has_runs || len >= NO_OFFSET_THRESHOLD {
+            let mut offset = out.len() + 4 * len;
+            for (_, container) in &self.containers {
+                out.extend_from_slice(&(offset as u32).to_le_bytes());
+                offset += container.serialized_size();
+            }
+        }
+
+        for (_, container) in &self.containers {
+            match container {
+                Container::Array(values) => {
+                    for value in values {
+                        out.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                Container::Bitmap(words, _) => {
+                    for word in words.iter() {
+                        out.extend_from_slice(&word.to_le_bytes());
+                    }
+                }
+                Container::Run(runs) => {
+                    out.extend_from_slice(&(runs.len() as u16).to_le_bytes());
+                    for (start, length) in runs {
+                        out.extend_from_slice(&start.to_le_bytes());
+                        out.extend_from_slice(&length.to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Reads the portable Roaring format, as written by
+    /// [`to_bytes`](Self::to_bytes) or another Roaring implementation
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let bitmap = Self::read(&mut reader)?;
+        reader.finish()?;
+
+        Ok(bitmap)
+    }
+
+    /// Reads one bitmap in the portable Roaring format from the front of
+    /// `reader`
+    fn read(reader: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let cookie = reader.read_u32()?;
+        let (len, run_flags) = if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+            let len = reader.read_u32()? as usize;
+            if len > 1 << 16 {
+                return Err(DecodeError::Invalid("too many containers"));
+            }
+            (len, None)
+        } else if cookie as u16 == SERIAL_COOKIE {
+            let len = (cookie >> 16) as usize + 1;
+            (len, Some(reader.read_bytes(len.div_ceil(8))?))
+        } else {
+            return Err(DecodeError::BadMagic);
+        };
+
+        let mut header = Vec::with_capacity(len);
+        for _ in 0..len {
+            let key = reader.read_u16()?;
+            let container_len = reader.read_u16()? as usize + 1;
+            header.push((key, container_len));
+        }
+        if header.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(DecodeError::Invalid("container keys are not increasing"));
+        }
+
+        if run_flags.is_none() || len >= NO_OFFSET_THRESHOLD {
+            // Offsets only help random access; containers are read in order
+            reader.read_bytes(4 * len)?;
+        }
+
+        let mut containers = Vec::with_capacity(len);
+        for (index, (key, container_len)) in header.into_iter().enumerate() {
+            let is_run = run_flags.is_some_and(|flags| flags[index / 8] & (1 << (index % 8)) != 0);
+
+            let container = if is_run {
+                read_runs(reader)?
+            } else if container_len <= ARRAY_LIMIT {
+                read_array(reader, container_len)?
+            } else {
+                read_bitmap(reader)?
+            };
+
+            if container.len() != container_len {
+                return Err(DecodeError::Invalid("container length mismatch"));
+            }
+            containers.push((key, container));
+        }
+
+        Ok(Self { containers })
+    }
+
+    fn find(&self, key: u16) -> Result<usize, usize> {
+        self.containers.binary_search_by_key(&key, |&(key, _)| key)
+    }
+
+    fn has_run_containers(&self) -> bool {
+        self.containers
+            .iter()
+            .any(|(_, container)| matches!(container, Container::Run(_)))
+    }
+
+    /// Walks both sets' containers by key, keeping containers found on only
+    /// one side when the matching flag is set and combining the others
+    fn combine(
+        &self,
+        other: &Self,
+        keep_left: bool,
+        keep_right: bool,
+        combine: fn(&Container, &Container) -> Option<Container>,
+    ) -> Self {
+        let mut containers = Vec::new();
+        let (mut left, mut right) = (
+            self.containers.iter().peekable(),
+            other.containers.iter().peekable(),
+        );
+
+        loop {
+            match (left.peek(), right.peek()) {
+                (Some((left_key, a)), Some((right_key, b))) if left_key == right_key => {
+                    if let Some(container) = combine(a, b) {
+                        containers.push((*left_key, container));
+                    }
+                    left.next();
+                    right.next();
+                }
+                (Some((left_key, _)), Some((right_key, _))) if left_key < right_key => {
+                    let entry = left.next().unwrap();
+                    if keep_left {
+                        containers.push(entry.clone());
+                    }
+                }
+                (Some(_), None) => {
+                    let entry = left.next().unwrap();
+                    if keep_left {
+                        containers.push(entry.clone());
+                    }
+                }
+                (_, Some(_)) => {
+                    let entry = right.next().unwrap();
+                    if keep_right {
+                        containers.push(entry.clone());
+                    }
+                }
+                (None, None) => break,
+            }
+        }
+
+        Self { containers }
+    }
+}
+
+impl PartialEq for RoaringBitmap {
+    /// Compares the values, whatever the container representations
+    fn eq(&self, other: &Self) -> bool {
+        self.containers.len() == other.containers.len()
+            && self.containers.iter().zip(&other.containers).all(
+                |((left_key, left), (right_key, right))| {
+                    left_key == right_key
+                        && left.len() == right.len()
+                        && left.iter().eq(right.iter())
+                },
+            )
+    }
+}
+
+impl Eq for RoaringBitmap {}
+
+impl fmt::Debug for RoaringBitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<u32> for RoaringBitmap {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut bitmap = Self::new();
+        bitmap.extend(iter);
+        bitmap
+    }
+}
+
+impl Extend<u32> for RoaringBitmap {
+    fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RoaringBitmap {
+    type Item = u32;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+macro_rules! impl_operator {
+    ($type:ty, $trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:ident) => {
+        impl $trait<&$type> for &$type {
+            type Output = $type;
+
+            fn $method(self, other: &$type) -> $type {
+                self.$op(other)
+            }
+        }
+
+        impl $assign_trait<&$type> for $type {
+            fn $assign_method(&mut self, other: &$type) {
+                *self = self.$op(other);
+            }
+        }
+    };
+}
+use impl_operator;
+
+impl_operator!(RoaringBitmap, BitOr, bitor, BitOrAssign, bitor_assign, or);
+impl_operator!(
+    RoaringBitmap,
+    BitAnd,
+    bitand,
+    BitAndAssign,
+    bitand_assign,
+    and
+);
+impl_operator!(
+    RoaringBitmap,
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    xor
+);
+impl_operator!(RoaringBitmap, Sub, sub, SubAssign, sub_assign, andnot);
+
+/// Iterator over the values of a [`RoaringBitmap`] in increasing order
+pub struct Iter<'a> {
+    containers: std::slice::Iter<'a, (u16, Container)>,
+    current: Option<(u16, container::Iter<'a>)>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, values)) = &mut self.current
+                && let Some(low) = values.next()
+            {
+                return Some(join(*key, low));
+            }
+
+            let (key, container) = self.containers.next()?;
+            self.current = Some((*key, container.iter()));
+        }
+    }
+}
+
+fn split(value: u32) -> (u16, u16) {
+    ((value >> 16) as u16, value as u16)
+}
+
+fn join(key: u16, low: u16) -> u32 {
+    (key as u32) << 16 | low as u32
+}
+
+fn read_array(reader: &mut Reader<'_>, len: usize) -> Result<Container, DecodeError> {
+    let values: Vec<u16> = reader
+        .read_bytes(2 * len)?
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect();
+
+    if values.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(DecodeError::Invalid("array values are not increasing"));
+    }
+
+    Ok(Container::Array(values))
+}
+
+fn read_bitmap(reader: &mut Reader<'_>) -> Result<Container, DecodeError> {
+    let mut words = Box::new([0u64; BITMAP_WORDS]);
+    for (word, bytes) in words
+        .iter_mut()
+        .zip(reader.read_bytes(8 * BITMAP_WORDS)?.chunks_exact(8))
+    {
+        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let len = count_ones(words.as_slice());
+    Ok(Container::Bitmap(words, len))
+}
+
+fn read_runs(reader: &mut Reader<'_>) -> Result<Container, DecodeError> {
+    let num_runs = reader.read_u16()? as usize;
+    let mut runs: Vec<(u16, u16)> = Vec::with_capacity(num_runs);
+    let mut next_start = 0u32;
+
+    for _ in 0..num_runs {
+        let start = reader.read_u16()?;
+        let length = reader.read_u16()?;
+        let end = start as u32 + length as u32;
+
+        if (start as u32) < next_start || end > u16::MAX as u32 {
+            return Err(DecodeError::Invalid("runs overlap or overflow"));
+        }
+        runs.push((start, length));
+        next_start = end + 1;
+    }
+
+    if runs.is_empty() {
+        return Err(DecodeError::Invalid("empty run container"));
+    }
+
+    Ok(Container::Run(runs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Sparse, dense and consecutive stretches across several containers
+    fn sample() -> Vec<u32> {
+        (0..1_000)
+            .map(|i| i * 37)
+            .chain((1 << 16)..(1 << 16) + 10_000)
+            .chain((5 << 16..6 << 16).step_by(2))
+            .chain([u32::MAX - 1, u32::MAX])
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_contains_remove() {
+        let mut bitmap = RoaringBitmap::new();
+        assert!(bitmap.insert(7));
+        assert!(!bitmap.insert(7));
+        assert!(bitmap.insert(u32::MAX));
+        assert!(bitmap.insert(1 << 20));
+
+        assert!(bitmap.contains(7));
+        assert!(bitmap.contains(u32::MAX));
+        assert!(!bitmap.contains(8));
+        assert_eq!(bitmap.len(), 3);
+        assert_eq!(bitmap.min(), Some(7));
+        assert_eq!(bitmap.max(), Some(u32::MAX));
+
+        assert!(bitmap.remove(1 << 20));
+        assert!(!bitmap.remove(1 << 20));
+        assert_eq!(bitmap.containers.len(), 2);
+        assert_eq!(bitmap.iter().collect::<Vec<_>>(), vec![7, u32::MAX]);
+    }
+
+    #[test]
+    fn test_matches_btree_set() {
+        let values = sample();
+        let bitmap: RoaringBitmap = values.iter().copied().collect();
+        let expected: BTreeSet<u32> = values.into_iter().collect();
+
+        assert_eq!(bitmap.len(), expected.len() as u64);
+        assert!(bitmap.iter().eq(expected.iter().copied()));
+
+        for (n, &value) in expected.iter().enumerate().step_by(97) {
+            assert_eq!(bitmap.select(n as u64), Some(value));
+            assert_eq!(bitmap.rank(value), n as u64 + 1);
+        }
+        assert_eq!(bitmap.select(expected.len() as u64), None);
+        assert_eq!(bitmap.rank(36), 1);
+        assert_eq!(bitmap.rank(u32::MAX), bitmap.len());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let left: BTreeSet<u32> = sample().into_iter().collect();
+        let right: BTreeSet<u32> = (0..200_000)
+            .step_by(5)
+            .chain(5 << 16..5 << 16 | 100)
+            .collect();
+
+        let mut a: RoaringBitmap = left.iter().copied().collect();
+        let b: RoaringBitmap = right.iter().copied().collect();
+        for optimized in [false, true] {
+            if optimized {
+                assert!(a.run_optimize());
+            }
+
+            let check = |result: RoaringBitmap, expected: Vec<u32>| {
+                assert_eq!(result.iter().collect::<Vec<_>>(), expected);
+            };
+            check(&a | &b, left.union(&right).copied().collect());
+            check(&a & &b, left.intersection(&right).copied().collect());
+            check(
+                &a ^ &b,
+                left.symmetric_difference(&right).copied().collect(),
+            );
+            check(&a - &b, left.difference(&right).copied().collect());
+        }
+
+        let mut c = a.clone();
+        c -= &a;
+        assert!(c.is_empty());
+        c |= &b;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn test_run_optimize_keeps_values() {
+        let mut bitmap: RoaringBitmap = sample().into_iter().collect();
+        let plain = bitmap.clone();
+        let plain_size = bitmap.serialized_size();
+
+        assert!(bitmap.run_optimize());
+        assert_eq!(bitmap, plain);
+        assert!(bitmap.serialized_size() < plain_size);
+
+        let mut expected = plain;
+        for value in [(1 << 16) + 500, (1 << 16) + 20_000] {
+            assert_eq!(bitmap.insert(value), expected.insert(value));
+            assert_eq!(bitmap.remove(value - 1), expected.remove(value - 1));
+            assert_eq!(bitmap, expected);
+        }
+    }
+
+    #[test]
+    fn test_portable_format_without_runs() {
+        let bitmap: RoaringBitmap = [1, 2, 3].into_iter().collect();
+
+        #[rustfmt::skip]
+        let expected = [
+            0x3a, 0x30, 0, 0, // cookie 12346
+            1, 0, 0, 0,       // one container
+            0, 0, 2, 0,       // key 0, three values
+            16, 0, 0, 0,      // offset
+            1, 0, 2, 0, 3, 0, // array
+        ];
+        assert_eq!(bitmap.to_bytes(), expected);
+        assert_eq!(bitmap.serialized_size(), expected.len());
+        assert_eq!(RoaringBitmap::from_bytes(&expected).unwrap(), bitmap);
+    }
+
+    #[test]
+    fn test_portable_format_with_runs() {
+        let mut bitmap: RoaringBitmap = (0..100).collect();
+        assert!(bitmap.run_optimize());
+
+        #[rustfmt::skip]
+        let expected = [
+            0x3b, 0x30, 0, 0, // cookie 12347, one container
+            1,                // container 0 is a run container
+            0, 0, 99, 0,      // key 0, 100 values
+            1, 0, 0, 0, 99, 0 // one run: 0, length 100
+        ];
+        assert_eq!(bitmap.to_bytes(), expected);
+        assert_eq!(RoaringBitmap::from_bytes(&expected).unwrap(), bitmap);
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut bitmap: RoaringBitmap = sample().into_iter().collect();
+        for optimize in [false, true] {
+            if optimize {
+                bitmap.run_optimize();
+            }
+
+            let bytes = bitmap.to_bytes();
+            assert_eq!(bytes.len(), bitmap.serialized_size());
+            assert_eq!(RoaringBitmap::from_bytes(&bytes).unwrap(), bitmap);
+        }
+
+        let empty = RoaringBitmap::new();
+        assert_eq!(RoaringBitmap::from_bytes(&empty.to_bytes()).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_deserialization_errors() {
+        let bytes = RoaringBitmap::from_iter(sample()).to_bytes();
+
+        assert_eq!(
+            RoaringBitmap::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::Truncated)
+        );
+        assert_eq!(
+            RoaringBitmap::from_bytes(b"\x00\x00\x00\x00"),
+            Err(DecodeError::BadMagic)
+        );
+
+        // Second array value no longer above the first
+        let mut unsorted = RoaringBitmap::from_iter([1, 2, 3]).to_bytes();
+        unsorted[18] = 1;
+        assert!(RoaringBitmap::from_bytes(&unsorted).is_err());
+
+        // Cardinality in the header disagrees with the bitmap
+        let mut miscounted = bytes.clone();
+        let second_key = 8 + 4;
+        miscounted[second_key + 2] ^= 1;
+        assert!(RoaringBitmap::from_bytes(&miscounted).is_err());
+    }
+}
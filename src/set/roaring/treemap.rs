@@ -0,0 +1,497 @@
+use super::{RoaringBitmap, impl_operator};
+use crate::set::DecodeError;
+use crate::set::codec::Reader;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
+
+/// Compressed set of `u64` values: a [`RoaringBitmap`] of the low 32 bits
+/// for every distinct value of the high 32 bits.
+///
+/// [`to_bytes`](Self::to_bytes) writes the portable 64-bit Roaring format
+/// (a `u64` bitmap count, then each high key as a `u32` followed by its
+/// bitmap in the 32-bit format), which CRoaring, Java, Go and `roaring-rs`
+/// use for their 64-bit sets as well.
+#[derive(Clone, Default)]
+pub struct RoaringTreemap {
+    /// Sorted by key, never holding empty bitmaps
+    bitmaps: Vec<(u32, RoaringBitmap)>,
+}
+
+impl RoaringTreemap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values in the set
+    pub fn len(&self) -> u64 {
+        self.bitmaps.iter().map(|(_, bitmap)| bitmap.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bitmaps.is_empty()
+    }
+
+    /// Returns whether the value was newly added
+    pub fn insert(&mut self, value: u64) -> bool {
+        let (key, low) = split(value);
+        let index = match self.find(key) {
+            Ok(index) => index,
+            Err(index) => {
+                self.bitmaps.insert(index, (key, RoaringBitmap::new()));
+                index
+            }
+        };
+
+        self.bitmaps[index].1.insert(low)
+    }
+
+    /// Returns whether the value was present
+    pub fn remove(&mut self, value: u64) -> bool {
+        let (key, low) = split(value);
+        let Ok(index) = self.find(key) else {
+            return false;
+        };
+
+        let bitmap = &mut self.bitmaps[index].1;
+        let removed = bitmap.remove(low);
+        if bitmap.is_empty() {
+            self.bitmaps.remove(index);
+        }
+        removed
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        let (key, low) = split(value);
+        self.find(key)
+            .is_ok_and(|index| self.bitmaps[index].1.contains(low))
+    }
+
+    pub fn clear(&mut self) {
+        self.bitmaps.clear();
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        let (key, bitmap) = self.bitmaps.first()?;
+        bitmap.min().map(|low| join(*key, low))
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        let (key, bitmap) = self.bitmaps.last()?;
+        bitmap.max().map(|low| join(*key, low))
+    }
+
+    /// Number of values less than or equal to `value`
+    pub fn rank(&self, value: u64) -> u64 {
+        let (key, low) = split(value);
+        let mut rank = 0;
+
+        for (bitmap_key, bitmap) in &self.bitmaps {
+            if *bitmap_key > key {
+                break;
+            }
+            rank += if *bitmap_key < key {
+                bitmap.len()
+            } else {
+                bitmap.rank(low)
+            };
+        }
+
+        rank
+    }
+
+    /// The `n`-th smallest value, counting from zero
+    pub fn select(&self, mut n: u64) -> Option<u64> {
+        for (key, bitmap) in &self.bitmaps {
+            let len = bitmap.len();
+            if n < len {
+                return bitmap.select(n).map(|low| join(*key, low));
+            }
+            n -= len;
+        }
+
+        None
+    }
+
+    /// Values in increasing order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bitmaps: self.bitmaps.iter(),
+            current: None,
+        }
+    }
+
+    /// Values in either set
+    pub fn or(&self, other: &Self) -> Self {
+        self.combine(other, true, true, RoaringBitmap::or)
+    }
+
+    /// Values in both sets
+    pub fn and(&self, other: &Self) -> Self {
+        self.combine(other, false, false, RoaringBitmap::and)
+    }
+
+    /// Values in exactly one of the sets
+    pub fn xor(&self, other: &Self) -> Self {
+        self.combine(other, true, true, RoaringBitmap::xor)
+    }
+
+    /// Values in this set but not in `other`
+    pub fn andnot(&self, other: &Self) -> Self {
+        self.combine(other, true, false, RoaringBitmap::andnot)
+    }
+
+    /// Runs [`RoaringBitmap::run_optimize`] on every bitmap. Returns
+    /// whether any run container is left.
+    pub fn run_optimize(&mut self) -> bool {
+        let mut has_runs = false;
+        for (_, bitmap) in &mut self.bitmaps {
+            has_runs |= bitmap.run_optimize();
+        }
+        has_runs
+    }
+
+    /// Length of [`to_bytes`](Self::to_bytes) output
+    pub fn serialized_size(&self) -> usize {
+        8 + self
+            .bitmaps
+            .iter()
+            .map(|(_, bitmap)| 4 + bitmap.serialized_size())
+            .sum::<usize>()
+    }
+
+    /// Serializes in the portable 64-bit Roaring format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.serialized_size());
+        out.extend_from_slice(&(self.bitmaps.len() as u64).to_le_bytes());
+
+        for (key, bitmap) in &self.bitmaps {
+            out.extend_from_slice(&key.to_le_bytes());
+            out.extend_from_slice(&bitmap.to_bytes());
+        }
+
+        out
+    }
+
+    /// Reads the portable 64-bit Roaring format, as written by
+    /// [`to_bytes`](Self::to_bytes) or another Roaring implementation
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+
+        let len = reader.read_u64()?;
+        if len > 1 << 32 {
+            return Err(DecodeError::Invalid("too many bitmaps"));
+        }
+
+        let mut bitmaps: Vec<(u32, RoaringBitmap)> = Vec::new();
+        for _ in 0..len {
+            let key = reader.read_u32()?;
+            if bitmaps.last().is_some_and(|&(last, _)| last >= key) {
+                return Err(DecodeError::Invalid("bitmap keys are not increasing"));
+            }
+
+            let bitmap = RoaringBitmap::read(&mut reader)?;
+            if !bitmap.is_empty() {
+                bitmaps.push((key, bitmap));
+            }
+        }
+
+        reader.finish()?;
+
+        Ok(Self { bitmaps })
+    }
+
+    fn find(&self, key: u32) -> Result<usize, usize> {
+        self.bitmaps.binary_search_by_key(&key, |&(key, _)| key)
+    }
+
+    /// Walks both sets' bitmaps by key, keeping bitmaps found on only one
+    /// side when the matching flag is set and combining the others
+    fn combine(
+        &self,
+        other: &Self,
+        keep_left: bool,
+        keep_right: bool,
+        combine: fn(&RoaringBitmap, &RoaringBitmap) -> RoaringBitmap,
+    ) -> Self {
+        let mut bitmaps = Vec::new();
+        let (mut left, mut right) = (
+            self.bitmaps.iter().peekable(),
+            other.bitmaps.iter().peekable(),
+        );
+
+        loop {
+            match (left.peek(), right.peek()) {
+                (Some((left_key, a)), Some((right_key, b))) if left_key == right_key => {
+                    let bitmap = combine(a, b);
+                    if !bitmap.is_empty() {
+                        bitmaps.push((*left_key, bitmap));
+                    }
+                    left.next();
+                    right.next();
+                }
+                (Some((left_key, _)), Some((right_key, _))) if left_key < right_key => {
+                    let entry = left.next().unwrap();
+                    if keep_left {
+                        bitmaps.push(entry.clone());
+                    }
+                }
+                (Some(_), None) => {
+                    let entry = left.next().unwrap();
+                    if keep_left {
+                        bitmaps.push(entry.clone());
+                    }
+                }
+                (_, Some(_)) => {
+                    let entry = right.next().unwrap();
+                    if keep_right {
+                        bitmaps.push(entry.clone());
+                    }
+                }
+                (None, None) => break,
+            }
+        }
+
+        Self { bitmaps }
+    }
+}
+
+impl PartialEq for RoaringTreemap {
+    fn eq(&self, other: &Self) -> bool {
+        self.bitmaps == other.bitmaps
+    }
+}
+
+impl Eq for RoaringTreemap {}
+
+impl fmt::Debug for RoaringTreemap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<u64> for RoaringTreemap {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut treemap = Self::new();
+        treemap.extend(iter);
+        treemap
+    }
+}
+
+impl Extend<u64> for RoaringTreemap {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RoaringTreemap {
+    type Item = u64;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl_operator!(RoaringTreemap, BitOr, bitor, BitOrAssign, bitor_assign, or);
+impl_operator!(
+    RoaringTreemap,
+    BitAnd,
+    bitand,
+    BitAndAssign,
+    bitand_assign,
+    and
+);
+impl_operator!(
+    RoaringTreemap,
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    xor
+);
+impl_operator!(RoaringTreemap, Sub, sub, SubAssign, sub_assign, andnot);
+
+/// Iterator over the values of a [`RoaringTreemap`] in increasing order
+pub struct Iter<'a> {
+    bitmaps: std::slice::Iter<'a, (u32, RoaringBitmap)>,
+    current: Option<(u32, super::Iter<'a>)>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, values)) = &mut self.current
+                && let Some(low) = values.next()
+            {
+                return Some(join(*key, low));
+            }
+
+            let (key, bitmap) = self.bitmaps.next()?;
+            self.current = Some((*key, bitmap.iter()));
+        }
+    }
+}
+
+fn split(value: u64) -> (u32, u32) {
+    ((value >> 32) as u32, value as u32)
+}
+
+fn join(key: u32, low: u32) -> u64 {
+    (key as u64) << 32 | low as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Values spread over several high keys, including both ends of the range
+    fn sample() -> Vec<u64> {
+        (0..1_000)
+            .map(|i| i * 37)
+            .chain((1 << 32)..(1 << 32) + 10_000)
+            .chain((7 << 40..(7 << 40) + 100_000).step_by(3))
+            .chain([u64::MAX - 1, u64::MAX])
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_contains_remove() {
+        let mut treemap = RoaringTreemap::new();
+        assert!(treemap.insert(7));
+        assert!(!treemap.insert(7));
+        assert!(treemap.insert(u64::MAX));
+        assert!(treemap.insert(1 << 40));
+
+        assert!(treemap.contains(7));
+        assert!(treemap.contains(u64::MAX));
+        assert!(!treemap.contains(7 | 1 << 32));
+        assert_eq!(treemap.len(), 3);
+        assert_eq!(treemap.min(), Some(7));
+        assert_eq!(treemap.max(), Some(u64::MAX));
+
+        assert!(treemap.remove(1 << 40));
+        assert!(!treemap.remove(1 << 40));
+        assert_eq!(treemap.bitmaps.len(), 2);
+        assert_eq!(treemap.iter().collect::<Vec<_>>(), vec![7, u64::MAX]);
+    }
+
+    #[test]
+    fn test_matches_btree_set() {
+        let values = sample();
+        let treemap: RoaringTreemap = values.iter().copied().collect();
+        let expected: BTreeSet<u64> = values.into_iter().collect();
+
+        assert_eq!(treemap.len(), expected.len() as u64);
+        assert!(treemap.iter().eq(expected.iter().copied()));
+
+        for (n, &value) in expected.iter().enumerate().step_by(97) {
+            assert_eq!(treemap.select(n as u64), Some(value));
+            assert_eq!(treemap.rank(value), n as u64 + 1);
+        }
+        assert_eq!(treemap.select(expected.len() as u64), None);
+        assert_eq!(treemap.rank(u64::MAX), treemap.len());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let left: BTreeSet<u64> = sample().into_iter().collect();
+        let right: BTreeSet<u64> = (0..200_000)
+            .step_by(5)
+            .chain((1 << 32) + 5_000..(1 << 32) + 20_000)
+            .chain([u64::MAX])
+            .collect();
+
+        let a: RoaringTreemap = left.iter().copied().collect();
+        let b: RoaringTreemap = right.iter().copied().collect();
+
+        let check = |result: RoaringTreemap, expected: Vec<u64>| {
+            assert_eq!(result.iter().collect::<Vec<_>>(), expected);
+        };
+        check(&a | &b, left.union(&right).copied().collect());
+        check(&a & &b, left.intersection(&right).copied().collect());
+        check(
+            &a ^ &b,
+            left.symmetric_difference(&right).copied().collect(),
+        );
+        check(&a - &b, left.difference(&right).copied().collect());
+
+        let mut c = a.clone();
+        c -= &a;
+        assert!(c.is_empty());
+        assert!(c.bitmaps.is_empty());
+        c |= &b;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn test_portable_format() {
+        let treemap: RoaringTreemap = [1, 2 | 1 << 32].into_iter().collect();
+
+        #[rustfmt::skip]
+        let expected = [
+            2, 0, 0, 0, 0, 0, 0, 0, // two bitmaps
+            0, 0, 0, 0,             // high key 0
+            0x3a, 0x30, 0, 0,       // cookie 12346
+            1, 0, 0, 0,             // one container
+            0, 0, 0, 0,             // key 0, one value
+            16, 0, 0, 0,            // offset
+            1, 0,                   // array
+            1, 0, 0, 0,             // high key 1
+            0x3a, 0x30, 0, 0,
+            1, 0, 0, 0,
+            0, 0, 0, 0,
+            16, 0, 0, 0,
+            2, 0,
+        ];
+        assert_eq!(treemap.to_bytes(), expected);
+        assert_eq!(treemap.serialized_size(), expected.len());
+        assert_eq!(RoaringTreemap::from_bytes(&expected).unwrap(), treemap);
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut treemap: RoaringTreemap = sample().into_iter().collect();
+        for optimize in [false, true] {
+            if optimize {
+                treemap.run_optimize();
+            }
+
+            let bytes = treemap.to_bytes();
+            assert_eq!(bytes.len(), treemap.serialized_size());
+            assert_eq!(RoaringTreemap::from_bytes(&bytes).unwrap(), treemap);
+        }
+
+        let empty = RoaringTreemap::new();
+        assert_eq!(
+            RoaringTreemap::from_bytes(&empty.to_bytes()).unwrap(),
+            empty
+        );
+    }
+
+    #[test]
+    fn test_deserialization_errors() {
+        let bytes = RoaringTreemap::from_iter(sample()).to_bytes();
+
+        assert_eq!(
+            RoaringTreemap::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::Truncated)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(RoaringTreemap::from_bytes(&trailing).is_err());
+
+        // Both bitmaps under high key 0
+        let mut repeated = RoaringTreemap::from_iter([1, 2 | 1 << 32]).to_bytes();
+        repeated[30] = 0;
+        assert_eq!(
+            RoaringTreemap::from_bytes(&repeated),
+            Err(DecodeError::Invalid("bitmap keys are not increasing"))
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::filter::probe::Probes;
+use crate::set::sketch::IncompatibleSketches;
+use std::hash::{BuildHasher, Hash};
+
+/// Count-Min sketch (Cormode & Muthukrishnan) for approximate item
+/// frequencies in fixed memory.
+///
+/// Each of `depth` rows of `width` counters gets one counter per item, and
+/// an item's estimate is the smallest of its counters. Estimates never fall
+/// below the true count, and with probability `1 - δ` exceed it by at most
+/// `ε * total`, for `width = ⌈e / ε⌉` and `depth = ⌈ln(1 / δ)⌉`.
+pub struct CountMinSketch<S = DefaultBuildHasher> {
+    counters: Vec<u64>,
+    width: usize,
+    depth: u32,
+    total: u64,
+    hash_builder: S,
+}
+
+impl CountMinSketch {
+    /// Sizes the sketch for an additive error of `epsilon * total` with
+    /// probability `1 - delta`
+    pub fn with_error(epsilon: f64, delta: f64) -> Self {
+        Self::with_error_and_hasher(epsilon, delta, DefaultBuildHasher::default())
+    }
+
+    pub fn with_dimensions(width: usize, depth: u32) -> Self {
+        Self::with_dimensions_and_hasher(width, depth, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> CountMinSketch<S> {
+    /// Panics unless `0 < epsilon` and `0 < delta < 1`
+    pub fn with_error_and_hasher(epsilon: f64, delta: f64, hash_builder: S) -> Self {
+        assert!(epsilon > 0.0, "epsilon must be positive, got {epsilon}");
+        assert!(
+            delta > 0.0 && delta < 1.0,
+            "delta must be in (0, 1), got {delta}"
+        );
+
+        let width = (std::f64::consts::E / epsilon).ceil() as usize;
+        let depth = ((1.0 / delta).ln().ceil() as u32).max(1);
+
+        Self::with_dimensions_and_hasher(width, depth, hash_builder)
+    }
+
+    /// Panics if `width` or `depth` is zero
+    pub fn with_dimensions_and_hasher(width: usize, depth: u32, hash_builder: S) -> Self {
+        assert!(width > 0, "sketch must have at least one column");
+        assert!(depth > 0, "sketch must have at least one row");
+
+        Self {
+            counters: vec![0; width * depth as usize],
+            width,
+            depth,
+            total: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Sum of all counts added
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Additive error bound `e / width` as a fraction of the total
+    pub fn epsilon(&self) -> f64 {
+        std::f64::consts::E / self.width as f64
+    }
+
+    /// Probability `e^-depth` of an estimate exceeding the error bound
+    pub fn delta(&self) -> f64 {
+        (-(self.depth as f64)).exp()
+    }
+
+    /// Bytes taken by the counters
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.counters.as_slice())
+    }
+
+    /// Adds `count` occurrences of `item`. Counters saturate at `u64::MAX`.
+    pub fn add<T: Hash + ?Sized>(&mut self, item: &T, count: u64) {
+        for index in self.indices(item) {
+            self.counters[index] = self.counters[index].saturating_add(count);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// Conservative update (Estan & Varghese): only raises the item's
+    /// counters as far as its new estimate requires, leaving larger ones
+    /// untouched. Estimates stay upper bounds but overshoot less.
+    ///
+    /// Sketches updated this way can still be merged, but the merged
+    /// estimates lose part of the improvement.
+    pub fn add_conservative<T: Hash + ?Sized>(&mut self, item: &T, count: u64) {
+        // Walked twice, once for the minimum and once for the writes
+        let indices = self.indices(item);
+        let target = indices
+            .clone()
+            .map(|index| self.counters[index])
+            .min()
+            .unwrap()
+            .saturating_add(count);
+
+        for index in indices {
+            self.counters[index] = self.counters[index].max(target);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// Upper bound on the number of occurrences of `item`
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        self.indices(item)
+            .map(|index| self.counters[index])
+            .min()
+            .unwrap()
+    }
+
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+        self.total = 0;
+    }
+
+    /// One counter per row
+    fn indices<T: Hash + ?Sized>(
+        &self,
+        item: &T,
+    ) -> impl Iterator<Item = usize> + Clone + use<T, S> {
+        let width = self.width;
+
+        Probes::new(hash128(&self.hash_builder, item), self.depth)
+            .enumerate()
+            .map(move |(row, hash)| row * width + (hash % width as u64) as usize)
+    }
+}
+
+impl<S: BuildHasher + PartialEq> CountMinSketch<S> {
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.depth == other.depth
+            && self.hash_builder == other.hash_builder
+    }
+
+    /// Adds every count of `other`, as if its items had been added to this
+    /// sketch
+    pub fn merge(&mut self, other: &Self) -> Result<(), IncompatibleSketches> {
+        if !self.is_compatible(other) {
+            return Err(IncompatibleSketches);
+        }
+
+        for (counter, &other) in self.counters.iter_mut().zip(&other.counters) {
+            *counter = counter.saturating_add(other);
+        }
+        self.total = self.total.saturating_add(other.total);
+
+        Ok(())
+    }
+}
+
+impl<S: Clone> Clone for CountMinSketch<S> {
+    fn clone(&self) -> Self {
+        Self {
+            counters: self.counters.clone(),
+            width: self.width,
+            depth: self.depth,
+            total: self.total,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::BuildXxHash64;
+
+    /// Item `i` occurs `1 + i % 50` times, with a few heavy hitters on top
+    fn stream() -> Vec<(u32, u64)> {
+        let mut counts: Vec<(u32, u64)> = (0..5_000).map(|i| (i, 1 + i as u64 % 50)).collect();
+        counts[7].1 += 10_000;
+        counts[42].1 += 50_000;
+        counts
+    }
+
+    fn sketch_of(counts: &[(u32, u64)], conservative: bool) -> CountMinSketch {
+        let mut sketch = CountMinSketch::with_error(0.001, 0.01);
+        for &(item, count) in counts {
+            if conservative {
+                sketch.add_conservative(&item, count);
+            } else {
+                sketch.add(&item, count);
+            }
+        }
+        sketch
+    }
+
+    #[test]
+    fn test_sizing() {
+        let sketch = CountMinSketch::with_error(0.001, 0.01);
+
+        assert_eq!(sketch.width(), 2_719);
+        assert_eq!(sketch.depth(), 5);
+        assert!(sketch.epsilon() <= 0.001);
+        assert!(sketch.delta() <= 0.01);
+    }
+
+    #[test]
+    fn test_error_bounds() {
+        let counts = stream();
+        let total: u64 = counts.iter().map(|&(_, count)| count).sum();
+
+        for conservative in [false, true] {
+            let sketch = sketch_of(&counts, conservative);
+            assert_eq!(sketch.total(), total);
+
+            let bound = (sketch.epsilon() * total as f64) as u64;
+            let mut outside = 0;
+            for &(item, count) in &counts {
+                let estimate = sketch.estimate(&item);
+                assert!(estimate >= count);
+                outside += usize::from(estimate > count + bound);
+            }
+            assert!(outside <= counts.len() / 100, "{outside}");
+        }
+    }
+
+    #[test]
+    fn test_heavy_hitters() {
+        let sketch = sketch_of(&stream(), false);
+
+        assert!(sketch.estimate(&42) >= 50_000 + 43);
+        assert!(sketch.estimate(&7) >= 10_000 + 8);
+        assert!(sketch.estimate(&42) < 51_000);
+    }
+
+    #[test]
+    fn test_conservative_update_overshoots_less() {
+        let counts = stream();
+        let standard = sketch_of(&counts, false);
+        let conservative = sketch_of(&counts, true);
+
+        let error = |sketch: &CountMinSketch| -> u64 {
+            counts
+                .iter()
+                .map(|&(item, count)| sketch.estimate(&item) - count)
+                .sum()
+        };
+
+        for &(item, _) in &counts {
+            assert!(conservative.estimate(&item) <= standard.estimate(&item));
+        }
+        assert!(error(&conservative) < error(&standard));
+    }
+
+    #[test]
+    fn test_merge() {
+        let counts = stream();
+        let (left, right) = counts.split_at(2_000);
+
+        let mut merged = sketch_of(left, false);
+        merged.merge(&sketch_of(right, false)).unwrap();
+
+        let whole = sketch_of(&counts, false);
+        assert_eq!(merged.counters, whole.counters);
+        assert_eq!(merged.total(), whole.total());
+    }
+
+    #[test]
+    fn test_incompatible_merge() {
+        let mut sketch = CountMinSketch::with_dimensions(100, 4);
+
+        assert_eq!(
+            sketch.merge(&CountMinSketch::with_dimensions(100, 5)),
+            Err(IncompatibleSketches)
+        );
+        assert!(
+            !CountMinSketch::with_dimensions_and_hasher(100, 4, BuildXxHash64::with_seed(1))
+                .is_compatible(&CountMinSketch::with_dimensions_and_hasher(
+                    100,
+                    4,
+                    BuildXxHash64::with_seed(2)
+                ))
+        );
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut sketch = CountMinSketch::with_dimensions(64, 3);
+        sketch.add(&"hot", 10);
+        sketch.clear();
+
+        assert_eq!(sketch.estimate(&"hot"), 0);
+        assert_eq!(sketch.total(), 0);
+    }
+}
@@ -0,0 +1,13 @@
+use std::fmt;
+
+/// Returned when merging sketches built with different parameters or hashers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncompatibleSketches;
+
+impl fmt::Display for IncompatibleSketches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sketches have different parameters or hashers")
+    }
+}
+
+impl std::error::Error for IncompatibleSketches {}
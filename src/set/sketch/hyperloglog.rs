@@ -0,0 +1,756 @@
+use crate::hash::DefaultBuildHasher;
+use crate::set::DecodeError;
+use crate::set::codec::{Reader, checksum};
+use crate::set::sketch::IncompatibleSketches;
+use std::borrow::Cow;
+use std::hash::{BuildHasher, Hash};
+
+const MIN_PRECISION: u8 = 4;
+const MAX_PRECISION: u8 = 18;
+
+/// Index bits of the sparse representation, `p'` in the HLL++ paper
+const SPARSE_PRECISION: u32 = 25;
+const RHO_BITS: u32 = 6;
+
+const MAGIC: &[u8; 4] = b"HLLS";
+const VERSION: u8 = 1;
+const SPARSE_TAG: u8 = 0;
+const DENSE_TAG: u8 = 1;
+
+/// HyperLogLog distinct count sketch (Flajolet et al.) with the HLL++
+/// sparse representation (Heule, Nunkesser, Hall).
+///
+/// Small sketches store `(index, rank)` pairs at a precision of 25 bits,
+/// which is nearly exact for low cardinalities, and switch to `2^p` dense
+/// registers once those would take less memory. Estimates use Ertl's
+/// improved estimator ("New cardinality estimation algorithms for
+/// HyperLogLog sketches"), which corrects the raw estimate's bias over the
+/// whole range analytically instead of with HLL++'s empirical tables.
+///
+/// New sparse indices go to an unsorted buffer that is sorted and merged in
+/// once it fills, as in HLL++, so building a sparse sketch doesn't shift the
+/// sorted entries on every insert.
+///
+/// The relative standard error is about `1.04 / sqrt(2^p)`.
+pub struct HyperLogLog<S = DefaultBuildHasher> {
+    precision: u8,
+    repr: Repr,
+    /// Sparse entries not merged into `repr` yet, in insertion order
+    buffer: Vec<u32>,
+    hash_builder: S,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Repr {
+    /// Sorted by index, one entry per index: `index << 6 | rank`
+    Sparse(Vec<u32>),
+    Dense(Vec<u8>),
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u8) -> Self {
+        Self::with_hasher(precision, DefaultBuildHasher::default())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> HyperLogLog<S> {
+    /// Panics unless `4 <= precision <= 18`
+    pub fn with_hasher(precision: u8, hash_builder: S) -> Self {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "precision must be in 4..=18, got {precision}"
+        );
+
+        Self {
+            precision,
+            repr: Repr::Sparse(Vec::new()),
+            buffer: Vec::new(),
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Expected relative standard error of [`estimate`](Self::estimate)
+    /// once the sketch is dense
+    pub fn relative_error(&self) -> f64 {
+        1.04 / (self.num_registers() as f64).sqrt()
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.repr, Repr::Sparse(_))
+    }
+
+    /// Bytes taken by the sparse entries and buffer, or the registers
+    pub fn memory_usage(&self) -> usize {
+        match &self.repr {
+            Repr::Sparse(entries) => {
+                std::mem::size_of_val(entries.as_slice())
+                    + std::mem::size_of_val(self.buffer.as_slice())
+            }
+            Repr::Dense(registers) => registers.len(),
+        }
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let hash = self.hash_builder.hash_one(item);
+        let (sparse_limit, buffer_limit) = (self.sparse_limit(), self.buffer_limit());
+
+        match &mut self.repr {
+            Repr::Sparse(entries) => {
+                let index = (hash >> (64 - SPARSE_PRECISION)) as u32;
+                let entry =
+                    index << RHO_BITS | rank(hash << SPARSE_PRECISION, 64 - SPARSE_PRECISION);
+
+                match entries.binary_search_by_key(&index, |&entry| entry >> RHO_BITS) {
+                    Ok(position) => entries[position] = entries[position].max(entry),
+                    Err(_) => self.buffer.push(entry),
+                }
+
+                // Flushing as soon as the sketch might be over the limit
+                // keeps the switch to dense at the same point as inserting
+                // every entry directly
+                if self.buffer.len() >= buffer_limit
+                    || entries.len() + self.buffer.len() > sparse_limit
+                {
+                    self.flush();
+                }
+            }
+            Repr::Dense(registers) => {
+                let index = (hash >> (64 - self.precision)) as usize;
+                let rank = rank(hash << self.precision, 64 - self.precision as u32) as u8;
+                registers[index] = registers[index].max(rank);
+            }
+        }
+    }
+
+    /// Estimated number of distinct items inserted
+    pub fn estimate(&self) -> f64 {
+        match &self.repr {
+            Repr::Sparse(entries) => {
+                let entries = self.with_buffer(entries);
+                // Ertl's estimator works the same over the 2^25 implicit
+                // registers of the sparse representation
+                let max_rank = 64 - SPARSE_PRECISION + 1;
+                let mut histogram = vec![0u64; max_rank as usize + 1];
+                histogram[0] = (1u64 << SPARSE_PRECISION) - entries.len() as u64;
+                for &entry in entries.iter() {
+                    histogram[(entry & ((1 << RHO_BITS) - 1)) as usize] += 1;
+                }
+
+                ertl_estimate(&histogram, 1 << SPARSE_PRECISION)
+            }
+            Repr::Dense(registers) => {
+                let max_rank = 64 - self.precision as usize + 1;
+                let mut histogram = vec![0u64; max_rank + 1];
+                for &register in registers {
+                    histogram[register as usize] += 1;
+                }
+
+                ertl_estimate(&histogram, registers.len() as u64)
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.repr = Repr::Sparse(Vec::new());
+        self.buffer.clear();
+    }
+
+    /// Serializes sparse sketches as delta-encoded varints and dense ones as
+    /// 6-bit registers, followed by a checksum. The hasher is not stored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 + self.memory_usage());
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.precision);
+
+        match &self.repr {
+            Repr::Sparse(entries) => {
+                let entries = self.with_buffer(entries);
+                bytes.push(SPARSE_TAG);
+                bytes.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+
+                let mut previous = 0;
+                for &entry in entries.iter() {
+                    write_varint(&mut bytes, entry - previous);
+                    previous = entry;
+                }
+            }
+            Repr::Dense(registers) => {
+                bytes.push(DENSE_TAG);
+
+                let mut buffer = 0u64;
+                let mut buffered = 0;
+                for &register in registers {
+                    buffer |= (register as u64) << buffered;
+                    buffered += RHO_BITS;
+                    while buffered >= 8 {
+                        bytes.push(buffer as u8);
+                        buffer >>= 8;
+                        buffered -= 8;
+                    }
+                }
+                if buffered > 0 {
+                    bytes.push(buffer as u8);
+                }
+            }
+        }
+
+        let checksum = checksum(&[&bytes]);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn from_bytes_with_hasher(bytes: &[u8], hash_builder: S) -> Result<Self, DecodeError> {
+        if bytes.len() < 8 {
+            return Err(DecodeError::Truncated);
+        }
+        let (body, expected_checksum) = bytes.split_at(bytes.len() - 8);
+
+        let mut reader = Reader::new(body);
+        let version = reader.read_header(MAGIC)?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let precision = reader.read_u8()?;
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return Err(DecodeError::Invalid("precision out of range"));
+        }
+        let tag = reader.read_u8()?;
+
+        if checksum(&[body]) != u64::from_le_bytes(expected_checksum.try_into().unwrap()) {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        let mut sketch = Self::with_hasher(precision, hash_builder);
+
+        match tag {
+            SPARSE_TAG => {
+                let len = reader.read_usize()?;
+                if len > sketch.sparse_limit() {
+                    return Err(DecodeError::Invalid("too many sparse entries"));
+                }
+
+                let mut entries = Vec::with_capacity(len);
+                let mut previous = 0u32;
+                for _ in 0..len {
+                    let delta = read_varint(&mut reader)?;
+                    let entry = previous
+                        .checked_add(delta)
+                        .filter(|&entry| entry >> RHO_BITS < 1 << SPARSE_PRECISION)
+                        .ok_or(DecodeError::Invalid("sparse index out of range"))?;
+
+                    let rank = entry & ((1 << RHO_BITS) - 1);
+                    let ordered = entries.is_empty() || entry >> RHO_BITS > previous >> RHO_BITS;
+                    if rank == 0 || rank > 64 - SPARSE_PRECISION + 1 || !ordered {
+                        return Err(DecodeError::Invalid("bad sparse entry"));
+                    }
+
+                    entries.push(entry);
+                    previous = entry;
+                }
+
+                sketch.repr = Repr::Sparse(entries);
+            }
+            DENSE_TAG => {
+                let num_registers = sketch.num_registers();
+                let packed = reader.read_bytes((num_registers * RHO_BITS as usize).div_ceil(8))?;
+                let max_rank = 64 - precision + 1;
+
+                let mut registers = Vec::with_capacity(num_registers);
+                let mut buffer = 0u64;
+                let mut buffered = 0;
+                let mut packed = packed.iter();
+                while registers.len() < num_registers {
+                    while buffered < RHO_BITS {
+                        buffer |= (*packed.next().unwrap() as u64) << buffered;
+                        buffered += 8;
+                    }
+
+                    let register = (buffer & ((1 << RHO_BITS) - 1)) as u8;
+                    if register > max_rank {
+                        return Err(DecodeError::Invalid("register out of range"));
+                    }
+                    registers.push(register);
+                    buffer >>= RHO_BITS;
+                    buffered -= RHO_BITS;
+                }
+
+                sketch.repr = Repr::Dense(registers);
+            }
+            _ => return Err(DecodeError::Invalid("unknown representation")),
+        }
+        reader.finish()?;
+
+        Ok(sketch)
+    }
+
+    fn num_registers(&self) -> usize {
+        1 << self.precision
+    }
+
+    /// Sparse entries take 4 bytes against 1 per dense register
+    fn sparse_limit(&self) -> usize {
+        self.num_registers() / 4
+    }
+
+    /// Buffered entries that trigger a flush, small enough to sort quickly
+    /// and large enough that merging the sorted entries is rare
+    fn buffer_limit(&self) -> usize {
+        (self.sparse_limit() / 16).max(1)
+    }
+
+    /// Merges the buffer into the sparse entries, switching to dense if they
+    /// outgrow the limit
+    fn flush(&mut self) {
+        if let Repr::Sparse(entries) = &mut self.repr
+            && !self.buffer.is_empty()
+        {
+            sort_entries(&mut self.buffer);
+            *entries = merge_sparse(entries, &self.buffer);
+            self.buffer.clear();
+
+            if entries.len() > self.sparse_limit() {
+                self.make_dense();
+            }
+        }
+    }
+
+    /// `entries` with the buffer merged in, for reads that can't flush
+    fn with_buffer<'a>(&self, entries: &'a [u32]) -> Cow<'a, [u32]> {
+        if self.buffer.is_empty() {
+            return Cow::Borrowed(entries);
+        }
+
+        let mut buffer = self.buffer.clone();
+        sort_entries(&mut buffer);
+        Cow::Owned(merge_sparse(entries, &buffer))
+    }
+
+    fn make_dense(&mut self) {
+        self.flush();
+
+        if let Repr::Sparse(entries) = &self.repr {
+            let mut registers = vec![0; self.num_registers()];
+            for &entry in entries {
+                let (index, rank) = dense_entry(entry, self.precision);
+                registers[index] = registers[index].max(rank);
+            }
+
+            self.repr = Repr::Dense(registers);
+        }
+    }
+}
+
+impl<S: BuildHasher + PartialEq> HyperLogLog<S> {
+    /// Adds every item counted by `other`, as if they had been inserted into
+    /// this sketch
+    pub fn merge(&mut self, other: &Self) -> Result<(), IncompatibleSketches> {
+        if self.precision != other.precision || self.hash_builder != other.hash_builder {
+            return Err(IncompatibleSketches);
+        }
+
+        self.flush();
+
+        match (&mut self.repr, &other.repr) {
+            (Repr::Sparse(entries), Repr::Sparse(other_entries)) => {
+                *entries = merge_sparse(entries, &other.with_buffer(other_entries));
+                if entries.len() > self.sparse_limit() {
+                    self.make_dense();
+                }
+            }
+            (Repr::Sparse(_), Repr::Dense(_)) => {
+                self.make_dense();
+                return self.merge(other);
+            }
+            (Repr::Dense(registers), Repr::Sparse(other_entries)) => {
+                for &entry in other.with_buffer(other_entries).iter() {
+                    let (index, rank) = dense_entry(entry, self.precision);
+                    registers[index] = registers[index].max(rank);
+                }
+            }
+            (Repr::Dense(registers), Repr::Dense(other_registers)) => {
+                for (register, &other) in registers.iter_mut().zip(other_registers) {
+                    *register = (*register).max(other);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Clone> Clone for HyperLogLog<S> {
+    fn clone(&self) -> Self {
+        Self {
+            precision: self.precision,
+            repr: self.repr.clone(),
+            buffer: self.buffer.clone(),
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+/// Position of the first set bit among the top `bits` bits of `value`,
+/// counting from 1, or `bits + 1` if they are all zero
+fn rank(value: u64, bits: u32) -> u32 {
+    if value == 0 {
+        bits + 1
+    } else {
+        value.leading_zeros().min(bits) + 1
+    }
+}
+
+/// Register index and rank at precision `p` of a sparse entry
+fn dense_entry(entry: u32, precision: u8) -> (usize, u8) {
+    let extra_bits = SPARSE_PRECISION - precision as u32;
+    let sparse_index = entry >> RHO_BITS;
+    let index = (sparse_index >> extra_bits) as usize;
+
+    // The index bits below the register index come first in the rank
+    let low = sparse_index & ((1 << extra_bits) - 1);
+    let rank = if low != 0 {
+        low.leading_zeros() - (32 - extra_bits) + 1
+    } else {
+        extra_bits + (entry & ((1 << RHO_BITS) - 1))
+    };
+
+    (index, rank as u8)
+}
+
+/// Sorts buffered entries by index, keeping the highest rank of each
+fn sort_entries(entries: &mut Vec<u32>) {
+    entries.sort_unstable();
+    entries.dedup_by(|next, kept| {
+        let same_index = *next >> RHO_BITS == *kept >> RHO_BITS;
+        if same_index {
+            *kept = (*kept).max(*next);
+        }
+        same_index
+    });
+}
+
+fn merge_sparse(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+
+    while i < left.len() && j < right.len() {
+        let (a, b) = (left[i], right[j]);
+        match (a >> RHO_BITS).cmp(&(b >> RHO_BITS)) {
+            std::cmp::Ordering::Less => {
+                merged.push(a);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                merged.push(b);
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                merged.push(a.max(b));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    merged.extend_from_slice(&left[i..]);
+    merged.extend_from_slice(&right[j..]);
+
+    merged
+}
+
+/// Ertl's improved estimator over a histogram of register values, where
+/// `histogram[k]` counts the registers holding `k` and the last bucket is the
+/// maximum possible rank `q + 1`
+fn ertl_estimate(histogram: &[u64], num_registers: u64) -> f64 {
+    let m = num_registers as f64;
+    let q = histogram.len() - 2;
+
+    let mut z = m * tau(1.0 - histogram[q + 1] as f64 / m);
+    for k in (1..=q).rev() {
+        z = 0.5 * (z + histogram[k] as f64);
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+
+    m * m / (2.0 * std::f64::consts::LN_2 * z)
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if z == previous {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if z == previous {
+            return z / 3.0;
+        }
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(reader: &mut Reader<'_>) -> Result<u32, DecodeError> {
+    let mut value = 0u64;
+    for shift in (0..35).step_by(7) {
+        let byte = reader.read_u8()?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return u32::try_from(value).map_err(|_| DecodeError::Invalid("varint overflows u32"));
+        }
+    }
+
+    Err(DecodeError::Invalid("varint overflows u32"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::BuildXxHash64;
+
+    fn filled(precision: u8, items: std::ops::Range<u64>) -> HyperLogLog {
+        let mut sketch = HyperLogLog::new(precision);
+        for i in items {
+            sketch.insert(&i);
+        }
+        sketch
+    }
+
+    fn assert_close(estimate: f64, actual: f64, tolerance: f64) {
+        let error = (estimate - actual).abs() / actual.max(1.0);
+        assert!(error <= tolerance, "estimate {estimate}, actual {actual}");
+    }
+
+    #[test]
+    fn test_empty() {
+        let sketch = HyperLogLog::new(14);
+        assert!(sketch.is_sparse());
+        assert_eq!(sketch.estimate(), 0.0);
+    }
+
+    #[test]
+    fn test_small_cardinalities_are_nearly_exact() {
+        for n in [1, 10, 100, 1_000] {
+            let sketch = filled(14, 0..n);
+            assert!(sketch.is_sparse());
+            assert!((sketch.estimate() - n as f64).abs() <= 1.0 + n as f64 * 0.001);
+        }
+    }
+
+    #[test]
+    fn test_duplicates_are_not_counted() {
+        let mut sketch = filled(12, 0..500);
+        for i in 0..500u64 {
+            sketch.insert(&i);
+        }
+        assert_close(sketch.estimate(), 500.0, 0.01);
+    }
+
+    #[test]
+    fn test_accuracy() {
+        for precision in [4, 10, 14] {
+            for n in [1_000, 20_000, 200_000] {
+                let sketch = filled(precision, 0..n);
+                let tolerance = 4.0 * sketch.relative_error();
+                assert_close(sketch.estimate(), n as f64, tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_switches_to_dense() {
+        let mut sketch = HyperLogLog::new(10);
+        let mut switched_at = None;
+
+        for i in 0..1_000u64 {
+            sketch.insert(&i);
+            if switched_at.is_none() && !sketch.is_sparse() {
+                switched_at = Some(i + 1);
+            }
+        }
+
+        // 1024 registers take as many bytes as 256 sparse entries
+        assert_eq!(switched_at, Some(257));
+        assert_eq!(sketch.memory_usage(), 1_024);
+        assert_close(sketch.estimate(), 1_000.0, 4.0 * sketch.relative_error());
+    }
+
+    #[test]
+    fn test_sparse_and_dense_agree() {
+        let sparse = filled(18, 0..30_000);
+        assert!(sparse.is_sparse());
+
+        let mut dense = sparse.clone();
+        dense.make_dense();
+        assert_close(
+            dense.estimate(),
+            sparse.estimate(),
+            4.0 * dense.relative_error(),
+        );
+
+        // Inserting into either gives the same registers
+        let mut direct = HyperLogLog::new(18);
+        direct.make_dense();
+        for i in 0..30_000u64 {
+            direct.insert(&i);
+        }
+        assert_eq!(direct.repr, dense.repr);
+    }
+
+    #[test]
+    fn test_buffered_entries() {
+        let mut sketch = HyperLogLog::new(14);
+        // The last 108 items are still buffered when the repeats arrive, so
+        // the buffer holds the same indices twice
+        for i in (0..1_900u64).chain(1_800..1_900) {
+            sketch.insert(&i);
+        }
+        assert_eq!(sketch.buffer.len(), 208);
+
+        // Reads see the buffer as if it had been merged
+        let estimate = sketch.estimate();
+        let bytes = sketch.to_bytes();
+        sketch.flush();
+        assert!(sketch.buffer.is_empty());
+        assert_eq!(sketch.estimate(), estimate);
+        assert_eq!(sketch.to_bytes(), bytes);
+
+        let Repr::Sparse(entries) = &sketch.repr else {
+            panic!("sketch should still be sparse");
+        };
+        assert_eq!(entries.len(), 1_900);
+        assert!(
+            entries
+                .windows(2)
+                .all(|pair| pair[0] >> RHO_BITS < pair[1] >> RHO_BITS)
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let cases = [
+            (0..100, 50..200),
+            (0..100, 0..50_000),
+            (0..50_000, 25_000..80_000),
+        ];
+
+        for (left, right) in cases {
+            let union = filled(12, left.start.min(right.start)..left.end.max(right.end));
+
+            let mut merged = filled(12, left.clone());
+            merged.merge(&filled(12, right.clone())).unwrap();
+
+            let mut reversed = filled(12, right);
+            reversed.merge(&filled(12, left)).unwrap();
+
+            assert_eq!(merged.estimate(), union.estimate());
+            assert_eq!(reversed.estimate(), union.estimate());
+        }
+    }
+
+    #[test]
+    fn test_incompatible_merge() {
+        let mut a = HyperLogLog::new(12);
+
+        assert_eq!(a.merge(&HyperLogLog::new(13)), Err(IncompatibleSketches));
+        assert_eq!(
+            HyperLogLog::with_hasher(12, BuildXxHash64::with_seed(1))
+                .merge(&HyperLogLog::with_hasher(12, BuildXxHash64::with_seed(2))),
+            Err(IncompatibleSketches)
+        );
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        for n in [0, 100, 100_000] {
+            let mut sketch = filled(14, 0..n);
+            let bytes = sketch.to_bytes();
+
+            let decoded = HyperLogLog::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.to_bytes(), bytes);
+            sketch.flush();
+            assert_eq!(decoded.repr, sketch.repr);
+        }
+
+        // Dense registers take 6 bits each
+        let dense = filled(14, 0..100_000);
+        assert_eq!(dense.to_bytes().len(), 7 + (1 << 14) * 6 / 8 + 8);
+    }
+
+    #[test]
+    fn test_deserialization_errors() {
+        let bytes = filled(10, 0..100).to_bytes();
+
+        assert_eq!(
+            HyperLogLog::from_bytes(&bytes[..4]).err(),
+            Some(DecodeError::Truncated)
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[20] ^= 1;
+        assert_eq!(
+            HyperLogLog::from_bytes(&corrupted).err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert_eq!(
+            HyperLogLog::from_bytes(&wrong_magic).err(),
+            Some(DecodeError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn test_rank_conversion() {
+        // An entry whose extra index bits are zero keeps counting into the
+        // sparse rank
+        let entry = (0b1010 << 21) << RHO_BITS | 3;
+        assert_eq!(dense_entry(entry, 4), (0b1010, 21 + 3));
+
+        let entry = ((0b1010 << 21) | 1 << 20) << RHO_BITS | 3;
+        assert_eq!(dense_entry(entry, 4), (0b1010, 1));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::hash::DefaultBuildHasher;
+use crate::set::sketch::minhash::similarity;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// Locality-sensitive hashing index over [`MinHash`](super::MinHash)
+/// signatures, for finding near-duplicates without comparing every pair.
+///
+/// The first `bands * rows` values of each signature are cut into `bands`
+/// bands of `rows` values, and two signatures become candidates when any
+/// band matches exactly. Sets with Jaccard similarity `s` collide with
+/// probability `1 - (1 - s^rows)^bands`, an S-curve whose steepest point is
+/// near `(1 / bands)^(1 / rows)`.
+pub struct LshIndex<K, S = DefaultBuildHasher> {
+    bands: usize,
+    rows: usize,
+    entries: Vec<(K, Vec<u64>)>,
+    /// One table per band, from band hash to entry indices
+    buckets: Vec<HashMap<u64, Vec<usize>>>,
+    hash_builder: S,
+}
+
+impl<K> LshIndex<K> {
+    /// Panics if `bands` or `rows` is zero
+    pub fn new(bands: usize, rows: usize) -> Self {
+        Self::with_hasher(bands, rows, DefaultBuildHasher::default())
+    }
+
+    /// Picks the bands and rows fitting in `num_hashes` whose S-curve is
+    /// steepest closest to `threshold`.
+    ///
+    /// Panics if `num_hashes` is zero or `threshold` is not in `(0, 1)`
+    pub fn with_threshold(num_hashes: usize, threshold: f64) -> Self {
+        Self::with_threshold_and_hasher(num_hashes, threshold, DefaultBuildHasher::default())
+    }
+}
+
+impl<K, S: BuildHasher> LshIndex<K, S> {
+    /// Panics if `bands` or `rows` is zero
+    pub fn with_hasher(bands: usize, rows: usize, hash_builder: S) -> Self {
+        assert!(bands > 0, "index must have at least one band");
+        assert!(rows > 0, "bands must have at least one row");
+
+        Self {
+            bands,
+            rows,
+            entries: Vec::new(),
+            buckets: (0..bands).map(|_| HashMap::new()).collect(),
+            hash_builder,
+        }
+    }
+
+    /// Same as [`with_threshold`](LshIndex::with_threshold) with a custom
+    /// hasher.
+    ///
+    /// Panics if `num_hashes` is zero or `threshold` is not in `(0, 1)`
+    pub fn with_threshold_and_hasher(num_hashes: usize, threshold: f64, hash_builder: S) -> Self {
+        assert!(num_hashes > 0, "signatures must have at least one value");
+        assert!(
+            threshold > 0.0 && threshold < 1.0,
+            "threshold must be in (0, 1), got {threshold}"
+        );
+
+        let distance = |rows: usize| {
+            let bands = (num_hashes / rows) as f64;
+            ((1.0 / bands).powf(1.0 / rows as f64) - threshold).abs()
+        };
+        let rows = (1..=num_hashes)
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+            .unwrap();
+
+        Self::with_hasher(num_hashes / rows, rows, hash_builder)
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn bands(&self) -> usize {
+        self.bands
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of indexed signatures
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Probability that two sets with Jaccard similarity `similarity`
+    /// become candidates
+    pub fn collision_probability(&self, similarity: f64) -> f64 {
+        1.0 - (1.0 - similarity.powi(self.rows as i32)).powi(self.bands as i32)
+    }
+
+    /// Indexes `signature` under `key`.
+    ///
+    /// Panics if the signature has fewer than `bands * rows` values
+    pub fn insert(&mut self, key: K, signature: &[u64]) {
+        let index = self.entries.len();
+        let hashes: Vec<u64> = self.band_hashes(signature).collect();
+        for (band, hash) in hashes.into_iter().enumerate() {
+            self.buckets[band].entry(hash).or_default().push(index);
+        }
+        self.entries.push((key, signature.to_vec()));
+    }
+
+    /// Keys sharing at least one band with `signature`, in insertion order.
+    ///
+    /// Panics if the signature has fewer than `bands * rows` values
+    pub fn candidates(&self, signature: &[u64]) -> Vec<&K> {
+        self.candidate_indices(signature)
+            .into_iter()
+            .map(|index| &self.entries[index].0)
+            .collect()
+    }
+
+    /// Candidates whose estimated similarity to `signature` is at least
+    /// `threshold`, most similar first
+    pub fn query(&self, signature: &[u64], threshold: f64) -> Vec<(&K, f64)> {
+        let mut matches: Vec<(&K, f64)> = self
+            .candidate_indices(signature)
+            .into_iter()
+            .map(|index| {
+                let (key, indexed) = &self.entries[index];
+                (key, similarity(signature, indexed))
+            })
+            .filter(|&(_, similarity)| similarity >= threshold)
+            .collect();
+
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1));
+        matches
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        for table in &mut self.buckets {
+            table.clear();
+        }
+    }
+
+    fn candidate_indices(&self, signature: &[u64]) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .band_hashes(signature)
+            .zip(&self.buckets)
+            .filter_map(|(hash, table)| table.get(&hash))
+            .flatten()
+            .copied()
+            .collect();
+
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    fn band_hashes<'a>(&'a self, signature: &'a [u64]) -> impl Iterator<Item = u64> + 'a {
+        assert!(
+            signature.len() >= self.bands * self.rows,
+            "signature has {} values, index needs {}",
+            signature.len(),
+            self.bands * self.rows
+        );
+
+        signature
+            .chunks_exact(self.rows)
+            .take(self.bands)
+            .map(|band| self.hash_builder.hash_one(band))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::BuildSipHasher24;
+    use crate::set::sketch::MinHash;
+
+    fn signature(items: impl IntoIterator<Item = u64>) -> Vec<u64> {
+        let mut sketch = MinHash::new(128);
+        for item in items {
+            sketch.insert(&item);
+        }
+        sketch.signature()
+    }
+
+    #[test]
+    fn test_with_threshold() {
+        let index = LshIndex::<u32>::with_threshold(128, 0.5);
+        assert_eq!((index.bands(), index.rows()), (25, 5));
+        assert!(index.collision_probability(0.9) > 0.99);
+        assert!(index.collision_probability(0.2) < 0.01);
+    }
+
+    #[test]
+    fn test_finds_near_duplicates() {
+        let mut index = LshIndex::with_threshold(128, 0.5);
+
+        // Documents of 200 shingles each, every one with a near-duplicate
+        // sharing 190 of them (similarity 0.9)
+        for doc in 0..100u64 {
+            let base = doc * 1_000;
+            index.insert(doc, &signature(base..base + 200));
+        }
+
+        for doc in 0..100u64 {
+            let base = doc * 1_000;
+            let near = signature(base + 10..base + 210);
+
+            let matches = index.query(&near, 0.7);
+            assert_eq!(matches.len(), 1, "doc {doc}: {matches:?}");
+            assert_eq!(*matches[0].0, doc);
+            assert!(matches[0].1 > 0.7);
+        }
+
+        assert!(index.candidates(&signature(500_000..500_200)).len() <= 2);
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        let mut index = LshIndex::with_threshold_and_hasher(128, 0.5, BuildSipHasher24::default());
+        assert_eq!((index.bands(), index.rows()), (25, 5));
+
+        index.insert("doc", &signature(0..200));
+        let matches = index.query(&signature(10..210), 0.7);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(*matches[0].0, "doc");
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut index = LshIndex::new(4, 4);
+        let sig = signature(0..50);
+        index.insert("doc", &sig);
+        assert_eq!(index.candidates(&sig), vec![&"doc"]);
+
+        index.clear();
+        assert!(index.is_empty());
+        assert!(index.candidates(&sig).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_short_signature_panics() {
+        let mut index = LshIndex::new(8, 4);
+        index.insert(0, &[0; 16]);
+    }
+}
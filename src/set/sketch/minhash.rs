@@ -0,0 +1,293 @@
+use crate::hash::murmur64;
+use crate::hash::{DefaultBuildHasher, hash128};
+use crate::set::sketch::IncompatibleSketches;
+use std::hash::{BuildHasher, Hash};
+
+/// Marks a one-permutation bin no item has fallen into
+const EMPTY: u64 = u64::MAX;
+
+/// How a [`MinHash`] derives its signature values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinHashScheme {
+    /// Every item is hashed by each of `k` independent functions, and each
+    /// function keeps its minimum. `O(k)` per insertion.
+    KPermutations,
+    /// Every item is hashed once into one of `k` bins, and each bin keeps its
+    /// minimum. Bins left empty borrow the value of another bin chosen by
+    /// optimal densification (Shrivastava). `O(1)` per insertion.
+    OnePermutation,
+}
+
+/// MinHash signature of a set, for estimating the Jaccard similarity
+/// `|A ∩ B| / |A ∪ B|` of two sets from fixed-size sketches (Broder).
+///
+/// Two signatures agree at any position with probability equal to the
+/// similarity of their sets, so the estimate has a standard error of
+/// `sqrt(J * (1 - J) / k)` for `k` hashes.
+pub struct MinHash<S = DefaultBuildHasher> {
+    mins: Vec<u64>,
+    scheme: MinHashScheme,
+    hash_builder: S,
+}
+
+impl MinHash {
+    /// Uses `num_hashes` independent hash functions
+    pub fn new(num_hashes: usize) -> Self {
+        Self::with_hasher(num_hashes, DefaultBuildHasher::default())
+    }
+
+    /// Hashes each item once into one of `num_bins` bins
+    pub fn one_permutation(num_bins: usize) -> Self {
+        Self::one_permutation_with_hasher(num_bins, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> MinHash<S> {
+    /// Panics if `num_hashes` is zero
+    pub fn with_hasher(num_hashes: usize, hash_builder: S) -> Self {
+        Self::with_scheme(num_hashes, MinHashScheme::KPermutations, hash_builder)
+    }
+
+    /// Panics if `num_bins` is zero
+    pub fn one_permutation_with_hasher(num_bins: usize, hash_builder: S) -> Self {
+        Self::with_scheme(num_bins, MinHashScheme::OnePermutation, hash_builder)
+    }
+
+    fn with_scheme(len: usize, scheme: MinHashScheme, hash_builder: S) -> Self {
+        assert!(len > 0, "signature must have at least one value");
+
+        Self {
+            mins: vec![EMPTY; len],
+            scheme,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn scheme(&self) -> MinHashScheme {
+        self.scheme
+    }
+
+    /// Length of the signature
+    pub fn num_hashes(&self) -> usize {
+        self.mins.len()
+    }
+
+    /// Whether nothing has been inserted
+    pub fn is_empty(&self) -> bool {
+        self.mins.iter().all(|&min| min == EMPTY)
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let (h1, h2) = hash128(&self.hash_builder, item);
+
+        match self.scheme {
+            MinHashScheme::KPermutations => {
+                for (i, min) in self.mins.iter_mut().enumerate() {
+                    let value = murmur64(h1.wrapping_add((i as u64).wrapping_mul(h2)));
+                    *min = (*min).min(value);
+                }
+            }
+            MinHashScheme::OnePermutation => {
+                let bin = reduce(h1, self.mins.len());
+                self.mins[bin] = self.mins[bin].min(h2);
+            }
+        }
+    }
+
+    /// Values to compare or index in an [`LshIndex`](super::LshIndex).
+    /// Signatures of the same scheme, length and hasher are comparable.
+    pub fn signature(&self) -> Vec<u64> {
+        match self.scheme {
+            MinHashScheme::KPermutations => self.mins.clone(),
+            MinHashScheme::OnePermutation => densify(&self.mins),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.mins.fill(EMPTY);
+    }
+}
+
+impl<S: BuildHasher + PartialEq> MinHash<S> {
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.scheme == other.scheme
+            && self.mins.len() == other.mins.len()
+            && self.hash_builder == other.hash_builder
+    }
+
+    /// Estimated Jaccard similarity of the two sets
+    pub fn jaccard(&self, other: &Self) -> Result<f64, IncompatibleSketches> {
+        if !self.is_compatible(other) {
+            return Err(IncompatibleSketches);
+        }
+
+        Ok(similarity(&self.signature(), &other.signature()))
+    }
+
+    /// Turns this into the signature of the union of both sets
+    pub fn merge(&mut self, other: &Self) -> Result<(), IncompatibleSketches> {
+        if !self.is_compatible(other) {
+            return Err(IncompatibleSketches);
+        }
+
+        for (min, &other) in self.mins.iter_mut().zip(&other.mins) {
+            *min = (*min).min(other);
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Clone> Clone for MinHash<S> {
+    fn clone(&self) -> Self {
+        Self {
+            mins: self.mins.clone(),
+            scheme: self.scheme,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+/// Fraction of positions at which two signatures agree
+pub(crate) fn similarity(left: &[u64], right: &[u64]) -> f64 {
+    let matching = left.iter().zip(right).filter(|(a, b)| a == b).count();
+    matching as f64 / left.len().max(right.len()) as f64
+}
+
+/// Maps a uniform hash onto `0..len` without a division
+fn reduce(hash: u64, len: usize) -> usize {
+    ((hash as u128 * len as u128) >> 64) as usize
+}
+
+/// Fills every empty bin with the value of a non-empty one, probing bins
+/// with a hash of `(bin, attempt)` so that all signatures borrow from the
+/// same place whenever the same bins are filled. Signatures of empty sets
+/// are left as they are.
+fn densify(mins: &[u64]) -> Vec<u64> {
+    if mins.iter().all(|&min| min == EMPTY) {
+        return mins.to_vec();
+    }
+
+    let mut dense = mins.to_vec();
+    for (bin, value) in dense.iter_mut().enumerate() {
+        if *value != EMPTY {
+            continue;
+        }
+
+        for attempt in 0u64.. {
+            let probe = murmur64((bin as u64) << 32 ^ attempt ^ 0x9e37_79b9_7f4a_7c15);
+            let source = mins[reduce(probe, mins.len())];
+            if source != EMPTY {
+                *value = source;
+                break;
+            }
+        }
+    }
+
+    dense
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::BuildXxHash64;
+
+    fn sketch_of(
+        scheme: MinHashScheme,
+        len: usize,
+        items: impl IntoIterator<Item = u64>,
+    ) -> MinHash {
+        let mut sketch = match scheme {
+            MinHashScheme::KPermutations => MinHash::new(len),
+            MinHashScheme::OnePermutation => MinHash::one_permutation(len),
+        };
+        for item in items {
+            sketch.insert(&item);
+        }
+        sketch
+    }
+
+    const SCHEMES: [MinHashScheme; 2] =
+        [MinHashScheme::KPermutations, MinHashScheme::OnePermutation];
+
+    #[test]
+    fn test_jaccard_accuracy() {
+        // |A ∩ B| = 500, |A ∪ B| = 1500
+        for scheme in SCHEMES {
+            let a = sketch_of(scheme, 512, 0..1_000);
+            let b = sketch_of(scheme, 512, 500..1_500);
+
+            let estimate = a.jaccard(&b).unwrap();
+            assert!(
+                (estimate - 1.0 / 3.0).abs() < 0.07,
+                "{scheme:?}: {estimate}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_identical_and_disjoint_sets() {
+        for scheme in SCHEMES {
+            let a = sketch_of(scheme, 128, 0..1_000);
+            let same = sketch_of(scheme, 128, (0..1_000).rev());
+            let disjoint = sketch_of(scheme, 128, 1_000..2_000);
+
+            assert_eq!(a.jaccard(&same).unwrap(), 1.0);
+            assert!(a.jaccard(&disjoint).unwrap() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_densification_of_small_sets() {
+        let a = sketch_of(MinHashScheme::OnePermutation, 256, 0..20);
+        let b = sketch_of(MinHashScheme::OnePermutation, 256, 10..30);
+
+        assert!(!a.signature().contains(&EMPTY));
+        let estimate = a.jaccard(&b).unwrap();
+        assert!((estimate - 1.0 / 3.0).abs() < 0.2, "{estimate}");
+    }
+
+    #[test]
+    fn test_merge_is_union() {
+        for scheme in SCHEMES {
+            let mut merged = sketch_of(scheme, 64, 0..300);
+            merged.merge(&sketch_of(scheme, 64, 200..700)).unwrap();
+
+            assert_eq!(
+                merged.signature(),
+                sketch_of(scheme, 64, 0..700).signature()
+            );
+        }
+    }
+
+    #[test]
+    fn test_incompatible() {
+        let k = MinHash::new(64);
+
+        assert_eq!(
+            k.jaccard(&MinHash::one_permutation(64)),
+            Err(IncompatibleSketches)
+        );
+        assert_eq!(k.jaccard(&MinHash::new(32)), Err(IncompatibleSketches));
+        assert!(
+            !MinHash::with_hasher(64, BuildXxHash64::with_seed(1))
+                .is_compatible(&MinHash::with_hasher(64, BuildXxHash64::with_seed(2)))
+        );
+    }
+
+    #[test]
+    fn test_empty_and_clear() {
+        let mut sketch = MinHash::one_permutation(16);
+        assert!(sketch.is_empty());
+        assert!(sketch.signature().iter().all(|&value| value == EMPTY));
+
+        sketch.insert("word");
+        assert!(!sketch.is_empty());
+        sketch.clear();
+        assert!(sketch.is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+mod count_min;
+mod error;
+mod hyperloglog;
+mod lsh;
+mod minhash;
+mod top_k;
+
+pub use count_min::CountMinSketch;
+pub use error::IncompatibleSketches;
+pub use hyperloglog::HyperLogLog;
+pub use lsh::LshIndex;
+pub use minhash::{MinHash, MinHashScheme};
+pub use top_k::{TopK, TopKEntry};
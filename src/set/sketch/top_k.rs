@@ -0,0 +1,315 @@
+use crate::tree::AvlTree;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Most frequent items of a stream, tracked with the Space-Saving algorithm
+/// (Metwally, Agrawal, El Abbadi) in a fixed number of counters.
+///
+/// An item that isn't monitored takes over the counter with the smallest
+/// count, inheriting that count as its error. Any item occurring more than
+/// `total / capacity` times is guaranteed to be monitored, and every
+/// reported count lies within `[count - error, count]` of the true one.
+///
+/// Counters are indexed by `(count, sequence)` in an [`AvlTree`], so the
+/// smallest one is found and replaced in `O(log capacity)`.
+pub struct TopK<T> {
+    counters: HashMap<T, Counter>,
+    by_count: AvlTree<(u64, u64), T>,
+    capacity: usize,
+    next_seq: u64,
+    total: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Counter {
+    count: u64,
+    error: u64,
+    seq: u64,
+}
+
+/// Item reported by [`TopK::top`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopKEntry<T> {
+    pub item: T,
+    /// Upper bound on the number of occurrences
+    pub count: u64,
+    /// Maximum overestimation, so the item occurred at least
+    /// `count - error` times
+    pub error: u64,
+}
+
+impl<T: Hash + Eq + Clone> TopK<T> {
+    /// Panics if `capacity` is zero
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "top-k needs at least one counter");
+
+        Self {
+            counters: HashMap::with_capacity(capacity),
+            by_count: AvlTree::new(),
+            capacity,
+            next_seq: 0,
+            total: 0,
+        }
+    }
+
+    /// Number of counters
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of monitored items
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    /// Number of occurrences offered so far
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn offer(&mut self, item: T) {
+        self.offer_count(item, 1);
+    }
+
+    /// Records `count` occurrences of `item`
+    pub fn offer_count(&mut self, item: T, count: u64) {
+        if count == 0 {
+            return;
+        }
+        self.total = self.total.saturating_add(count);
+
+        if let Some(counter) = self.counters.get(&item).copied() {
+            self.by_count.remove(&(counter.count, counter.seq));
+            self.track(item, counter.count.saturating_add(count), counter.error);
+            return;
+        }
+
+        if self.counters.len() < self.capacity {
+            self.track(item, count, 0);
+            return;
+        }
+
+        let (&(min_count, min_seq), _) = self.by_count.min().unwrap();
+        let evicted = self.by_count.remove(&(min_count, min_seq)).unwrap();
+        self.counters.remove(&evicted);
+
+        self.track(item, min_count.saturating_add(count), min_count);
+    }
+
+    /// Estimated count of `item` if it is monitored
+    pub fn count(&self, item: &T) -> Option<u64> {
+        self.counters.get(item).map(|counter| counter.count)
+    }
+
+    /// Up to `k` monitored items with the highest counts, highest first.
+    /// Items with equal counts are listed most recently updated first.
+    pub fn top(&self, k: usize) -> Vec<TopKEntry<T>> {
+        self.by_count
+            .iter()
+            .rev()
+            .take(k)
+            .map(|(_, item)| {
+                let counter = self.counters[item];
+                TopKEntry {
+                    item: item.clone(),
+                    count: counter.count,
+                    error: counter.error,
+                }
+            })
+            .collect()
+    }
+
+    /// Adds the counts of `other`, following the mergeable summary of
+    /// Agarwal et al.: an item missing from a full summary could have
+    /// occurred up to that summary's smallest count, which is added to both
+    /// its count and its error. The `capacity` largest counts are kept.
+    pub fn merge(&mut self, other: &Self) {
+        let floor = |summary: &Self| {
+            if summary.counters.len() < summary.capacity {
+                0
+            } else {
+                summary.by_count.min().map_or(0, |(&(count, _), _)| count)
+            }
+        };
+        let (self_floor, other_floor) = (floor(self), floor(other));
+
+        let mut merged: Vec<(T, u64, u64)> = Vec::new();
+        for (item, counter) in &self.counters {
+            let (count, error) = other
+                .counters
+                .get(item)
+                .map_or((other_floor, other_floor), |other| {
+                    (other.count, other.error)
+                });
+            merged.push((
+                item.clone(),
+                counter.count.saturating_add(count),
+                counter.error.saturating_add(error),
+            ));
+        }
+        for (item, counter) in &other.counters {
+            if !self.counters.contains_key(item) {
+                merged.push((
+                    item.clone(),
+                    counter.count.saturating_add(self_floor),
+                    counter.error.saturating_add(self_floor),
+                ));
+            }
+        }
+
+        merged.sort_by_key(|&(_, count, _)| Reverse(count));
+        merged.truncate(self.capacity);
+
+        self.counters.clear();
+        self.by_count = AvlTree::new();
+        for (item, count, error) in merged.into_iter().rev() {
+            self.track(item, count, error);
+        }
+        self.total = self.total.saturating_add(other.total);
+    }
+
+    pub fn clear(&mut self) {
+        self.counters.clear();
+        self.by_count = AvlTree::new();
+        self.total = 0;
+    }
+
+    fn track(&mut self, item: T, count: u64, error: u64) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.by_count.insert((count, seq), item.clone());
+        self.counters.insert(item, Counter { count, error, seq });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Item `i` occurs `1000 / (i + 1)` times, interleaved
+    fn zipf_stream(num_items: u64) -> (Vec<u64>, HashMap<u64, u64>) {
+        let mut stream = Vec::new();
+        let mut counts = HashMap::new();
+
+        for round in 0..1_000 {
+            for i in 0..num_items {
+                if round < 1_000 / (i + 1) {
+                    stream.push(i);
+                    *counts.entry(i).or_insert(0) += 1;
+                }
+            }
+        }
+
+        (stream, counts)
+    }
+
+    #[test]
+    fn test_exact_below_capacity() {
+        let mut top = TopK::new(10);
+        for item in ["a", "b", "a", "c", "a", "b"] {
+            top.offer(item);
+        }
+
+        let entries = top.top(2);
+        assert_eq!(
+            entries,
+            vec![
+                TopKEntry {
+                    item: "a",
+                    count: 3,
+                    error: 0
+                },
+                TopKEntry {
+                    item: "b",
+                    count: 2,
+                    error: 0
+                },
+            ]
+        );
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.total(), 6);
+    }
+
+    #[test]
+    fn test_evicts_smallest_counter() {
+        let mut top = TopK::new(2);
+        top.offer_count("a", 5);
+        top.offer_count("b", 2);
+        top.offer("c");
+
+        assert_eq!(top.count(&"b"), None);
+        assert_eq!(top.count(&"c"), Some(3));
+        assert_eq!(top.top(2)[1].error, 2);
+    }
+
+    #[test]
+    fn test_heavy_hitters_and_bounds() {
+        let (stream, counts) = zipf_stream(500);
+        let mut top = TopK::new(50);
+        for &item in &stream {
+            top.offer(item);
+        }
+
+        let entries = top.top(10);
+        let items: Vec<u64> = entries.iter().map(|entry| entry.item).collect();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+
+        for entry in top.top(50) {
+            let actual = counts[&entry.item];
+            assert!(entry.count >= actual);
+            assert!(entry.count - entry.error <= actual);
+        }
+
+        // Anything above total / capacity is monitored
+        let threshold = top.total() / 50;
+        for (item, &count) in &counts {
+            if count > threshold {
+                assert!(top.count(item).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let (stream, counts) = zipf_stream(300);
+        let (left, right) = stream.split_at(stream.len() / 3);
+
+        let mut merged = TopK::new(40);
+        let mut other = TopK::new(40);
+        for &item in left {
+            merged.offer(item);
+        }
+        for &item in right {
+            other.offer(item);
+        }
+        merged.merge(&other);
+
+        assert_eq!(merged.total(), stream.len() as u64);
+        assert_eq!(merged.len(), 40);
+
+        let items: Vec<u64> = merged.top(5).iter().map(|entry| entry.item).collect();
+        assert_eq!(items, (0..5).collect::<Vec<_>>());
+
+        for entry in merged.top(40) {
+            let actual = counts[&entry.item];
+            assert!(entry.count >= actual);
+            assert!(entry.count - entry.error <= actual);
+        }
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut top = TopK::new(4);
+        top.offer(1);
+        top.clear();
+
+        assert!(top.is_empty());
+        assert!(top.top(4).is_empty());
+    }
+}
@@ -20,6 +20,7 @@ pub fn get_node<K, V>(node: &AVLTreeNode<K, V>) -> &AVLTreeNode<K, V> {
 
 pub struct AvlTreeIterator<'a, K, V, I> {
     next_node: Option<&'a AVLTreeNode<K, V>>,
+    next_back_node: Option<&'a AVLTreeNode<K, V>>,
     get_item_func: fn(&'a AVLTreeNode<K, V>) -> I,
 }
 
@@ -38,9 +39,11 @@ impl<'a, K, V, R> AvlTreeIterator<'a, K, V, R> {
         get_item_func: fn(&'a AVLTreeNode<K, V>) -> R,
     ) -> Self {
         let next_node = root.as_ref().map(|root| root.find_leftmost_node());
+        let next_back_node = root.as_ref().map(|root| root.find_rightmost_node());
 
         Self {
             next_node,
+            next_back_node,
             get_item_func,
         }
     }
@@ -70,6 +73,40 @@ impl<'a, K, V, R> AvlTreeIterator<'a, K, V, R> {
 
         None
     }
+
+    fn find_predecessor(&self, node: &'a AVLTreeNode<K, V>) -> Option<&'a AVLTreeNode<K, V>> {
+        if let Some(left) = &node.left {
+            return Some(left.find_rightmost_node());
+        }
+
+        let mut current = node;
+
+        let mut parent = unsafe { current.parent.as_ref() };
+
+        while let Some(node) = parent {
+            // If we're the left child of our parent, we need to go up again
+            if node
+                .left
+                .as_ref()
+                .is_some_and(|left| std::ptr::eq(&**left, current))
+            {
+                current = node;
+                parent = unsafe { node.parent.as_ref() };
+            } else {
+                return Some(node);
+            }
+        }
+
+        None
+    }
+
+    /// True if `node`, just returned from one end, was the last one left
+    fn is_last(&self, node: &AVLTreeNode<K, V>) -> bool {
+        self.next_node.is_some_and(|next| std::ptr::eq(next, node))
+            && self
+                .next_back_node
+                .is_some_and(|next_back| std::ptr::eq(next_back, node))
+    }
 }
 
 impl<K, V, R> Iterator for AvlTreeIterator<'_, K, V, R> {
@@ -78,7 +115,29 @@ impl<K, V, R> Iterator for AvlTreeIterator<'_, K, V, R> {
     fn next(&mut self) -> Option<Self::Item> {
         let current = self.next_node?;
         let result = (self.get_item_func)(current);
-        self.next_node = self.find_successor(current);
+
+        if self.is_last(current) {
+            self.next_node = None;
+            self.next_back_node = None;
+        } else {
+            self.next_node = self.find_successor(current);
+        }
+
+        Some(result)
+    }
+}
+
+impl<K, V, R> DoubleEndedIterator for AvlTreeIterator<'_, K, V, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let current = self.next_back_node?;
+        let result = (self.get_item_func)(current);
+
+        if self.is_last(current) {
+            self.next_node = None;
+            self.next_back_node = None;
+        } else {
+            self.next_back_node = self.find_predecessor(current);
+        }
 
         Some(result)
     }
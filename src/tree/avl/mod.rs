@@ -124,15 +124,15 @@ impl<K: Ord, V> AvlTree<K, V> {
         })
     }
 
-    pub fn iter(&self) -> AvlTreeKeyValueIterator<K, V> {
+    pub fn iter(&self) -> AvlTreeKeyValueIterator<'_, K, V> {
         self.into_iter()
     }
 
-    pub fn keys(&self) -> AvlTreeKeyIterator<K, V> {
+    pub fn keys(&self) -> AvlTreeKeyIterator<'_, K, V> {
         AvlTreeKeyIterator::new(self.root.as_deref(), get_key)
     }
 
-    pub fn values(&self) -> AvlTreeValueIterator<K, V> {
+    pub fn values(&self) -> AvlTreeValueIterator<'_, K, V> {
         AvlTreeValueIterator::new(self.root.as_deref(), get_value)
     }
 }
@@ -344,7 +344,7 @@ impl<K, V> AvlTree<K, V> {
     }
 
     #[cfg(test)]
-    fn nodes(&self) -> AvlTreeNodeIterator<K, V> {
+    fn nodes(&self) -> AvlTreeNodeIterator<'_, K, V> {
         AvlTreeNodeIterator::new(self.root.as_deref(), get_node)
     }
 }
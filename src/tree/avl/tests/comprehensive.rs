@@ -484,15 +484,15 @@ fn test_all_invariants_maintained(operations: Vec<(bool, i32, char)>) -> bool {
                 match node {
                     None => true,
                     Some(node) => {
-                        if let Some(min_key) = min {
-                            if node.key <= *min_key {
-                                return false;
-                            }
+                        if let Some(min_key) = min
+                            && node.key <= *min_key
+                        {
+                            return false;
                         }
-                        if let Some(max_key) = max {
-                            if node.key >= *max_key {
-                                return false;
-                            }
+                        if let Some(max_key) = max
+                            && node.key >= *max_key
+                        {
+                            return false;
                         }
 
                         is_bst(&node.left, min, Some(&node.key))
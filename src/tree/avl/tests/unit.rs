@@ -108,6 +108,38 @@ fn test_iterator(values: Vec<(i32, char)>) -> bool {
     avl_values == std_btree_values
 }
 
+#[quickcheck]
+fn test_double_ended_iterator(values: Vec<(i32, char)>, from_back: Vec<bool>) -> bool {
+    let avl_tree = values.iter().cloned().collect::<AvlTree<_, _>>();
+    let std_btree = values
+        .iter()
+        .cloned()
+        .collect::<std::collections::BTreeMap<_, _>>();
+
+    if !avl_tree.keys().rev().eq(std_btree.keys().rev()) {
+        return false;
+    }
+
+    // Alternating ends must meet in the middle without repeating an item
+    let mut avl_iter = avl_tree.iter();
+    let mut std_iter = std_btree.iter();
+    for back in from_back
+        .into_iter()
+        .chain(std::iter::repeat_n(false, values.len() + 1))
+    {
+        let (avl_item, std_item) = if back {
+            (avl_iter.next_back(), std_iter.next_back())
+        } else {
+            (avl_iter.next(), std_iter.next())
+        };
+        if avl_item != std_item {
+            return false;
+        }
+    }
+
+    true
+}
+
 #[quickcheck]
 fn test_height(values: Vec<(i32, char)>) -> bool {
     let avl_tree = values.into_iter().collect::<AvlTree<_, _>>();
@@ -268,16 +300,16 @@ fn test_tree_invariants(values: Vec<(i32, char)>) -> bool {
             None => true,
             Some(node) => {
                 // Check the current node's key against bounds
-                if let Some(min_key) = min {
-                    if node.key <= *min_key {
-                        return false;
-                    }
+                if let Some(min_key) = min
+                    && node.key <= *min_key
+                {
+                    return false;
                 }
 
-                if let Some(max_key) = max {
-                    if node.key >= *max_key {
-                        return false;
-                    }
+                if let Some(max_key) = max
+                    && node.key >= *max_key
+                {
+                    return false;
                 }
 
                 // Recursively check left and right subtrees
@@ -0,0 +1,352 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+use std::slice;
+
+/// Ordered map stored as a `Vec` of entries sorted by key.
+///
+/// Lookups are binary searches over contiguous memory, and iteration is a
+/// slice walk, so small or read-mostly maps beat a pointer-based tree such
+/// as [`AvlTree`](super::AvlTree). Inserting or removing a single entry
+/// shifts the entries after it and costs `O(n)`; [`Extend`] sorts the new
+/// entries and merges them in one `O(n + m log m)` pass instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlatMap<K, V> {
+    /// Sorted by key, keys unique
+    entries: Vec<(K, V)>,
+}
+
+impl<K: Ord, V> FlatMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            Err(index) => {
+                self.entries.insert(index, (key, value));
+                None
+            }
+        }
+    }
+
+    pub fn get<Q: Borrow<K>>(&self, key: &Q) -> Option<&V> {
+        let index = self.search(key.borrow()).ok()?;
+        Some(&self.entries[index].1)
+    }
+
+    pub fn get_mut<Q: Borrow<K>>(&mut self, key: &Q) -> Option<&mut V> {
+        let index = self.search(key.borrow()).ok()?;
+        Some(&mut self.entries[index].1)
+    }
+
+    pub fn contains<Q: Borrow<K>>(&self, key: &Q) -> bool {
+        self.search(key.borrow()).is_ok()
+    }
+
+    pub fn remove<Q: Borrow<K>>(&mut self, key: &Q) -> Option<V> {
+        let index = self.search(key.borrow()).ok()?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn min(&self) -> Option<(&K, &V)> {
+        self.entries.first().map(get_key_value)
+    }
+
+    pub fn max(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(get_key_value)
+    }
+
+    pub fn iter(&self) -> FlatMapKeyValueIterator<'_, K, V> {
+        self.into_iter()
+    }
+
+    pub fn keys(&self) -> FlatMapKeyIterator<'_, K, V> {
+        FlatMapIterator::new(&self.entries, get_key)
+    }
+
+    pub fn values(&self) -> FlatMapValueIterator<'_, K, V> {
+        FlatMapIterator::new(&self.entries, get_value)
+    }
+
+    /// Entries with keys in `range`, in key order
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> FlatMapKeyValueIterator<'_, K, V> {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.entries.partition_point(|(k, _)| k < key),
+            Bound::Excluded(key) => self.entries.partition_point(|(k, _)| k <= key),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => self.entries.partition_point(|(k, _)| k <= key),
+            Bound::Excluded(key) => self.entries.partition_point(|(k, _)| k < key),
+            Bound::Unbounded => self.entries.len(),
+        };
+
+        FlatMapIterator::new(&self.entries[start..end.max(start)], get_key_value)
+    }
+
+    fn search(&self, key: &K) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.cmp(key))
+    }
+}
+
+impl<K, V> FlatMap<K, V> {
+    pub fn size(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<K: Ord, V> Default for FlatMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for FlatMap<K, V> {
+    /// Sorts the new entries and merges them with the existing ones in a
+    /// single pass. For repeated keys the last value wins, as with
+    /// [`insert`](FlatMap::insert).
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let mut incoming: Vec<(K, V)> = iter.into_iter().collect();
+        if incoming.is_empty() {
+            return;
+        }
+
+        // Stable, so equal keys keep their order and the last one is kept
+        incoming.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut incoming = dedup_keep_last(incoming).into_iter().peekable();
+
+        let existing = std::mem::take(&mut self.entries);
+        let mut merged = Vec::with_capacity(existing.len() + incoming.len());
+        let mut existing = existing.into_iter().peekable();
+
+        loop {
+            let order = match (existing.peek(), incoming.peek()) {
+                (Some(old), Some(new)) => old.0.cmp(&new.0),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            match order {
+                Ordering::Less => merged.extend(existing.next()),
+                Ordering::Greater => merged.extend(incoming.next()),
+                Ordering::Equal => {
+                    existing.next();
+                    merged.extend(incoming.next());
+                }
+            }
+        }
+
+        self.entries = merged;
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for FlatMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a FlatMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = FlatMapKeyValueIterator<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FlatMapIterator::new(&self.entries, get_key_value)
+    }
+}
+
+impl<K, V> IntoIterator for FlatMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// Entries of a sorted run with equal keys next to each other, keeping the
+/// last of each group
+fn dedup_keep_last<K: Ord, V>(sorted: Vec<(K, V)>) -> Vec<(K, V)> {
+    let mut result: Vec<(K, V)> = Vec::with_capacity(sorted.len());
+    for entry in sorted {
+        match result.last_mut() {
+            Some(last) if last.0 == entry.0 => *last = entry,
+            _ => result.push(entry),
+        }
+    }
+    result
+}
+
+pub struct FlatMapIterator<'a, K, V, R> {
+    entries: slice::Iter<'a, (K, V)>,
+    get_item_func: fn(&'a (K, V)) -> R,
+}
+
+pub type FlatMapKeyValueIterator<'a, K, V> = FlatMapIterator<'a, K, V, (&'a K, &'a V)>;
+
+pub type FlatMapKeyIterator<'a, K, V> = FlatMapIterator<'a, K, V, &'a K>;
+
+pub type FlatMapValueIterator<'a, K, V> = FlatMapIterator<'a, K, V, &'a V>;
+
+impl<'a, K, V, R> FlatMapIterator<'a, K, V, R> {
+    fn new(entries: &'a [(K, V)], get_item_func: fn(&'a (K, V)) -> R) -> Self {
+        Self {
+            entries: entries.iter(),
+            get_item_func,
+        }
+    }
+}
+
+impl<K, V, R> Iterator for FlatMapIterator<'_, K, V, R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(self.get_item_func)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V, R> DoubleEndedIterator for FlatMapIterator<'_, K, V, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(self.get_item_func)
+    }
+}
+
+impl<K, V, R> ExactSizeIterator for FlatMapIterator<'_, K, V, R> {}
+
+fn get_key_value<K, V>((key, value): &(K, V)) -> (&K, &V) {
+    (key, value)
+}
+
+fn get_key<K, V>((key, _): &(K, V)) -> &K {
+    key
+}
+
+fn get_value<K, V>((_, value): &(K, V)) -> &V {
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::AvlTree;
+    use quickcheck_macros::quickcheck;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut map = FlatMap::new();
+        assert_eq!(map.insert(3, 'c'), None);
+        assert_eq!(map.insert(1, 'a'), None);
+        assert_eq!(map.insert(2, 'b'), None);
+        assert_eq!(map.insert(1, 'd'), Some('a'));
+
+        assert_eq!(map.get(&1), Some(&'d'));
+        assert_eq!(map.get(&4), None);
+        assert!(map.contains(&2));
+        assert_eq!(map.size(), 3);
+        assert_eq!(map.min(), Some((&1, &'d')));
+        assert_eq!(map.max(), Some((&3, &'c')));
+
+        *map.get_mut(&2).unwrap() = 'e';
+        assert_eq!(map.remove(&2), Some('e'));
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec!['d', 'c']);
+    }
+
+    #[test]
+    fn test_range() {
+        let map: FlatMap<i32, i32> = (0..20).map(|i| (i * 5, i)).collect();
+        let keys = |iter: FlatMapKeyValueIterator<'_, i32, i32>| {
+            iter.map(|(&key, _)| key).collect::<Vec<_>>()
+        };
+
+        assert_eq!(keys(map.range(10..25)), vec![10, 15, 20]);
+        assert_eq!(keys(map.range(11..=25)), vec![15, 20, 25]);
+        assert_eq!(keys(map.range(..10)), vec![0, 5]);
+        assert_eq!(keys(map.range(90..)), vec![90, 95]);
+        assert_eq!(
+            keys(map.range((Bound::Excluded(90), Bound::Unbounded))),
+            vec![95]
+        );
+        assert!(keys(map.range(30..30)).is_empty());
+        assert!(keys(map.range(200..)).is_empty());
+        assert_eq!(map.range(..).next_back(), Some((&95, &19)));
+    }
+
+    #[test]
+    fn test_extend_merges() {
+        let mut map: FlatMap<i32, &str> = [(1, "a"), (5, "b"), (9, "c")].into_iter().collect();
+        map.extend([(7, "x"), (5, "y"), (0, "z"), (7, "w")]);
+
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&0, &"z"), (&1, &"a"), (&5, &"y"), (&7, &"w"), (&9, &"c")]
+        );
+    }
+
+    #[quickcheck]
+    fn prop_matches_btree_map(
+        initial: Vec<(i16, u8)>,
+        inserts: Vec<(i16, u8)>,
+        removals: Vec<i16>,
+    ) -> bool {
+        let mut map: FlatMap<i16, u8> = initial.iter().copied().collect();
+        let mut expected: BTreeMap<i16, u8> = initial.into_iter().collect();
+
+        map.extend(inserts.iter().copied());
+        expected.extend(inserts);
+        for key in removals {
+            assert_eq!(map.remove(&key), expected.remove(&key));
+        }
+
+        map.size() == expected.len() && map.iter().eq(expected.iter())
+    }
+
+    #[test]
+    fn test_matches_avl_tree() {
+        let entries: Vec<(u32, u32)> = (0..1_000).map(|i| (i * 7_919 % 1_000, i)).collect();
+
+        let map: FlatMap<u32, u32> = entries.iter().copied().collect();
+        let tree: AvlTree<u32, u32> = entries.into_iter().collect();
+
+        assert!(map.iter().eq(tree.iter()));
+        assert_eq!(map.min(), tree.min());
+        assert_eq!(map.max(), tree.max());
+        assert_eq!(map.size(), tree.size());
+    }
+
+    #[test]
+    fn test_owned_iteration() {
+        let map: FlatMap<u8, char> = [(2, 'b'), (1, 'a')].into_iter().collect();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            vec![(1, 'a'), (2, 'b')]
+        );
+    }
+}
@@ -1,3 +1,7 @@
 mod avl;
+pub(crate) mod flat_map;
+mod rb;
 
 pub use avl::AvlTree;
+pub use flat_map::FlatMap;
+pub use rb::RbTree;
@@ -0,0 +1,166 @@
+use crate::tree::rb::RbTree;
+use crate::tree::rb::node::{NIL, RBTreeNode, leftmost, predecessor, rightmost, successor};
+
+pub fn get_key_value<K, V>(node: &RBTreeNode<K, V>) -> (&K, &V) {
+    (&node.key, &node.value)
+}
+
+pub fn get_key<K, V>(node: &RBTreeNode<K, V>) -> &K {
+    &node.key
+}
+
+pub fn get_value<K, V>(node: &RBTreeNode<K, V>) -> &V {
+    &node.value
+}
+
+#[cfg(test)]
+pub fn get_node<K, V>(node: &RBTreeNode<K, V>) -> &RBTreeNode<K, V> {
+    node
+}
+
+pub struct RbTreeIterator<'a, K, V, I> {
+    nodes: &'a [RBTreeNode<K, V>],
+    next_node: usize,
+    next_back_node: usize,
+    get_item_func: fn(&'a RBTreeNode<K, V>) -> I,
+}
+
+pub type RbTreeKeyValueIterator<'a, K, V> = RbTreeIterator<'a, K, V, (&'a K, &'a V)>;
+
+pub type RbTreeKeyIterator<'a, K, V> = RbTreeIterator<'a, K, V, &'a K>;
+
+pub type RbTreeValueIterator<'a, K, V> = RbTreeIterator<'a, K, V, &'a V>;
+
+#[cfg(test)]
+pub type RbTreeNodeIterator<'a, K, V> = RbTreeIterator<'a, K, V, &'a RBTreeNode<K, V>>;
+
+impl<'a, K, V, R> RbTreeIterator<'a, K, V, R> {
+    pub fn new(tree: &'a RbTree<K, V>, get_item_func: fn(&'a RBTreeNode<K, V>) -> R) -> Self {
+        let (next_node, next_back_node) = match tree.root {
+            NIL => (NIL, NIL),
+            root => (leftmost(&tree.nodes, root), rightmost(&tree.nodes, root)),
+        };
+
+        Self {
+            nodes: &tree.nodes,
+            next_node,
+            next_back_node,
+            get_item_func,
+        }
+    }
+
+    /// Returns the item at `index`, taken from one end, and empties the
+    /// iterator if that was the last node between the two ends
+    fn take(&mut self, index: usize) -> R {
+        if self.next_node == self.next_back_node {
+            self.next_node = NIL;
+            self.next_back_node = NIL;
+        }
+
+        (self.get_item_func)(&self.nodes[index])
+    }
+}
+
+impl<K, V, R> Iterator for RbTreeIterator<'_, K, V, R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next_node;
+        if current == NIL {
+            return None;
+        }
+
+        let item = self.take(current);
+        if self.next_node != NIL {
+            self.next_node = successor(self.nodes, current);
+        }
+
+        Some(item)
+    }
+}
+
+impl<K, V, R> DoubleEndedIterator for RbTreeIterator<'_, K, V, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let current = self.next_back_node;
+        if current == NIL {
+            return None;
+        }
+
+        let item = self.take(current);
+        if self.next_back_node != NIL {
+            self.next_back_node = predecessor(self.nodes, current);
+        }
+
+        Some(item)
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for RbTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = Self::new();
+
+        for i in iter {
+            tree.insert(i.0, i.1);
+        }
+
+        tree
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a RbTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = RbTreeKeyValueIterator<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RbTreeKeyValueIterator::new(self, get_key_value)
+    }
+}
+
+pub struct RbTreeOwnedIterator<K, V> {
+    entries: std::vec::IntoIter<(K, V)>,
+}
+
+impl<K, V> RbTreeOwnedIterator<K, V> {
+    fn new(tree: RbTree<K, V>) -> Self {
+        let mut order = Vec::with_capacity(tree.size());
+        let mut current = match tree.root {
+            NIL => NIL,
+            root => leftmost(&tree.nodes, root),
+        };
+        while current != NIL {
+            order.push(current);
+            current = successor(&tree.nodes, current);
+        }
+
+        let mut slots: Vec<_> = tree
+            .nodes
+            .into_iter()
+            .map(|node| Some((node.key, node.value)))
+            .collect();
+        let entries: Vec<_> = order
+            .into_iter()
+            .map(|index| slots[index].take().unwrap())
+            .collect();
+
+        Self {
+            entries: entries.into_iter(),
+        }
+    }
+}
+
+impl<K, V> Iterator for RbTreeOwnedIterator<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+impl<K, V> IntoIterator for RbTree<K, V> {
+    type Item = (K, V);
+    type IntoIter = RbTreeOwnedIterator<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RbTreeOwnedIterator::new(self)
+    }
+}
@@ -0,0 +1,406 @@
+mod iter;
+mod node;
+
+#[cfg(test)]
+mod tests;
+
+use crate::tree::rb::iter::{
+    RbTreeKeyIterator, RbTreeKeyValueIterator, RbTreeValueIterator, get_key, get_value,
+};
+use crate::tree::rb::node::{Color, NIL, RBTreeNode, leftmost, rightmost};
+use std::borrow::Borrow;
+
+#[cfg(test)]
+use crate::tree::rb::iter::RbTreeNodeIterator;
+#[cfg(test)]
+use crate::tree::rb::iter::get_node;
+
+/// Red-black tree with the same interface as [`AvlTree`](crate::tree::AvlTree).
+///
+/// Nodes live in a `Vec` and link to each other by index. Removing a node
+/// moves the last one into its slot, so the storage stays compact.
+pub struct RbTree<K, V> {
+    nodes: Vec<RBTreeNode<K, V>>,
+    root: usize,
+}
+
+impl<K: Ord, V> RbTree<K, V> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            root: NIL,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut parent = NIL;
+        let mut current = self.root;
+        let mut is_left = false;
+
+        while current != NIL {
+            parent = current;
+            let node = &mut self.nodes[current];
+            match key.cmp(&node.key) {
+                std::cmp::Ordering::Less => {
+                    current = node.left;
+                    is_left = true;
+                }
+                std::cmp::Ordering::Greater => {
+                    current = node.right;
+                    is_left = false;
+                }
+                std::cmp::Ordering::Equal => {
+                    return Some(std::mem::replace(&mut node.value, value));
+                }
+            }
+        }
+
+        let inserted = self.nodes.len();
+        self.nodes.push(RBTreeNode::new(key, value, parent));
+
+        if parent == NIL {
+            self.root = inserted;
+        } else if is_left {
+            self.nodes[parent].left = inserted;
+        } else {
+            self.nodes[parent].right = inserted;
+        }
+
+        self.fix_after_insert(inserted);
+
+        None
+    }
+
+    pub fn get<Q: Borrow<K>>(&self, key: &Q) -> Option<&V> {
+        match self.find(key) {
+            NIL => None,
+            index => Some(&self.nodes[index].value),
+        }
+    }
+
+    pub fn contains<Q: Borrow<K>>(&self, key: &Q) -> bool {
+        self.find(key) != NIL
+    }
+
+    pub fn remove<Q: Borrow<K>>(&mut self, key: &Q) -> Option<V> {
+        match self.find(key) {
+            NIL => None,
+            index => Some(self.remove_node(index).value),
+        }
+    }
+
+    pub fn min(&self) -> Option<(&K, &V)> {
+        if self.root == NIL {
+            return None;
+        }
+
+        let node = &self.nodes[leftmost(&self.nodes, self.root)];
+        Some((&node.key, &node.value))
+    }
+
+    pub fn max(&self) -> Option<(&K, &V)> {
+        if self.root == NIL {
+            return None;
+        }
+
+        let node = &self.nodes[rightmost(&self.nodes, self.root)];
+        Some((&node.key, &node.value))
+    }
+
+    pub fn iter(&self) -> RbTreeKeyValueIterator<'_, K, V> {
+        self.into_iter()
+    }
+
+    pub fn keys(&self) -> RbTreeKeyIterator<'_, K, V> {
+        RbTreeKeyIterator::new(self, get_key)
+    }
+
+    pub fn values(&self) -> RbTreeValueIterator<'_, K, V> {
+        RbTreeValueIterator::new(self, get_value)
+    }
+
+    fn find<Q: Borrow<K>>(&self, key: &Q) -> usize {
+        let mut current = self.root;
+
+        while current != NIL {
+            let node = &self.nodes[current];
+            match key.borrow().cmp(&node.key) {
+                std::cmp::Ordering::Less => current = node.left,
+                std::cmp::Ordering::Greater => current = node.right,
+                std::cmp::Ordering::Equal => break,
+            }
+        }
+
+        current
+    }
+}
+
+impl<K, V> RbTree<K, V> {
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Missing children count as black
+    fn color(&self, index: usize) -> Color {
+        match index {
+            NIL => Color::Black,
+            index => self.nodes[index].color,
+        }
+    }
+
+    fn set_color(&mut self, index: usize, color: Color) {
+        if index != NIL {
+            self.nodes[index].color = color;
+        }
+    }
+
+    fn fix_after_insert(&mut self, mut node: usize) {
+        while self.color(self.nodes[node].parent) == Color::Red {
+            // A red parent is never the root, so the grandparent exists
+            let parent = self.nodes[node].parent;
+            let grandparent = self.nodes[parent].parent;
+
+            if parent == self.nodes[grandparent].left {
+                let uncle = self.nodes[grandparent].right;
+                if self.color(uncle) == Color::Red {
+                    self.set_color(parent, Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(grandparent, Color::Red);
+                    node = grandparent;
+                    continue;
+                }
+
+                if node == self.nodes[parent].right {
+                    node = parent;
+                    self.rotate_left(node);
+                }
+                let parent = self.nodes[node].parent;
+                self.set_color(parent, Color::Black);
+                self.set_color(grandparent, Color::Red);
+                self.rotate_right(grandparent);
+            } else {
+                let uncle = self.nodes[grandparent].left;
+                if self.color(uncle) == Color::Red {
+                    self.set_color(parent, Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(grandparent, Color::Red);
+                    node = grandparent;
+                    continue;
+                }
+
+                if node == self.nodes[parent].left {
+                    node = parent;
+                    self.rotate_right(node);
+                }
+                let parent = self.nodes[node].parent;
+                self.set_color(parent, Color::Black);
+                self.set_color(grandparent, Color::Red);
+                self.rotate_left(grandparent);
+            }
+        }
+
+        self.set_color(self.root, Color::Black);
+    }
+
+    /// Unlinks the node at `index`, rebalances and frees its slot
+    fn remove_node(&mut self, index: usize) -> RBTreeNode<K, V> {
+        let RBTreeNode {
+            left, right, color, ..
+        } = self.nodes[index];
+
+        let (child, child_parent, removed_color) = if left == NIL {
+            let parent = self.nodes[index].parent;
+            self.transplant(index, right);
+            (right, parent, color)
+        } else if right == NIL {
+            let parent = self.nodes[index].parent;
+            self.transplant(index, left);
+            (left, parent, color)
+        } else {
+            // The successor takes the removed node's place and color
+            let successor = leftmost(&self.nodes, right);
+            let successor_color = self.nodes[successor].color;
+            let child = self.nodes[successor].right;
+
+            let child_parent = if self.nodes[successor].parent == index {
+                successor
+            } else {
+                let parent = self.nodes[successor].parent;
+                self.transplant(successor, child);
+                self.nodes[successor].right = right;
+                self.nodes[right].parent = successor;
+                parent
+            };
+
+            self.transplant(index, successor);
+            self.nodes[successor].left = left;
+            self.nodes[left].parent = successor;
+            self.nodes[successor].color = color;
+
+            (child, child_parent, successor_color)
+        };
+
+        if removed_color == Color::Black {
+            self.fix_after_remove(child, child_parent);
+        }
+
+        self.free(index)
+    }
+
+    /// Restores the black height after removing a black node from above
+    /// `node`, whose parent is passed separately since `node` may be [`NIL`]
+    fn fix_after_remove(&mut self, mut node: usize, mut parent: usize) {
+        while node != self.root && self.color(node) == Color::Black {
+            if node == self.nodes[parent].left {
+                let mut sibling = self.nodes[parent].right;
+                if self.color(sibling) == Color::Red {
+                    self.set_color(sibling, Color::Black);
+                    self.set_color(parent, Color::Red);
+                    self.rotate_left(parent);
+                    sibling = self.nodes[parent].right;
+                }
+
+                let (near, far) = (self.nodes[sibling].left, self.nodes[sibling].right);
+                if self.color(near) == Color::Black && self.color(far) == Color::Black {
+                    self.set_color(sibling, Color::Red);
+                    node = parent;
+                    parent = self.nodes[node].parent;
+                    continue;
+                }
+
+                if self.color(far) == Color::Black {
+                    self.set_color(near, Color::Black);
+                    self.set_color(sibling, Color::Red);
+                    self.rotate_right(sibling);
+                    sibling = self.nodes[parent].right;
+                }
+                self.nodes[sibling].color = self.nodes[parent].color;
+                self.set_color(parent, Color::Black);
+                self.set_color(self.nodes[sibling].right, Color::Black);
+                self.rotate_left(parent);
+            } else {
+                let mut sibling = self.nodes[parent].left;
+                if self.color(sibling) == Color::Red {
+                    self.set_color(sibling, Color::Black);
+                    self.set_color(parent, Color::Red);
+                    self.rotate_right(parent);
+                    sibling = self.nodes[parent].left;
+                }
+
+                let (near, far) = (self.nodes[sibling].right, self.nodes[sibling].left);
+                if self.color(near) == Color::Black && self.color(far) == Color::Black {
+                    self.set_color(sibling, Color::Red);
+                    node = parent;
+                    parent = self.nodes[node].parent;
+                    continue;
+                }
+
+                if self.color(far) == Color::Black {
+                    self.set_color(near, Color::Black);
+                    self.set_color(sibling, Color::Red);
+                    self.rotate_left(sibling);
+                    sibling = self.nodes[parent].left;
+                }
+                self.nodes[sibling].color = self.nodes[parent].color;
+                self.set_color(parent, Color::Black);
+                self.set_color(self.nodes[sibling].left, Color::Black);
+                self.rotate_right(parent);
+            }
+
+            node = self.root;
+        }
+
+        self.set_color(node, Color::Black);
+    }
+
+    fn rotate_left(&mut self, node: usize) {
+        let pivot = self.nodes[node].right;
+        let inner = self.nodes[pivot].left;
+
+        self.nodes[node].right = inner;
+        if inner != NIL {
+            self.nodes[inner].parent = node;
+        }
+
+        self.replace_child(self.nodes[node].parent, node, pivot);
+        self.nodes[pivot].left = node;
+        self.nodes[node].parent = pivot;
+    }
+
+    fn rotate_right(&mut self, node: usize) {
+        let pivot = self.nodes[node].left;
+        let inner = self.nodes[pivot].right;
+
+        self.nodes[node].left = inner;
+        if inner != NIL {
+            self.nodes[inner].parent = node;
+        }
+
+        self.replace_child(self.nodes[node].parent, node, pivot);
+        self.nodes[pivot].right = node;
+        self.nodes[node].parent = pivot;
+    }
+
+    /// Puts the subtree at `replacement` where the one at `node` was
+    fn transplant(&mut self, node: usize, replacement: usize) {
+        self.replace_child(self.nodes[node].parent, node, replacement);
+    }
+
+    /// Points `parent` (or the root if it is [`NIL`]) at `new_child`
+    /// instead of `old_child`, and sets `new_child`'s parent
+    fn replace_child(&mut self, parent: usize, old_child: usize, new_child: usize) {
+        if parent == NIL {
+            self.root = new_child;
+        } else if self.nodes[parent].left == old_child {
+            self.nodes[parent].left = new_child;
+        } else {
+            self.nodes[parent].right = new_child;
+        }
+
+        if new_child != NIL {
+            self.nodes[new_child].parent = parent;
+        }
+    }
+
+    /// Removes the already unlinked node at `index` from storage, moving the
+    /// last node into its slot
+    fn free(&mut self, index: usize) -> RBTreeNode<K, V> {
+        let removed = self.nodes.swap_remove(index);
+        if index == self.nodes.len() {
+            return removed;
+        }
+
+        let moved = self.nodes.len();
+        let RBTreeNode {
+            left,
+            right,
+            parent,
+            ..
+        } = self.nodes[index];
+
+        self.replace_child(parent, moved, index);
+        if left != NIL {
+            self.nodes[left].parent = index;
+        }
+        if right != NIL {
+            self.nodes[right].parent = index;
+        }
+
+        removed
+    }
+
+    #[cfg(test)]
+    fn nodes(&self) -> RbTreeNodeIterator<'_, K, V> {
+        RbTreeNodeIterator::new(self, get_node)
+    }
+}
+
+impl<K: Ord, V> Default for RbTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,78 @@
+/// Index of a missing child or parent
+pub const NIL: usize = usize::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+pub struct RBTreeNode<K, V> {
+    pub key: K,
+    pub value: V,
+    pub left: usize,
+    pub right: usize,
+    pub parent: usize,
+    pub color: Color,
+}
+
+impl<K, V> RBTreeNode<K, V> {
+    pub fn new(key: K, value: V, parent: usize) -> Self {
+        Self {
+            key,
+            value,
+            left: NIL,
+            right: NIL,
+            parent,
+            color: Color::Red,
+        }
+    }
+}
+
+/// Leftmost node of the subtree at `index`, which must not be [`NIL`]
+pub fn leftmost<K, V>(nodes: &[RBTreeNode<K, V>], mut index: usize) -> usize {
+    while nodes[index].left != NIL {
+        index = nodes[index].left;
+    }
+    index
+}
+
+/// Rightmost node of the subtree at `index`, which must not be [`NIL`]
+pub fn rightmost<K, V>(nodes: &[RBTreeNode<K, V>], mut index: usize) -> usize {
+    while nodes[index].right != NIL {
+        index = nodes[index].right;
+    }
+    index
+}
+
+/// Next node in key order, [`NIL`] after the last one
+pub fn successor<K, V>(nodes: &[RBTreeNode<K, V>], index: usize) -> usize {
+    if nodes[index].right != NIL {
+        return leftmost(nodes, nodes[index].right);
+    }
+
+    let mut current = index;
+    let mut parent = nodes[index].parent;
+    // Climb while we're the right child of our parent
+    while parent != NIL && nodes[parent].right == current {
+        current = parent;
+        parent = nodes[parent].parent;
+    }
+    parent
+}
+
+/// Previous node in key order, [`NIL`] before the first one
+pub fn predecessor<K, V>(nodes: &[RBTreeNode<K, V>], index: usize) -> usize {
+    if nodes[index].left != NIL {
+        return rightmost(nodes, nodes[index].left);
+    }
+
+    let mut current = index;
+    let mut parent = nodes[index].parent;
+    // Climb while we're the left child of our parent
+    while parent != NIL && nodes[parent].left == current {
+        current = parent;
+        parent = nodes[parent].parent;
+    }
+    parent
+}
@@ -0,0 +1,63 @@
+use crate::tree::RbTree;
+use crate::tree::rb::node::{Color, NIL};
+
+mod unit;
+
+impl<K: Ord, V> RbTree<K, V> {
+    pub fn check_parent_references(&self) -> bool {
+        if self.root == NIL {
+            return self.nodes.is_empty();
+        }
+
+        self.nodes[self.root].parent == NIL && self.check_node_parent_references(self.root)
+    }
+
+    fn check_node_parent_references(&self, node: usize) -> bool {
+        [self.nodes[node].left, self.nodes[node].right]
+            .into_iter()
+            .filter(|&child| child != NIL)
+            .all(|child| {
+                self.nodes[child].parent == node && self.check_node_parent_references(child)
+            })
+    }
+
+    /// Number of black nodes on every path from `node` down to a missing
+    /// child, or `None` if the paths disagree or a red node has a red child
+    pub fn black_height(&self, node: usize) -> Option<usize> {
+        if node == NIL {
+            return Some(1);
+        }
+
+        let (left, right, color) = {
+            let node = &self.nodes[node];
+            (node.left, node.right, node.color)
+        };
+        if color == Color::Red
+            && (self.color(left) == Color::Red || self.color(right) == Color::Red)
+        {
+            return None;
+        }
+
+        let left_height = self.black_height(left)?;
+        let right_height = self.black_height(right)?;
+        (left_height == right_height).then_some(left_height + (color == Color::Black) as usize)
+    }
+
+    pub fn is_red_black(&self) -> bool {
+        self.color(self.root) == Color::Black && self.black_height(self.root).is_some()
+    }
+
+    pub fn height(&self) -> usize {
+        self.node_height(self.root)
+    }
+
+    fn node_height(&self, node: usize) -> usize {
+        if node == NIL {
+            return 0;
+        }
+
+        1 + self
+            .node_height(self.nodes[node].left)
+            .max(self.node_height(self.nodes[node].right))
+    }
+}
@@ -0,0 +1,158 @@
+use crate::tree::rb::RbTree;
+use quickcheck_macros::quickcheck;
+use std::collections::BTreeMap;
+
+#[test]
+fn test_get_insert_simple() {
+    let mut tree = RbTree::new();
+
+    assert_eq!(tree.get(&1), None);
+
+    assert_eq!(tree.insert(1, 'a'), None);
+    assert_eq!(tree.get(&1), Some(&'a'));
+    assert_eq!(tree.get(&2), None);
+
+    assert_eq!(tree.insert(1, 'b'), Some('a'));
+    assert_eq!(tree.get(&1), Some(&'b'));
+
+    assert_eq!(tree.insert(2, 'b'), None);
+    assert_eq!(tree.get(&2), Some(&'b'));
+    assert!(tree.contains(&1));
+    assert!(!tree.contains(&3));
+    assert_eq!(tree.size(), 2);
+}
+
+#[test]
+fn test_remove_operation() {
+    let mut tree: RbTree<_, _> = (0..100).map(|i| (i, i * 10)).collect();
+
+    for i in (0..100).step_by(3) {
+        assert_eq!(tree.remove(&i), Some(i * 10));
+        assert_eq!(tree.remove(&i), None);
+        assert!(tree.is_red_black());
+        assert!(tree.check_parent_references());
+    }
+
+    assert_eq!(tree.size(), 66);
+    assert!(tree.keys().all(|key| key % 3 != 0));
+
+    for i in 0..100 {
+        tree.remove(&i);
+    }
+    assert!(tree.is_empty());
+    assert_eq!(tree.min(), None);
+    assert_eq!(tree.iter().next(), None);
+}
+
+#[test]
+fn test_min_max() {
+    let tree: RbTree<_, _> = [(5, 'e'), (1, 'a'), (9, 'i'), (3, 'c')]
+        .into_iter()
+        .collect();
+
+    assert_eq!(tree.min(), Some((&1, &'a')));
+    assert_eq!(tree.max(), Some((&9, &'i')));
+    assert_eq!(tree.keys().copied().collect::<Vec<_>>(), vec![1, 3, 5, 9]);
+    assert_eq!(
+        tree.values().copied().collect::<Vec<_>>(),
+        vec!['a', 'c', 'e', 'i']
+    );
+}
+
+#[test]
+fn test_sequential_inserts_stay_shallow() {
+    let tree: RbTree<_, _> = (0..1_000).map(|i| (i, ())).collect();
+
+    assert!(tree.is_red_black());
+    // A red-black tree with n nodes is at most 2 * log2(n + 1) high
+    assert!(tree.height() <= 2 * 10);
+}
+
+#[quickcheck]
+fn test_tree_invariants(values: Vec<(i32, char)>) -> bool {
+    let tree = values.into_iter().collect::<RbTree<_, _>>();
+
+    is_bst(&tree) && tree.is_red_black() && tree.check_parent_references()
+}
+
+#[quickcheck]
+fn test_invariants_after_removals(values: Vec<(i8, char)>, removals: Vec<i8>) -> bool {
+    let mut tree = values.into_iter().collect::<RbTree<_, _>>();
+
+    removals.iter().all(|key| {
+        tree.remove(key);
+        is_bst(&tree) && tree.is_red_black() && tree.check_parent_references()
+    })
+}
+
+#[quickcheck]
+fn test_height_bound(values: Vec<(i32, char)>) -> bool {
+    let tree = values.into_iter().collect::<RbTree<_, _>>();
+
+    tree.height() as f64 <= 2.0 * ((tree.size() + 1) as f64).log2()
+}
+
+#[quickcheck]
+fn test_iterator(values: Vec<(i32, char)>) -> bool {
+    let rb_tree = values.iter().cloned().collect::<RbTree<_, _>>();
+    let std_btree = values.iter().cloned().collect::<BTreeMap<_, _>>();
+
+    rb_tree.iter().eq(std_btree.iter())
+}
+
+#[quickcheck]
+fn test_double_ended_iterator(values: Vec<(i32, char)>, from_back: Vec<bool>) -> bool {
+    let rb_tree = values.iter().cloned().collect::<RbTree<_, _>>();
+    let std_btree = values.iter().cloned().collect::<BTreeMap<_, _>>();
+
+    if !rb_tree.keys().rev().eq(std_btree.keys().rev()) {
+        return false;
+    }
+
+    // Alternating ends must meet in the middle without repeating an item
+    let mut rb_iter = rb_tree.iter();
+    let mut std_iter = std_btree.iter();
+    from_back
+        .into_iter()
+        .chain(std::iter::repeat_n(false, values.len() + 1))
+        .all(|back| {
+            if back {
+                rb_iter.next_back() == std_iter.next_back()
+            } else {
+                rb_iter.next() == std_iter.next()
+            }
+        })
+}
+
+#[quickcheck]
+fn test_into_owned_iterator(values: Vec<(i32, char)>) -> bool {
+    let rb_tree = values.iter().cloned().collect::<RbTree<_, _>>();
+    let std_btree = values.iter().cloned().collect::<BTreeMap<_, _>>();
+
+    rb_tree.into_iter().eq(std_btree)
+}
+
+#[quickcheck]
+fn test_matches_btree_map(operations: Vec<(bool, u8, u16)>) -> bool {
+    let mut tree = RbTree::new();
+    let mut expected = BTreeMap::new();
+
+    for (insert, key, value) in operations {
+        let matches = if insert {
+            tree.insert(key, value) == expected.insert(key, value)
+        } else {
+            tree.remove(&key) == expected.remove(&key)
+        };
+        if !matches || tree.size() != expected.len() {
+            return false;
+        }
+    }
+
+    tree.iter().eq(expected.iter()) && tree.is_red_black()
+}
+
+fn is_bst<K: Ord, V>(tree: &RbTree<K, V>) -> bool {
+    tree.nodes()
+        .zip(tree.nodes().skip(1))
+        .all(|(previous, next)| previous.key < next.key)
+}